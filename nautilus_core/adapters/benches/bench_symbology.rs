@@ -0,0 +1,117 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use databento::dbn::{
+    self,
+    decode::{dbn::Decoder, DbnMetadata, DecodeStream},
+};
+use fallible_streaming_iterator::FallibleStreamingIterator;
+use indexmap::IndexMap;
+use nautilus_adapters::databento::{
+    symbology::{decode_nautilus_instrument_id, decode_nautilus_instrument_ids, SymbologyResolver},
+    types::PublisherId,
+};
+use nautilus_model::identifiers::Venue;
+
+const TEST_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/databento/test_data");
+
+/// The number of times the MBO test data records are repeated for a replay-like stream.
+const REPETITIONS: usize = 10_000;
+
+/// Loads the MBO test data records along with their metadata.
+fn load_mbo_records() -> (dbn::Metadata, Vec<dbn::MboMsg>) {
+    let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.mbo.dbn.zst"));
+    let decoder = Decoder::from_zstd_file(path).unwrap();
+    let metadata = decoder.metadata().clone();
+    let mut dbn_stream = decoder.decode_stream::<dbn::MboMsg>();
+
+    let mut records = Vec::new();
+    while let Some(msg) = dbn_stream.next().unwrap() {
+        records.push(msg.clone());
+    }
+
+    (metadata, records)
+}
+
+fn symbology_bench(c: &mut Criterion) {
+    let (metadata, records) = load_mbo_records();
+    let publisher_venue_map: IndexMap<PublisherId, Venue> = IndexMap::from([(
+        dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+        Venue::from("GLBX"),
+    )]);
+
+    let mut group = c.benchmark_group("decode_nautilus_instrument_id");
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for msg in &records {
+                let record = dbn::RecordRef::from(msg);
+                decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map).unwrap();
+            }
+        });
+    });
+
+    group.bench_function("resolver", |b| {
+        b.iter(|| {
+            let resolver = SymbologyResolver::new(metadata.clone(), &publisher_venue_map);
+            for msg in &records {
+                resolver.resolve(&dbn::RecordRef::from(msg)).unwrap();
+            }
+        });
+    });
+
+    let record_refs: Vec<dbn::RecordRef> = records.iter().map(dbn::RecordRef::from).collect();
+
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            decode_nautilus_instrument_ids(&record_refs, &metadata, &publisher_venue_map).unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+fn resolver_bench(c: &mut Criterion) {
+    let (metadata, records) = load_mbo_records();
+    let publisher_venue_map: IndexMap<PublisherId, Venue> = IndexMap::from([(
+        dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+        Venue::from("GLBX"),
+    )]);
+    let repeated: Vec<&dbn::MboMsg> = records
+        .iter()
+        .cycle()
+        .take(records.len() * REPETITIONS)
+        .collect();
+
+    let mut group = c.benchmark_group("repetitive_mbo");
+    group.throughput(Throughput::Elements(repeated.len() as u64));
+
+    group.bench_function("resolver", |b| {
+        b.iter(|| {
+            let resolver = SymbologyResolver::new(metadata.clone(), &publisher_venue_map);
+            for msg in &repeated {
+                resolver.resolve(&dbn::RecordRef::from(*msg)).unwrap();
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, symbology_bench, resolver_bench);
+criterion_main!(benches);
@@ -13,7 +13,7 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
-use std::{env, fs, path::PathBuf};
+use std::{collections::HashMap, env, fs, path::PathBuf};
 
 use databento::dbn;
 use dbn::{
@@ -38,7 +38,7 @@ use super::{
         decode_imbalance_msg, decode_instrument_def_msg_v1, decode_record, decode_statistics_msg,
         decode_status_msg, raw_ptr_to_ustr,
     },
-    symbology::decode_nautilus_instrument_id,
+    symbology::{build_glbx_exchange_map, DuplicateSymbolPolicy, SymbologyResolver},
     types::{DatabentoImbalance, DatabentoPublisher, DatabentoStatistics, Dataset, PublisherId},
 };
 
@@ -72,6 +72,7 @@ pub struct DatabentoDataLoader {
     publishers_map: IndexMap<PublisherId, DatabentoPublisher>,
     venue_dataset_map: IndexMap<Venue, Dataset>,
     publisher_venue_map: IndexMap<PublisherId, Venue>,
+    glbx_exchange_map: HashMap<Symbol, Venue>,
 }
 
 impl DatabentoDataLoader {
@@ -81,6 +82,7 @@ impl DatabentoDataLoader {
             publishers_map: IndexMap::new(),
             venue_dataset_map: IndexMap::new(),
             publisher_venue_map: IndexMap::new(),
+            glbx_exchange_map: HashMap::new(),
         };
 
         // Load publishers
@@ -135,6 +137,15 @@ impl DatabentoDataLoader {
         Ok(())
     }
 
+    /// Load the Globex exchange map from the definitions file at the given `filepath`, so
+    /// Globex records resolve to the exchange venue of their symbol (e.g. `XCME`) rather than
+    /// the `GLBX` publisher venue.
+    pub fn load_glbx_exchange_map(&mut self, filepath: PathBuf) -> anyhow::Result<()> {
+        self.glbx_exchange_map =
+            build_glbx_exchange_map(&filepath, DuplicateSymbolPolicy::default())?;
+        Ok(())
+    }
+
     /// Return the internal Databento publishers currently held by the loader.
     #[must_use]
     pub const fn get_publishers(&self) -> &IndexMap<u16, DatabentoPublisher> {
@@ -208,7 +219,9 @@ impl DatabentoDataLoader {
         T: dbn::Record + dbn::HasRType + 'static,
     {
         let decoder = Decoder::from_zstd_file(filepath)?;
-        let metadata = decoder.metadata().clone();
+        let resolver =
+            SymbologyResolver::new(decoder.metadata().clone(), &self.publisher_venue_map)
+                .with_glbx_exchange_map(&self.glbx_exchange_map);
         let mut dbn_stream = decoder.decode_stream::<T>();
 
         let price_precision = Currency::USD().precision; // Hard coded for now
@@ -221,13 +234,8 @@ impl DatabentoDataLoader {
                 Some(rec) => {
                     let record = dbn::RecordRef::from(rec);
                     let instrument_id = match &instrument_id {
-                        Some(id) => *id, // Copy
-                        None => decode_nautilus_instrument_id(
-                            &record,
-                            &metadata,
-                            &self.publisher_venue_map,
-                        )
-                        .unwrap(), // TODO: Panic on error for now
+                        Some(id) => *id,                            // Copy
+                        None => resolver.resolve(&record).unwrap(), // TODO: Panic on error for now
                     };
 
                     match decode_record(
@@ -400,7 +408,9 @@ impl DatabentoDataLoader {
         T: dbn::Record + dbn::HasRType + 'static,
     {
         let decoder = Decoder::from_zstd_file(filepath)?;
-        let metadata = decoder.metadata().clone();
+        let resolver =
+            SymbologyResolver::new(decoder.metadata().clone(), &self.publisher_venue_map)
+                .with_glbx_exchange_map(&self.glbx_exchange_map);
         let mut dbn_stream = decoder.decode_stream::<T>();
 
         Ok(std::iter::from_fn(move || {
@@ -411,13 +421,8 @@ impl DatabentoDataLoader {
                 Some(rec) => {
                     let record = dbn::RecordRef::from(rec);
                     let instrument_id = match &instrument_id {
-                        Some(id) => *id, // Copy
-                        None => decode_nautilus_instrument_id(
-                            &record,
-                            &metadata,
-                            &self.publisher_venue_map,
-                        )
-                        .unwrap(), // TODO: Panic on error for now
+                        Some(id) => *id,                            // Copy
+                        None => resolver.resolve(&record).unwrap(), // TODO: Panic on error for now
                     };
 
                     let msg = record.get::<dbn::StatusMsg>().expect("Invalid `StatusMsg`");
@@ -440,7 +445,9 @@ impl DatabentoDataLoader {
         T: dbn::Record + dbn::HasRType + 'static,
     {
         let decoder = Decoder::from_zstd_file(filepath)?;
-        let metadata = decoder.metadata().clone();
+        let resolver =
+            SymbologyResolver::new(decoder.metadata().clone(), &self.publisher_venue_map)
+                .with_glbx_exchange_map(&self.glbx_exchange_map);
         let mut dbn_stream = decoder.decode_stream::<T>();
 
         let price_precision = Currency::USD().precision; // Hard coded for now
@@ -453,13 +460,8 @@ impl DatabentoDataLoader {
                 Some(rec) => {
                     let record = dbn::RecordRef::from(rec);
                     let instrument_id = match &instrument_id {
-                        Some(id) => *id, // Copy
-                        None => decode_nautilus_instrument_id(
-                            &record,
-                            &metadata,
-                            &self.publisher_venue_map,
-                        )
-                        .unwrap(), // TODO: Panic on error for now
+                        Some(id) => *id,                            // Copy
+                        None => resolver.resolve(&record).unwrap(), // TODO: Panic on error for now
                     };
 
                     let msg = record
@@ -489,7 +491,9 @@ impl DatabentoDataLoader {
         T: dbn::Record + dbn::HasRType + 'static,
     {
         let decoder = Decoder::from_zstd_file(filepath)?;
-        let metadata = decoder.metadata().clone();
+        let resolver =
+            SymbologyResolver::new(decoder.metadata().clone(), &self.publisher_venue_map)
+                .with_glbx_exchange_map(&self.glbx_exchange_map);
         let mut dbn_stream = decoder.decode_stream::<T>();
 
         let price_precision = Currency::USD().precision; // Hard coded for now
@@ -502,13 +506,8 @@ impl DatabentoDataLoader {
                 Some(rec) => {
                     let record = dbn::RecordRef::from(rec);
                     let instrument_id = match &instrument_id {
-                        Some(id) => *id, // Copy
-                        None => decode_nautilus_instrument_id(
-                            &record,
-                            &metadata,
-                            &self.publisher_venue_map,
-                        )
-                        .unwrap(), // TODO: Panic on error for now
+                        Some(id) => *id,                            // Copy
+                        None => resolver.resolve(&record).unwrap(), // TODO: Panic on error for now
                     };
 
                     let msg = record.get::<dbn::StatMsg>().expect("Invalid `StatMsg`");
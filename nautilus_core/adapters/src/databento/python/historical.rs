@@ -13,7 +13,7 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
-use std::{fs, num::NonZeroU64, path::PathBuf, str::FromStr, sync::Arc};
+use std::{collections::HashMap, fs, num::NonZeroU64, path::PathBuf, str::FromStr, sync::Arc};
 
 use databento::{
     dbn::{self, SType},
@@ -44,7 +44,10 @@ use crate::databento::{
         decode_imbalance_msg, decode_instrument_def_msg, decode_record, decode_statistics_msg,
         decode_status_msg, raw_ptr_to_ustr,
     },
-    symbology::{check_consistent_symbology, decode_nautilus_instrument_id, infer_symbology_type},
+    symbology::{
+        build_glbx_exchange_map, check_consistent_symbology, infer_symbology_type,
+        DuplicateSymbolPolicy, SymbologyResolver,
+    },
     types::{DatabentoImbalance, DatabentoPublisher, DatabentoStatistics, PublisherId},
 };
 
@@ -58,6 +61,7 @@ pub struct DatabentoHistoricalClient {
     clock: &'static AtomicTime,
     inner: Arc<Mutex<databento::HistoricalClient>>,
     publisher_venue_map: Arc<IndexMap<PublisherId, Venue>>,
+    glbx_exchange_map: Arc<HashMap<Symbol, Venue>>,
 }
 
 #[pymethods]
@@ -83,10 +87,20 @@ impl DatabentoHistoricalClient {
             clock: get_atomic_clock_realtime(),
             inner: Arc::new(Mutex::new(client)),
             publisher_venue_map: Arc::new(publisher_venue_map),
+            glbx_exchange_map: Arc::new(HashMap::new()),
             key,
         })
     }
 
+    #[pyo3(name = "load_glbx_exchange_map")]
+    fn py_load_glbx_exchange_map(&mut self, filepath: PathBuf) -> PyResult<()> {
+        let glbx_exchange_map =
+            build_glbx_exchange_map(&filepath, DuplicateSymbolPolicy::default())
+                .map_err(to_pyvalue_err)?;
+        self.glbx_exchange_map = Arc::new(glbx_exchange_map);
+        Ok(())
+    }
+
     #[pyo3(name = "get_dataset_range")]
     fn py_get_dataset_range<'py>(
         &self,
@@ -209,6 +223,7 @@ impl DatabentoHistoricalClient {
 
         let price_precision = Currency::USD().precision; // TODO: Hard coded for now
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -219,14 +234,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<QuoteTick> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::Mbp1Msg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let (data, _) = decode_record(
                     &record,
@@ -277,6 +291,7 @@ impl DatabentoHistoricalClient {
 
         let price_precision = Currency::USD().precision; // TODO: Hard coded for now
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -287,14 +302,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<TradeTick> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::TradeMsg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let (data, _) = decode_record(
                     &record,
@@ -354,6 +368,7 @@ impl DatabentoHistoricalClient {
 
         let price_precision = Currency::USD().precision; // TODO: Hard coded for now
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -364,14 +379,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<Bar> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::OhlcvMsg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let (data, _) = decode_record(
                     &record,
@@ -423,6 +437,7 @@ impl DatabentoHistoricalClient {
 
         let price_precision = Currency::USD().precision; // TODO: Hard coded for now
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -433,14 +448,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<DatabentoImbalance> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::ImbalanceMsg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let imbalance = decode_imbalance_msg(msg, instrument_id, price_precision, ts_init)
                     .map_err(to_pyvalue_err)?;
@@ -481,6 +495,7 @@ impl DatabentoHistoricalClient {
 
         let price_precision = Currency::USD().precision; // TODO: Hard coded for now
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -491,14 +506,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<DatabentoStatistics> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::StatMsg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let statistics =
                     decode_statistics_msg(msg, instrument_id, price_precision, ts_init)
@@ -539,6 +553,7 @@ impl DatabentoHistoricalClient {
             .build();
 
         let publisher_venue_map = self.publisher_venue_map.clone();
+        let glbx_exchange_map = self.glbx_exchange_map.clone();
         let ts_init = self.clock.get_time_ns();
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -549,14 +564,13 @@ impl DatabentoHistoricalClient {
                 .await
                 .map_err(to_pyvalue_err)?;
 
-            let metadata = decoder.metadata().clone();
+            let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+                .with_glbx_exchange_map(&glbx_exchange_map);
             let mut result: Vec<InstrumentStatus> = Vec::new();
 
             while let Ok(Some(msg)) = decoder.decode_record::<dbn::StatusMsg>().await {
                 let record = dbn::RecordRef::from(msg);
-                let instrument_id =
-                    decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map)
-                        .map_err(to_pyvalue_err)?;
+                let instrument_id = resolver.resolve(&record).map_err(to_pyvalue_err)?;
 
                 let status =
                     decode_status_msg(msg, instrument_id, ts_init).map_err(to_pyvalue_err)?;
@@ -51,6 +51,12 @@ impl DatabentoDataLoader {
             .map_err(to_pyvalue_err)
     }
 
+    #[pyo3(name = "load_glbx_exchange_map")]
+    fn py_load_glbx_exchange_map(&mut self, filepath: PathBuf) -> PyResult<()> {
+        self.load_glbx_exchange_map(filepath)
+            .map_err(to_pyvalue_err)
+    }
+
     #[must_use]
     #[pyo3(name = "get_publishers")]
     fn py_get_publishers(&self) -> HashMap<u16, DatabentoPublisher> {
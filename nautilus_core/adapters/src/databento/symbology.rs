@@ -13,122 +13,2706 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    ops::RangeInclusive,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
 use databento::dbn;
-use dbn::Record;
+use dbn::{
+    decode::{DbnMetadata, DecodeRecordRef, DynDecoder},
+    PitSymbolMap, Record,
+};
 use indexmap::IndexMap;
 use nautilus_core::correctness::check_slice_not_empty;
 use nautilus_model::identifiers::{InstrumentId, Symbol, Venue};
+use ustr::Ustr;
+
+use super::types::PublisherId;
+
+/// Represents errors that can occur when decoding the `InstrumentId` of a DBN record.
+#[derive(thiserror::Error, Debug)]
+pub enum SymbologyError {
+    /// The record type carries no instrument to resolve (see [`supported_schemas`]).
+    #[error("DBN message type is not currently supported")]
+    UnsupportedSchema,
+    /// The symbol map has no raw symbol for the `instrument_id` on the `date`.
+    #[error("No raw symbol found for {instrument_id} on {date}")]
+    MissingRawSymbol {
+        instrument_id: u32,
+        date: time::Date,
+    },
+    /// No venue is mapped to the publisher with the `publisher_id`.
+    #[error("`Venue` not found for `publisher_id` {publisher_id} (symbol {symbol})")]
+    MissingVenue {
+        publisher_id: PublisherId,
+        symbol: Symbol,
+    },
+    /// The record `timestamp` is undefined or outside the range of supported dates.
+    #[error("Cannot resolve symbol for {instrument_id}: {}", describe_timestamp(*.timestamp))]
+    TimestampOutOfRange { instrument_id: u32, timestamp: u64 },
+    /// The record could not be decoded.
+    #[error(transparent)]
+    Decode(#[from] dbn::Error),
+    /// The symbol mapping record for the `instrument_id` could not be applied.
+    #[error("Error updating symbol map for {instrument_id}: {source}")]
+    InvalidSymbolMapping {
+        instrument_id: u32,
+        source: dbn::Error,
+    },
+    /// The instrument definition record is not the size of the layout for its DBN `version`.
+    #[error(
+        "Invalid instrument definition for DBN v{version}: \
+         expected {expected} bytes, found {found} bytes"
+    )]
+    InvalidDefinition {
+        version: u8,
+        expected: usize,
+        found: usize,
+    },
+    /// The record holds a raw symbol or exchange which is not a valid `Symbol` or `Venue`.
+    #[error(transparent)]
+    InvalidIdentifier(anyhow::Error),
+    /// The numeric instrument ID `id` resolved to both the `first` and `second`
+    /// `InstrumentId` on the `date`, at the record with the `index`.
+    #[error(
+        "Conflicting `InstrumentId`s for {id} on {date}: {first} and {second} (record {index})"
+    )]
+    ConflictingInstrumentId {
+        id: u32,
+        date: time::Date,
+        first: InstrumentId,
+        second: InstrumentId,
+        index: usize,
+    },
+    /// The record with the `index` in a batch failed to resolve.
+    #[error("Failed to resolve record {index}: {source}")]
+    Record {
+        index: usize,
+        source: Box<SymbologyError>,
+    },
+}
+
+/// Describes the out of range record `timestamp` of a [`SymbologyError::TimestampOutOfRange`].
+fn describe_timestamp(timestamp: u64) -> Cow<'static, str> {
+    if timestamp == dbn::UNDEF_TIMESTAMP {
+        Cow::Borrowed("record timestamp is undefined")
+    } else {
+        Cow::Owned(format!("invalid record timestamp {timestamp}"))
+    }
+}
+
+/// Caches the point-in-time symbol map for the most recently resolved date.
+///
+/// Consecutive records usually share the same date, so the map is only rebuilt from the
+/// metadata when the date rolls over.
+///
+/// Mappings received as `SymbolMappingMsg` records (e.g. a live remap mid-session) take
+/// precedence over the metadata for the rest of the stream.
+#[derive(Debug, Default)]
+pub struct SymbolMapCache {
+    cached: Option<(time::Date, PitSymbolMap)>,
+    nearest_symbols: Option<NearestSymbols>,
+    unmapped_dates: HashSet<time::Date>,
+    warned: HashSet<(u32, time::Date)>,
+    remapped: PitSymbolMap,
+}
+
+impl SymbolMapCache {
+    /// Creates a new empty [`SymbolMapCache`] instance.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the symbol map for the given `date`, building it from the `metadata` if the
+    /// cached map is for a different date.
+    ///
+    /// The cached map is only replaced once the map for the `date` is built, so a date with
+    /// no symbol map leaves the map of the previous date cached.
+    pub fn get(
+        &mut self,
+        metadata: &dbn::Metadata,
+        date: time::Date,
+    ) -> Result<&PitSymbolMap, SymbologyError> {
+        let (_, symbol_map) = match self.cached.take() {
+            Some(cached) if cached.0 == date => self.cached.insert(cached),
+            previous => match metadata.symbol_map_for_date(date) {
+                Ok(symbol_map) => self.cached.insert((date, symbol_map)),
+                Err(e) => {
+                    self.cached = previous;
+                    return Err(e.into());
+                }
+            },
+        };
+        Ok(symbol_map)
+    }
+
+    /// Returns the raw symbol mapped to the `instrument_id` by the full symbol map of the
+    /// `metadata`, on the mapped date nearest to the given `date`.
+    ///
+    /// This rescues records dated outside the metadata's query range (e.g. due to clock skew
+    /// at a day boundary), for which no point-in-time symbol map can be built.
+    pub fn get_nearest(
+        &mut self,
+        metadata: &dbn::Metadata,
+        date: time::Date,
+        instrument_id: u32,
+    ) -> Result<Option<&String>, SymbologyError> {
+        let nearest_symbols = match self.nearest_symbols.take() {
+            Some(nearest_symbols) => self.nearest_symbols.insert(nearest_symbols),
+            None => self
+                .nearest_symbols
+                .insert(index_by_instrument_id(metadata)?),
+        };
+        let raw_symbol = nearest_symbols
+            .get(&instrument_id)
+            .and_then(|symbols| {
+                symbols
+                    .iter()
+                    .min_by_key(|(mapped_date, _)| (*mapped_date - date).abs())
+            })
+            .map(|(_, raw_symbol)| raw_symbol.as_ref());
+        Ok(raw_symbol)
+    }
+
+    /// Returns the raw symbol mapped to the `instrument_id` on the given `date`, falling back
+    /// to the nearest mapped date (see [`SymbolMapCache::get_nearest`]) when no symbol map
+    /// can be built for the `date`.
+    ///
+    /// Dates without a symbol map are remembered, so are not rebuilt for every record, and
+    /// the fallback is only warned of once per instrument ID and date.
+    pub fn get_or_nearest(
+        &mut self,
+        metadata: &dbn::Metadata,
+        date: time::Date,
+        instrument_id: u32,
+    ) -> Result<Option<&String>, SymbologyError> {
+        if !self.unmapped_dates.contains(&date) {
+            match self.get(metadata, date) {
+                Ok(_) => return Ok(self.get(metadata, date)?.get(instrument_id)),
+                Err(e) => {
+                    tracing::debug!("Cannot build symbol map for {date}: {e}");
+                    self.unmapped_dates.insert(date);
+                }
+            }
+        }
+
+        if self.warned.insert((instrument_id, date)) {
+            tracing::warn!(
+                "Cannot build symbol map for {instrument_id} on {date}, \
+                falling back to the full symbol map"
+            );
+        }
+        self.get_nearest(metadata, date, instrument_id)
+    }
+
+    /// Updates the mapping from a symbol mapping `record`, so subsequent records for its
+    /// instrument ID resolve to the mapped symbol.
+    ///
+    /// Both the DBN v1 and v2 symbol mapping layouts are supported, as the layout is
+    /// detected from the record length.
+    ///
+    /// Returns whether the `record` was a symbol mapping (other records are ignored).
+    pub fn on_symbol_mapping(&mut self, record: &dbn::RecordRef) -> Result<bool, SymbologyError> {
+        if !record.has::<dbn::SymbolMappingMsg>() {
+            return Ok(false);
+        }
+        self.remapped.on_record(*record).map_err(|source| {
+            SymbologyError::InvalidSymbolMapping {
+                instrument_id: record.header().instrument_id,
+                source,
+            }
+        })?;
+        Ok(true)
+    }
+
+    /// Returns the raw symbol most recently mapped to the `instrument_id` by a symbol mapping
+    /// record (if any).
+    #[must_use]
+    pub fn remapped(&self, instrument_id: u32) -> Option<&String> {
+        self.remapped.get(instrument_id)
+    }
+}
+
+/// The raw symbols of the full symbol map by instrument ID, with their mapped dates.
+type NearestSymbols = HashMap<u32, Vec<(time::Date, Arc<String>)>>;
+
+/// Indexes the full symbol map of the `metadata` by instrument ID, with the mapped dates of
+/// each instrument ID in ascending order.
+fn index_by_instrument_id(metadata: &dbn::Metadata) -> Result<NearestSymbols, SymbologyError> {
+    let mut nearest_symbols: HashMap<u32, Vec<_>> = HashMap::new();
+    for ((date, instrument_id), raw_symbol) in metadata.symbol_map()?.inner() {
+        nearest_symbols
+            .entry(*instrument_id)
+            .or_default()
+            .push((*date, Arc::clone(raw_symbol)));
+    }
+    for symbols in nearest_symbols.values_mut() {
+        symbols.sort_unstable_by_key(|(date, _)| *date);
+    }
+    Ok(nearest_symbols)
+}
+
+/// Caller-supplied venue aliases which resolve the venues of a publisher venue map to
+/// canonical venues.
+///
+/// Lookups are case-insensitive: venues are uppercased before lookup, so `xnas` resolves
+/// to `XNAS` without an alias, while a legacy name such as `NASDAQ` can be aliased to `XNAS`.
+#[derive(Clone, Debug, Default)]
+pub struct VenueAliases {
+    aliases: IndexMap<Ustr, Venue>,
+}
+
+impl VenueAliases {
+    /// Creates a new [`VenueAliases`] instance mapping each alias to its canonical venue.
+    #[must_use]
+    pub fn new<'b>(aliases: impl IntoIterator<Item = (&'b str, Venue)>) -> Self {
+        let aliases = aliases
+            .into_iter()
+            .map(|(alias, venue)| (Ustr::from(&alias.to_ascii_uppercase()), venue))
+            .collect();
+        Self { aliases }
+    }
+
+    /// Returns the canonical venue for the given `venue`.
+    #[must_use]
+    pub fn canonical(&self, venue: Venue) -> Venue {
+        let venue = if venue.as_str().bytes().any(|b| b.is_ascii_lowercase()) {
+            Venue::from_str_unchecked(&venue.as_str().to_ascii_uppercase())
+        } else {
+            venue
+        };
+        self.aliases.get(&venue.inner()).copied().unwrap_or(venue)
+    }
+}
+
+/// Rules mapping ranges of CME Globex (`GLBX.MDP3`) numeric instrument IDs to the exchange
+/// `Venue` (e.g. `XCME`, `XCBT`, `XNYM`, `XCEC`) the instruments are listed on.
+///
+/// Ranges are consulted for Globex records before the publisher venue map, so the exchange
+/// can be inferred without configuring a venue for every symbol.
+#[derive(Clone, Debug, Default)]
+pub struct GlbxVenueRanges {
+    ranges: Vec<(RangeInclusive<u32>, Venue)>,
+}
+
+impl GlbxVenueRanges {
+    /// Creates a new [`GlbxVenueRanges`] instance from the given instrument ID `ranges`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any range is empty, or any two ranges overlap.
+    pub fn new(
+        ranges: impl IntoIterator<Item = (RangeInclusive<u32>, Venue)>,
+    ) -> anyhow::Result<Self> {
+        let mut ranges: Vec<(RangeInclusive<u32>, Venue)> = ranges.into_iter().collect();
+        if let Some((range, venue)) = ranges.iter().find(|(range, _)| range.is_empty()) {
+            anyhow::bail!("Empty instrument ID range {range:?} for {venue}");
+        }
+
+        ranges.sort_by_key(|(range, _)| *range.start());
+        for pair in ranges.windows(2) {
+            let ((prev, prev_venue), (next, next_venue)) = (&pair[0], &pair[1]);
+            if next.start() <= prev.end() {
+                anyhow::bail!(
+                    "Instrument ID range {next:?} for {next_venue} overlaps {prev:?} for {prev_venue}"
+                );
+            }
+        }
+
+        Ok(Self { ranges })
+    }
+
+    /// Returns the venue of the range containing the given `instrument_id` (if found).
+    #[must_use]
+    pub fn venue(&self, instrument_id: u32) -> Option<Venue> {
+        let index = self
+            .ranges
+            .partition_point(|(range, _)| *range.end() < instrument_id);
+        self.ranges
+            .get(index)
+            .filter(|(range, _)| range.contains(&instrument_id))
+            .map(|(_, venue)| *venue)
+    }
+}
+
+/// Venues keyed by the `raw_instrument_id` of instrument definitions, which identifies an
+/// instrument on its venue independently of the publisher's raw symbol.
+///
+/// Raw symbols are ambiguous for instruments listed on several venues (e.g. options with
+/// identical OSI-like symbols across exchanges), so a mapped `raw_instrument_id` resolves the
+/// venue of a definition ahead of its embedded exchange and the publisher venue map.
+#[derive(Clone, Debug, Default)]
+pub struct RawInstrumentVenues {
+    venues: HashMap<u32, Venue>,
+}
+
+impl RawInstrumentVenues {
+    /// Creates a new [`RawInstrumentVenues`] instance from the given `raw_instrument_id` to
+    /// `Venue` pairs.
+    #[must_use]
+    pub fn new(venues: impl IntoIterator<Item = (u32, Venue)>) -> Self {
+        Self {
+            venues: venues.into_iter().collect(),
+        }
+    }
+
+    /// Returns the venue mapped to the given `raw_instrument_id` (if found).
+    #[must_use]
+    pub fn venue(&self, raw_instrument_id: u32) -> Option<Venue> {
+        self.venues.get(&raw_instrument_id).copied()
+    }
+}
+
+/// Publisher venue maps scoped to DBN datasets (e.g. `XNAS.ITCH`).
+///
+/// The same publisher ID can mean different venues across datasets, so the venue of a
+/// publisher in a dataset scoped map takes precedence over the global publisher venue map
+/// for records of that dataset. Publishers not in the scoped map fall back to the global map.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetVenueMaps {
+    maps: HashMap<String, IndexMap<PublisherId, Venue>>,
+}
+
+impl DatasetVenueMaps {
+    /// Creates a new empty [`DatasetVenueMaps`] instance.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the publisher with the `publisher_id` to the `venue` for records of the
+    /// `dataset`, returning the previously mapped venue (if any).
+    pub fn insert(
+        &mut self,
+        dataset: &str,
+        publisher_id: PublisherId,
+        venue: Venue,
+    ) -> Option<Venue> {
+        self.maps
+            .entry(dataset.to_string())
+            .or_default()
+            .insert(publisher_id, venue)
+    }
+
+    /// Returns the venue mapped to the publisher with the `publisher_id` for the `dataset`
+    /// (if found).
+    #[must_use]
+    pub fn venue(&self, dataset: &str, publisher_id: PublisherId) -> Option<Venue> {
+        self.maps.get(dataset)?.get(&publisher_id).copied()
+    }
+
+    /// Returns the publisher venue map for records of the `dataset`, which is the global
+    /// `publisher_venue_map` overridden by the map scoped to the `dataset` (if any).
+    #[must_use]
+    pub fn publisher_venue_map<'m>(
+        &self,
+        dataset: &str,
+        publisher_venue_map: &'m IndexMap<PublisherId, Venue>,
+    ) -> Cow<'m, IndexMap<PublisherId, Venue>> {
+        match self.maps.get(dataset) {
+            Some(scoped) => {
+                let mut merged = publisher_venue_map.clone();
+                merged.extend(scoped.iter().map(|(id, venue)| (*id, *venue)));
+                Cow::Owned(merged)
+            }
+            None => Cow::Borrowed(publisher_venue_map),
+        }
+    }
+}
+
+/// A Nautilus `InstrumentId` resolved for a record, along with its `Venue` and the market
+/// segment (e.g. options or futures) of the record publisher.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResolvedInstrument {
+    pub instrument_id: InstrumentId,
+    pub venue: Venue,
+    /// The segment mapped to the record publisher (if any).
+    pub segment: Option<Ustr>,
+}
+
+impl ResolvedInstrument {
+    /// Creates a new [`ResolvedInstrument`] for the `instrument_id` resolved for the record
+    /// with the `publisher_id`, whose segment is looked up in the `publisher_segment_map`.
+    fn new(
+        instrument_id: InstrumentId,
+        publisher_id: PublisherId,
+        publisher_segment_map: Option<&IndexMap<PublisherId, Ustr>>,
+    ) -> Self {
+        Self {
+            instrument_id,
+            venue: instrument_id.venue,
+            segment: publisher_segment_map.and_then(|map| map.get(&publisher_id).copied()),
+        }
+    }
+}
+
+/// The number of nanoseconds in a day, which buckets the [`InstrumentIdCache`] by date.
+const NANOS_PER_DAY: u64 = 86_400_000_000_000;
+
+/// Caches the `InstrumentId`s resolved for the symbol mapped records of the current date,
+/// keyed by DBN instrument ID and publisher (which together determine the `InstrumentId`).
+///
+/// Repeated records then skip the symbol map lookup and `Symbol`/`Venue` construction. The
+/// cache is cleared when the date rolls over, and on symbol mapping records since a remap
+/// changes the `InstrumentId`s resolved.
+#[derive(Debug, Default)]
+struct InstrumentIdCache {
+    day: Option<u64>,
+    instrument_ids: HashMap<(u32, PublisherId), InstrumentId>,
+}
+
+impl InstrumentIdCache {
+    /// Returns the cached `InstrumentId` for the `key` on the `day` (since the Unix epoch),
+    /// clearing the cache if it holds another day.
+    fn get(&mut self, key: (u32, PublisherId), day: u64) -> Option<InstrumentId> {
+        if self.day == Some(day) {
+            return self.instrument_ids.get(&key).copied();
+        }
+        self.day = Some(day);
+        self.instrument_ids.clear();
+        None
+    }
+
+    fn insert(&mut self, key: (u32, PublisherId), instrument_id: InstrumentId) {
+        self.instrument_ids.insert(key, instrument_id);
+    }
+
+    fn clear(&mut self) {
+        self.instrument_ids.clear();
+    }
+}
+
+/// Resolves Nautilus `InstrumentId`s for the records of a single DBN stream.
+///
+/// The resolver holds the stream `metadata` and `publisher_venue_map` which are otherwise
+/// passed identically for every record, along with a [`SymbolMapCache`] so the symbol map is
+/// only rebuilt when the record date rolls over. The `InstrumentId`s resolved for the
+/// current date are also cached, so repeated records resolve without any lookup.
+#[derive(Debug)]
+pub struct SymbologyResolver<'a> {
+    metadata: Cow<'a, dbn::Metadata>,
+    publisher_venue_map: Cow<'a, IndexMap<PublisherId, Venue>>,
+    default_venue: Option<Venue>,
+    venue_aliases: Option<&'a VenueAliases>,
+    glbx_exchange_map: Option<&'a HashMap<Symbol, Venue>>,
+    glbx_venue_ranges: Option<&'a GlbxVenueRanges>,
+    raw_instrument_venues: Option<&'a RawInstrumentVenues>,
+    publisher_segment_map: Option<&'a IndexMap<PublisherId, Ustr>>,
+    symbol_map_cache: RefCell<SymbolMapCache>,
+    instrument_id_cache: RefCell<InstrumentIdCache>,
+}
+
+impl<'a> SymbologyResolver<'a> {
+    /// Creates a new [`SymbologyResolver`] instance for a stream with the given `metadata`.
+    #[must_use]
+    pub fn new(
+        metadata: dbn::Metadata,
+        publisher_venue_map: &'a IndexMap<PublisherId, Venue>,
+    ) -> Self {
+        Self::from_metadata(Cow::Owned(metadata), publisher_venue_map)
+    }
+
+    /// Creates a new [`SymbologyResolver`] instance borrowing the `metadata`, for the free
+    /// decoding functions which are given the metadata by reference.
+    fn borrowed(
+        metadata: &'a dbn::Metadata,
+        publisher_venue_map: &'a IndexMap<PublisherId, Venue>,
+    ) -> Self {
+        Self::from_metadata(Cow::Borrowed(metadata), publisher_venue_map)
+    }
+
+    fn from_metadata(
+        metadata: Cow<'a, dbn::Metadata>,
+        publisher_venue_map: &'a IndexMap<PublisherId, Venue>,
+    ) -> Self {
+        Self {
+            metadata,
+            publisher_venue_map: Cow::Borrowed(publisher_venue_map),
+            default_venue: None,
+            venue_aliases: None,
+            glbx_exchange_map: None,
+            glbx_venue_ranges: None,
+            raw_instrument_venues: None,
+            publisher_segment_map: None,
+            symbol_map_cache: RefCell::new(SymbolMapCache::new()),
+            instrument_id_cache: RefCell::new(InstrumentIdCache::default()),
+        }
+    }
+
+    /// Sets the `default_venue` used for records whose publisher is not in the
+    /// `publisher_venue_map`.
+    #[must_use]
+    pub const fn with_default_venue(mut self, default_venue: Venue) -> Self {
+        self.default_venue = Some(default_venue);
+        self
+    }
+
+    /// Sets the `venue_aliases` which resolve the venues of the `publisher_venue_map` to
+    /// canonical venues.
+    #[must_use]
+    pub const fn with_venue_aliases(mut self, venue_aliases: &'a VenueAliases) -> Self {
+        self.venue_aliases = Some(venue_aliases);
+        self
+    }
+
+    /// Sets the `dataset_venue_maps`, whose map scoped to the `metadata.dataset` of the stream
+    /// overrides the `publisher_venue_map` (see [`DatasetVenueMaps`]).
+    #[must_use]
+    pub fn with_dataset_venue_maps(mut self, dataset_venue_maps: &DatasetVenueMaps) -> Self {
+        if let Some(scoped) = dataset_venue_maps.maps.get(&self.metadata.dataset) {
+            self.publisher_venue_map
+                .to_mut()
+                .extend(scoped.iter().map(|(id, venue)| (*id, *venue)));
+        }
+        self
+    }
+
+    /// Sets the `glbx_exchange_map` which resolves Globex symbols to their exchange venue
+    /// (see [`build_glbx_exchange_map`]), ahead of the `glbx_venue_ranges` and the
+    /// `publisher_venue_map`.
+    #[must_use]
+    pub const fn with_glbx_exchange_map(
+        mut self,
+        glbx_exchange_map: &'a HashMap<Symbol, Venue>,
+    ) -> Self {
+        self.glbx_exchange_map = Some(glbx_exchange_map);
+        self
+    }
+
+    /// Sets the `glbx_venue_ranges` which infer the exchange of Globex records from their
+    /// numeric instrument IDs.
+    #[must_use]
+    pub const fn with_glbx_venue_ranges(mut self, glbx_venue_ranges: &'a GlbxVenueRanges) -> Self {
+        self.glbx_venue_ranges = Some(glbx_venue_ranges);
+        self
+    }
+
+    /// Sets the `raw_instrument_venues` which resolve the venues of instrument definitions
+    /// from their `raw_instrument_id` (see [`RawInstrumentVenues`]).
+    #[must_use]
+    pub const fn with_raw_instrument_venues(
+        mut self,
+        raw_instrument_venues: &'a RawInstrumentVenues,
+    ) -> Self {
+        self.raw_instrument_venues = Some(raw_instrument_venues);
+        self
+    }
+
+    /// Sets the `publisher_segment_map` which maps publishers to the market segment of the
+    /// instruments resolved by [`SymbologyResolver::resolve_instrument`].
+    #[must_use]
+    pub const fn with_publisher_segment_map(
+        mut self,
+        publisher_segment_map: &'a IndexMap<PublisherId, Ustr>,
+    ) -> Self {
+        self.publisher_segment_map = Some(publisher_segment_map);
+        self
+    }
+
+    /// Returns the metadata of the stream being resolved.
+    #[must_use]
+    pub fn metadata(&self) -> &dbn::Metadata {
+        &self.metadata
+    }
+
+    /// Returns the symbology type of the symbols resolved for the stream.
+    #[must_use]
+    pub fn stype(&self) -> dbn::SType {
+        resolved_symbology_type(&self.metadata)
+    }
+
+    /// Resolves the `InstrumentId` for the given `record`.
+    ///
+    /// A symbol mapping record updates the mapping for its instrument ID (so later records
+    /// resolve with the new mapping) and resolves to the newly mapped `InstrumentId`.
+    pub fn resolve(&self, record: &dbn::RecordRef) -> Result<InstrumentId, SymbologyError> {
+        let mut instrument_id_cache = self.instrument_id_cache.borrow_mut();
+        let cache_key = symbol_mapped_fields(record).map(|(instrument_id, nanoseconds)| {
+            (
+                (instrument_id, record.header().publisher_id),
+                nanoseconds / NANOS_PER_DAY,
+            )
+        });
+        match cache_key {
+            Some((key, day)) => {
+                if let Some(instrument_id) = instrument_id_cache.get(key, day) {
+                    return Ok(instrument_id);
+                }
+            }
+            None if record.has::<dbn::SymbolMappingMsg>() => instrument_id_cache.clear(),
+            None => {}
+        }
+
+        if let Some(instrument_id) = self.resolve_raw_instrument_venue(record)? {
+            return Ok(instrument_id);
+        }
+
+        let instrument_id = self.decode(record)?;
+        if let Some((key, _)) = cache_key {
+            instrument_id_cache.insert(key, instrument_id);
+        }
+        Ok(instrument_id)
+    }
+
+    /// Resolves the `InstrumentId` of a definition `record` whose `raw_instrument_id` is
+    /// mapped by the `raw_instrument_venues` (if any).
+    fn resolve_raw_instrument_venue(
+        &self,
+        record: &dbn::RecordRef,
+    ) -> Result<Option<InstrumentId>, SymbologyError> {
+        let Some(raw_instrument_venues) = self.raw_instrument_venues else {
+            return Ok(None);
+        };
+        let Some((raw_symbol, _, raw_instrument_id)) =
+            decode_definition_symbology(record, self.metadata.version)?
+        else {
+            return Ok(None);
+        };
+        let Some(venue) = raw_instrument_venues.venue(raw_instrument_id) else {
+            return Ok(None);
+        };
+
+        let symbol = checked_symbol(raw_symbol)?;
+        let venue = self
+            .venue_aliases
+            .map_or(venue, |aliases| aliases.canonical(venue));
+        Ok(Some(InstrumentId::new(symbol, venue)))
+    }
+
+    /// Decodes the `InstrumentId` for the given `record`, reusing the symbol map held by the
+    /// symbol map cache while records share the same date.
+    ///
+    /// Records whose publisher is not in the `publisher_venue_map` resolve to the default
+    /// venue if one is set. If venue aliases are set the venue is resolved to its canonical
+    /// venue. Globex records resolve to the exchange venue of their symbol in the Globex
+    /// exchange map, otherwise to the Globex venue range containing their instrument ID, ahead
+    /// of the `publisher_venue_map`.
+    ///
+    /// Instrument definition records carry their raw symbol and exchange directly, so are
+    /// resolved without the symbol map, and to the embedded exchange when it is not empty.
+    ///
+    /// Symbol mapping records update the symbol map cache (see
+    /// [`SymbolMapCache::on_symbol_mapping`]) and resolve to the newly mapped `InstrumentId`.
+    fn decode(&self, record: &dbn::RecordRef) -> Result<InstrumentId, SymbologyError> {
+        let mut symbol_map_cache = self.symbol_map_cache.borrow_mut();
+        if symbol_map_cache.on_symbol_mapping(record)? {
+            // Symbol mappings are not publisher specific, so may not have a mapped venue
+            let instrument_id = record.header().instrument_id;
+            let publisher_id = record.header().publisher_id;
+            let Some(raw_symbol) = symbol_map_cache.remapped(instrument_id) else {
+                return Err(SymbologyError::MissingRawSymbol {
+                    instrument_id,
+                    date: record_date(record)?,
+                });
+            };
+            let symbol = checked_symbol(raw_symbol)?;
+            let venue = self.venue(publisher_id, instrument_id, symbol)?;
+            return Ok(InstrumentId::new(symbol, self.canonical(venue)));
+        }
+
+        if let Some((raw_symbol, exchange, _)) =
+            decode_definition_symbology(record, self.metadata.version)?
+        {
+            let symbol = checked_symbol(raw_symbol)?;
+            let venue = if exchange.is_empty() {
+                self.lookup_venue(record, symbol)?
+            } else {
+                checked_venue(exchange)?
+            };
+            return Ok(InstrumentId::new(symbol, self.canonical(venue)));
+        }
+
+        // The symbol is resolved ahead of the venue, so a missing venue reports the symbol
+        let symbol = resolve_raw_symbol(record, &self.metadata, &mut symbol_map_cache)?;
+        let venue = self.lookup_venue(record, symbol)?;
+
+        Ok(InstrumentId::new(symbol, self.canonical(venue)))
+    }
+
+    /// Returns the `Venue` for the `symbol` of the given `record` (see
+    /// [`SymbologyResolver::venue`]).
+    fn lookup_venue(
+        &self,
+        record: &dbn::RecordRef,
+        symbol: Symbol,
+    ) -> Result<Venue, SymbologyError> {
+        let publisher = record.publisher()?;
+        self.venue(
+            publisher as PublisherId,
+            record.header().instrument_id,
+            symbol,
+        )
+    }
+
+    /// Returns the `Venue` for the `symbol` with the numeric `instrument_id` of the publisher
+    /// with the `publisher_id`.
+    ///
+    /// Globex symbols in the Globex exchange map resolve to their exchange venue, otherwise
+    /// Globex instrument IDs within a Globex venue range resolve to the venue of that range.
+    /// Other records resolve to the venue mapped to their publisher, or the default venue.
+    fn venue(
+        &self,
+        publisher_id: PublisherId,
+        instrument_id: u32,
+        symbol: Symbol,
+    ) -> Result<Venue, SymbologyError> {
+        if publisher_id == dbn::Publisher::GlbxMdp3Glbx as PublisherId {
+            let venue =
+                glbx_exchange_venue(symbol, publisher_id, self.glbx_exchange_map).or_else(|| {
+                    self.glbx_venue_ranges
+                        .and_then(|ranges| ranges.venue(instrument_id))
+                });
+            if let Some(venue) = venue {
+                return Ok(venue);
+            }
+        }
+
+        publisher_venue(publisher_id, &self.publisher_venue_map)
+            .or(self.default_venue)
+            .ok_or(SymbologyError::MissingVenue {
+                publisher_id,
+                symbol,
+            })
+    }
+
+    /// Returns the canonical venue for the `venue` under the venue aliases (if set).
+    fn canonical(&self, venue: Venue) -> Venue {
+        self.venue_aliases
+            .map_or(venue, |aliases| aliases.canonical(venue))
+    }
+
+    /// Resolves the `InstrumentId` for the given `record` (see [`SymbologyResolver::resolve`])
+    /// along with its venue and the segment mapped to the record publisher.
+    pub fn resolve_instrument(
+        &self,
+        record: &dbn::RecordRef,
+    ) -> Result<ResolvedInstrument, SymbologyError> {
+        let instrument_id = self.resolve(record)?;
+        Ok(ResolvedInstrument::new(
+            instrument_id,
+            record.header().publisher_id,
+            self.publisher_segment_map,
+        ))
+    }
+}
+
+/// Lazily decodes the records of a DBN stream along with their Nautilus `InstrumentId`s.
+///
+/// Records are decoded one at a time, so arbitrarily large files can be processed without
+/// materializing the records. A [`dbn::RecordRef`] borrows the decoder buffer until the next
+/// record is decoded, so [`InstrumentIdStream::next_record`] yields records by reference while
+/// the [`Iterator`] implementation yields owned [`dbn::RecordEnum`]s.
+///
+/// A record whose `InstrumentId` cannot be resolved yields an error and the stream continues
+/// with the next record. A decoding error is yielded once and ends the stream.
+pub struct InstrumentIdStream<'a, D> {
+    decoder: D,
+    resolver: SymbologyResolver<'a>,
+    finished: bool,
+}
+
+impl<'a, D> InstrumentIdStream<'a, D>
+where
+    D: DecodeRecordRef + DbnMetadata,
+{
+    /// Creates a new [`InstrumentIdStream`] instance resolving the records of the `decoder`
+    /// with the `publisher_venue_map`.
+    pub fn new(decoder: D, publisher_venue_map: &'a IndexMap<PublisherId, Venue>) -> Self {
+        let resolver = SymbologyResolver::new(decoder.metadata().clone(), publisher_venue_map);
+        Self::from_resolver(decoder, resolver)
+    }
+
+    /// Creates a new [`InstrumentIdStream`] instance resolving the records of the `decoder`
+    /// with the `resolver`, which must have been created from the decoder metadata.
+    pub const fn from_resolver(decoder: D, resolver: SymbologyResolver<'a>) -> Self {
+        Self {
+            decoder,
+            resolver,
+            finished: false,
+        }
+    }
+
+    /// Decodes the next record and resolves its `InstrumentId`, returning `None` at the end
+    /// of the stream.
+    pub fn next_record(&mut self) -> Option<anyhow::Result<(InstrumentId, dbn::RecordRef<'_>)>> {
+        if self.finished {
+            return None;
+        }
+
+        match self.decoder.decode_record_ref() {
+            Ok(Some(record)) => Some(
+                self.resolver
+                    .resolve(&record)
+                    .map(|instrument_id| (instrument_id, record))
+                    .map_err(Into::into),
+            ),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(anyhow::anyhow!("Error decoding DBN record: {e}")))
+            }
+        }
+    }
+}
+
+impl<'a, D> Iterator for InstrumentIdStream<'a, D>
+where
+    D: DecodeRecordRef + DbnMetadata,
+{
+    type Item = anyhow::Result<(InstrumentId, dbn::RecordEnum)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|result| {
+            let (instrument_id, record) = result?;
+            Ok((instrument_id, record.as_enum()?.to_owned()))
+        })
+    }
+}
+
+/// Returns the symbology type of the symbols which `InstrumentId`s are built from for a stream
+/// with the given `metadata`.
+///
+/// The symbol map resolves instrument IDs to the symbols requested (`stype_in`), so a stream
+/// requested with continuous or parent symbology resolves to the smart symbol (e.g. `ES.c.0` or
+/// `ES.FUT`) rather than the raw symbol of the underlying contract. Callers which need the
+/// individual contracts should check for [`dbn::SType::Continuous`] or [`dbn::SType::Parent`].
+#[must_use]
+pub fn resolved_symbology_type(metadata: &dbn::Metadata) -> dbn::SType {
+    match metadata.stype_in {
+        Some(dbn::SType::InstrumentId) if metadata.stype_out != dbn::SType::InstrumentId => {
+            metadata.stype_out
+        }
+        Some(stype_in) => stype_in,
+        None => dbn::SType::RawSymbol,
+    }
+}
+
+pub fn decode_nautilus_instrument_id(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Result<InstrumentId, SymbologyError> {
+    SymbologyResolver::borrowed(metadata, publisher_venue_map).resolve(record)
+}
+
+/// Decodes the `InstrumentId` for the given `record` (see [`decode_nautilus_instrument_id`]),
+/// converting any panic raised while decoding into an error for the record at the `index`.
+///
+/// This is a transitional guard for production ingestion, so a record hitting a remaining
+/// `unwrap` or `panic!` fails on its own rather than aborting the whole ingestion.
+pub fn try_decode_nautilus_instrument_id(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+    index: usize,
+) -> anyhow::Result<InstrumentId> {
+    catch_decode_panic(index, || {
+        Ok(decode_nautilus_instrument_id(
+            record,
+            metadata,
+            publisher_venue_map,
+        )?)
+    })
+}
+
+/// Runs the `decode` closure, converting a panic into an error for the record at the `index`.
+fn catch_decode_panic<T>(
+    index: usize,
+    decode: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    // The decode only reads its inputs, so no broken state is observable after a panic
+    panic::catch_unwind(AssertUnwindSafe(decode)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(anyhow::anyhow!(
+            "Panic decoding `InstrumentId` for record {index}: {msg}"
+        ))
+    })
+}
+
+/// Decodes the `InstrumentId` for the given `record` along with the raw venue symbol it was
+/// resolved from.
+pub fn decode_nautilus_instrument_id_with_raw(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Result<(InstrumentId, Ustr), SymbologyError> {
+    let instrument_id = decode_nautilus_instrument_id(record, metadata, publisher_venue_map)?;
+
+    // The `Symbol` is built from the raw symbol unchanged
+    Ok((instrument_id, instrument_id.symbol.inner()))
+}
+
+/// Decodes the `InstrumentId` for the given `record` (see [`decode_nautilus_instrument_id`])
+/// along with its venue and the segment mapped to the record publisher by the
+/// `publisher_segment_map` (if given).
+pub fn decode_resolved_instrument(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+    publisher_segment_map: Option<&IndexMap<PublisherId, Ustr>>,
+) -> Result<ResolvedInstrument, SymbologyError> {
+    let instrument_id = decode_nautilus_instrument_id(record, metadata, publisher_venue_map)?;
+    Ok(ResolvedInstrument::new(
+        instrument_id,
+        record.header().publisher_id,
+        publisher_segment_map,
+    ))
+}
+
+/// Decodes the `InstrumentId`s for the given batch of `records`, sharing a single
+/// [`SymbologyResolver`] across the batch.
+///
+/// Returns the first error encountered, in which case no further records are decoded.
+pub fn decode_nautilus_instrument_ids(
+    records: &[dbn::RecordRef],
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Result<Vec<InstrumentId>, SymbologyError> {
+    let resolver = SymbologyResolver::borrowed(metadata, publisher_venue_map);
+    records
+        .iter()
+        .map(|record| resolver.resolve(record))
+        .collect()
+}
+
+/// Decodes the `InstrumentId`s for the given batch of `records`, continuing past records
+/// which fail to resolve, sharing a single [`SymbologyResolver`] across the batch.
+///
+/// Returns the resolved `InstrumentId`s in record order, along with every failure and the
+/// index of the record it occurred for. This reports all unresolvable records of a dataset
+/// in a single pass, rather than only the first.
+pub fn decode_all_collecting_errors(
+    records: &[dbn::RecordRef],
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> (Vec<InstrumentId>, Vec<(usize, SymbologyError)>) {
+    let resolver = SymbologyResolver::borrowed(metadata, publisher_venue_map);
+    let mut instrument_ids = Vec::with_capacity(records.len());
+    let mut errors = Vec::new();
+
+    for (index, record) in records.iter().enumerate() {
+        match resolver.resolve(record) {
+            Ok(instrument_id) => instrument_ids.push(instrument_id),
+            Err(e) => errors.push((index, e)),
+        }
+    }
+
+    (instrument_ids, errors)
+}
+
+/// Builds a lookup table from the DBN numeric `instrument_id` of the given `records` to the
+/// Nautilus `InstrumentId` they resolve to, sharing a single [`SymbologyResolver`] across
+/// the batch.
+///
+/// Records resolving the same numeric ID are deduplicated. A numeric ID may be reused for a
+/// different instrument on a later date, in which case the table holds the latest mapping.
+///
+/// # Errors
+///
+/// Returns an error if a record fails to resolve, or the same numeric ID resolves to two
+/// different `InstrumentId`s within a date.
+pub fn build_instrument_id_table(
+    records: &[dbn::RecordRef],
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Result<HashMap<u32, InstrumentId>, SymbologyError> {
+    let resolver = SymbologyResolver::borrowed(metadata, publisher_venue_map);
+    let mut resolved: HashMap<(u32, time::Date), InstrumentId> = HashMap::new();
+    let mut table = HashMap::new();
+
+    for (index, record) in records.iter().enumerate() {
+        let in_record = |source| SymbologyError::Record {
+            index,
+            source: Box::new(source),
+        };
+        let instrument_id = resolver.resolve(record).map_err(in_record)?;
+        let id = record.header().instrument_id;
+        let date = record_date(record).map_err(in_record)?;
+
+        match resolved.entry((id, date)) {
+            Entry::Occupied(entry) if *entry.get() != instrument_id => {
+                return Err(SymbologyError::ConflictingInstrumentId {
+                    id,
+                    date,
+                    first: *entry.get(),
+                    second: instrument_id,
+                    index,
+                })
+            }
+            Entry::Occupied(_) => {}
+            Entry::Vacant(entry) => {
+                entry.insert(instrument_id);
+            }
+        }
+        table.insert(id, instrument_id);
+    }
+
+    Ok(table)
+}
+
+/// Returns the date of the given `record`, from its index timestamp if symbol mapped,
+/// otherwise its event timestamp.
+fn record_date(record: &dbn::RecordRef) -> Result<time::Date, SymbologyError> {
+    let nanoseconds = symbol_mapped_fields(record)
+        .map_or(record.header().ts_event, |(_, nanoseconds)| nanoseconds);
+    time::OffsetDateTime::from_unix_timestamp_nanos(i128::from(nanoseconds))
+        .map(time::OffsetDateTime::date)
+        .map_err(|_| SymbologyError::TimestampOutOfRange {
+            instrument_id: record.header().instrument_id,
+            timestamp: nanoseconds,
+        })
+}
+
+/// Decodes the `InstrumentId`s of every record in the zstd-compressed DBN file at `path`,
+/// sharing a single [`SymbologyResolver`] across the file.
+///
+/// Globex records whose symbol is in the `glbx_exchange_map` (see [`build_glbx_exchange_map`])
+/// resolve to the exchange venue mapped for the symbol.
+///
+/// # Errors
+///
+/// Returns an error if the metadata cannot be read, the zstd stream is truncated or corrupt,
+/// or a record fails to resolve.
+pub fn resolve_from_dbn_zst(
+    path: &Path,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+    glbx_exchange_map: Option<&HashMap<Symbol, Venue>>,
+) -> anyhow::Result<Vec<InstrumentId>> {
+    let mut decoder = dbn::decode::dbn::Decoder::from_zstd_file(path).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read DBN metadata from '{}', the file may be missing \
+             or its zstd stream truncated or corrupt: {e}",
+            path.display()
+        )
+    })?;
+    let mut resolver = SymbologyResolver::new(decoder.metadata().clone(), publisher_venue_map);
+    if let Some(glbx_exchange_map) = glbx_exchange_map {
+        resolver = resolver.with_glbx_exchange_map(glbx_exchange_map);
+    }
+    let mut instrument_ids = Vec::new();
+
+    while let Some(record) = decoder.decode_record_ref().map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to decode record {} from '{}', \
+             the zstd stream may be truncated or corrupt: {e}",
+            instrument_ids.len(),
+            path.display()
+        )
+    })? {
+        let instrument_id = resolver.resolve(&record).map_err(|e| {
+            anyhow::anyhow!("Failed to resolve record {}: {e}", instrument_ids.len())
+        })?;
+        instrument_ids.push(instrument_id);
+    }
+
+    Ok(instrument_ids)
+}
+
+/// Resolves the `InstrumentId` for the given `raw_symbol` of the publisher with the
+/// `publisher_id` (e.g. from a REST response), with the same venue mapping as for records.
+///
+/// Globex symbols in the `glbx_exchange_map` (see [`build_glbx_exchange_map`]) resolve to
+/// their exchange venue, otherwise to the venue mapped to the publisher by the
+/// `publisher_venue_map`.
+pub fn resolve_instrument_id(
+    raw_symbol: &str,
+    publisher_id: PublisherId,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+    glbx_exchange_map: Option<&HashMap<Symbol, Venue>>,
+) -> Result<InstrumentId, SymbologyError> {
+    let symbol = checked_symbol(raw_symbol)?;
+    let venue = glbx_exchange_venue(symbol, publisher_id, glbx_exchange_map)
+        .or_else(|| publisher_venue(publisher_id, publisher_venue_map))
+        .ok_or(SymbologyError::MissingVenue {
+            publisher_id,
+            symbol,
+        })?;
+
+    Ok(InstrumentId::new(symbol, venue))
+}
+
+/// Returns the exchange venue mapped to the `symbol` by the `glbx_exchange_map`, if the
+/// publisher with the `publisher_id` is Globex.
+fn glbx_exchange_venue(
+    symbol: Symbol,
+    publisher_id: PublisherId,
+    glbx_exchange_map: Option<&HashMap<Symbol, Venue>>,
+) -> Option<Venue> {
+    if publisher_id != dbn::Publisher::GlbxMdp3Glbx as PublisherId {
+        return None;
+    }
+    glbx_exchange_map?.get(&symbol).copied()
+}
+
+/// Returns the `Symbol` for the `raw_symbol`, if it is a valid symbol.
+fn checked_symbol(raw_symbol: &str) -> Result<Symbol, SymbologyError> {
+    Symbol::new_checked(raw_symbol).map_err(SymbologyError::InvalidIdentifier)
+}
+
+/// Returns the `Venue` for the `exchange`, if it is a valid venue.
+fn checked_venue(exchange: &str) -> Result<Venue, SymbologyError> {
+    Venue::new_checked(exchange).map_err(SymbologyError::InvalidIdentifier)
+}
+
+/// Returns the venue mapped to the publisher with the `publisher_id`.
+fn publisher_venue(
+    publisher_id: PublisherId,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Option<Venue> {
+    publisher_venue_map.get(&publisher_id).copied()
+}
+
+/// Returns the raw symbol, exchange and raw instrument ID embedded in the given `record`, if
+/// it is an instrument definition.
+///
+/// The definition layout changed between DBN versions, so is selected by the `version` of
+/// the stream metadata (which reflects any upgrade applied on decoding):
+/// - DBN v1: [`dbn::compat::InstrumentDefMsgV1`] with 22 byte symbols, for v1 files decoded
+///   with [`dbn::VersionUpgradePolicy::AsIs`].
+/// - DBN v2: [`dbn::InstrumentDefMsg`] with 71 byte symbols, for v2 files and v1 files
+///   upgraded on decoding.
+fn decode_definition_symbology<'a>(
+    record: &dbn::RecordRef<'a>,
+    version: u8,
+) -> Result<Option<(&'a str, &'a str, u32)>, SymbologyError> {
+    if !record.has::<dbn::InstrumentDefMsg>() {
+        return Ok(None);
+    }
+
+    let (raw_symbol, exchange, raw_instrument_id) = if version <= 1 {
+        let msg = get_definition::<dbn::compat::InstrumentDefMsgV1>(record, version)?;
+        (msg.raw_symbol()?, msg.exchange()?, msg.raw_instrument_id)
+    } else {
+        let msg = get_definition::<dbn::InstrumentDefMsg>(record, version)?;
+        (msg.raw_symbol()?, msg.exchange()?, msg.raw_instrument_id)
+    };
+
+    Ok(Some((raw_symbol, exchange, raw_instrument_id)))
+}
+
+/// Returns the definition `record` in the layout `T` of the DBN `version`, checking the
+/// record length matches so a metadata version mismatch errors rather than misreading.
+fn get_definition<'a, T: dbn::HasRType>(
+    record: &dbn::RecordRef<'a>,
+    version: u8,
+) -> Result<&'a T, SymbologyError> {
+    let expected = std::mem::size_of::<T>();
+    if record.record_size() != expected {
+        return Err(SymbologyError::InvalidDefinition {
+            version,
+            expected,
+            found: record.record_size(),
+        });
+    }
+    record.get::<T>().ok_or(SymbologyError::UnsupportedSchema)
+}
+
+/// Reads the instrument ID and the timestamp the symbol is mapped for from a record.
+type SymbolMappedFields = fn(&dbn::RecordRef) -> Option<(u32, u64)>;
+
+/// The record types whose `InstrumentId`s are resolved through the symbol map, with the
+/// schemas they are received for.
+///
+/// This is the single source of truth for the supported records (see [`supported_schemas`]).
+const SYMBOL_MAPPED_RECORDS: [(&[dbn::Schema], SymbolMappedFields); 11] = [
+    (&[dbn::Schema::Mbo], |record| {
+        let msg = record.get::<dbn::MboMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Trades], |record| {
+        let msg = record.get::<dbn::TradeMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Mbp1, dbn::Schema::Tbbo], |record| {
+        let msg = record.get::<dbn::Mbp1Msg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Bbo1S, dbn::Schema::Bbo1M], |record| {
+        let msg = record.get::<dbn::BboMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Cmbp1, dbn::Schema::Tcbbo], |record| {
+        let msg = record.get::<dbn::Cmbp1Msg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Cbbo1S, dbn::Schema::Cbbo1M], |record| {
+        let msg = record.get::<dbn::CbboMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Mbp10], |record| {
+        let msg = record.get::<dbn::Mbp10Msg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (
+        &[
+            dbn::Schema::Ohlcv1S,
+            dbn::Schema::Ohlcv1M,
+            dbn::Schema::Ohlcv1H,
+            dbn::Schema::Ohlcv1D,
+            dbn::Schema::OhlcvEod,
+        ],
+        |record| {
+            let msg = record.get::<dbn::OhlcvMsg>()?;
+            Some((msg.hd.instrument_id, msg.hd.ts_event))
+        },
+    ),
+    (&[dbn::Schema::Status], |record| {
+        let msg = record.get::<dbn::StatusMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Imbalance], |record| {
+        let msg = record.get::<dbn::ImbalanceMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+    (&[dbn::Schema::Statistics], |record| {
+        let msg = record.get::<dbn::StatMsg>()?;
+        Some((msg.hd.instrument_id, msg.ts_recv))
+    }),
+];
+
+static SUPPORTED_SCHEMAS: OnceLock<Vec<dbn::Schema>> = OnceLock::new();
+
+/// Returns the instrument ID and symbol mapping timestamp of the `record`, if it is one of
+/// the [`SYMBOL_MAPPED_RECORDS`].
+fn symbol_mapped_fields(record: &dbn::RecordRef) -> Option<(u32, u64)> {
+    SYMBOL_MAPPED_RECORDS
+        .iter()
+        .find_map(|(_, fields)| fields(record))
+}
+
+/// Returns the DBN schemas whose records `InstrumentId`s can be decoded for (see
+/// [`decode_nautilus_instrument_id`]).
+#[must_use]
+pub fn supported_schemas() -> &'static [dbn::Schema] {
+    SUPPORTED_SCHEMAS.get_or_init(|| {
+        let mut schemas: Vec<dbn::Schema> = SYMBOL_MAPPED_RECORDS
+            .iter()
+            .flat_map(|(schemas, _)| schemas.iter().copied())
+            .chain([dbn::Schema::Definition])
+            .collect();
+        schemas.sort_unstable_by_key(|schema| *schema as u16);
+        schemas
+    })
+}
+
+/// Returns whether the `InstrumentId` of the `record` can be decoded, i.e. it is a record of
+/// one of the [`supported_schemas`] or a symbol mapping.
+#[must_use]
+pub fn can_decode(record: &dbn::RecordRef) -> bool {
+    record.has::<dbn::InstrumentDefMsg>()
+        || record.has::<dbn::SymbolMappingMsg>()
+        || symbol_mapped_fields(record).is_some()
+}
+
+pub fn get_nautilus_instrument_id_for_record(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    venue: Venue,
+) -> Result<InstrumentId, SymbologyError> {
+    get_nautilus_instrument_id_for_record_cached(
+        record,
+        metadata,
+        venue,
+        &mut SymbolMapCache::new(),
+    )
+}
+
+/// Returns the `InstrumentId` for the given `record` and `venue`, reusing the symbol map held
+/// by the `symbol_map_cache` while records share the same date.
+pub fn get_nautilus_instrument_id_for_record_cached(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    venue: Venue,
+    symbol_map_cache: &mut SymbolMapCache,
+) -> Result<InstrumentId, SymbologyError> {
+    let symbol = resolve_raw_symbol(record, metadata, symbol_map_cache)?;
+    Ok(InstrumentId::new(symbol, venue))
+}
+
+/// Returns the raw symbol mapped to the instrument ID of the `record` on the date it is
+/// symbol mapped for, reusing the symbol map held by the `symbol_map_cache`.
+fn resolve_raw_symbol(
+    record: &dbn::RecordRef,
+    metadata: &dbn::Metadata,
+    symbol_map_cache: &mut SymbolMapCache,
+) -> Result<Symbol, SymbologyError> {
+    let Some((instrument_id, nanoseconds)) = symbol_mapped_fields(record) else {
+        return Err(SymbologyError::UnsupportedSchema);
+    };
+
+    let datetime = Some(nanoseconds)
+        .filter(|ns| *ns != dbn::UNDEF_TIMESTAMP)
+        .and_then(|ns| i64::try_from(ns).ok())
+        .and_then(|ns| {
+            time::OffsetDateTime::UNIX_EPOCH.checked_add(time::Duration::nanoseconds(ns))
+        })
+        .ok_or(SymbologyError::TimestampOutOfRange {
+            instrument_id,
+            timestamp: nanoseconds,
+        })?;
+    if let Some(raw_symbol) = symbol_map_cache.remapped(instrument_id) {
+        return Ok(Symbol::from_str_unchecked(raw_symbol));
+    }
+
+    let date = datetime.date();
+    let no_raw_symbol = || SymbologyError::MissingRawSymbol {
+        instrument_id,
+        date,
+    };
+    let raw_symbol = symbol_map_cache
+        .get_or_nearest(metadata, date, instrument_id)?
+        .ok_or_else(no_raw_symbol)?;
+
+    Ok(Symbol::from_str_unchecked(raw_symbol))
+}
+
+/// How to handle a symbol defined more than once when building a symbol to venue map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateSymbolPolicy {
+    /// The venue of the last definition for the symbol is kept.
+    #[default]
+    KeepLast,
+    /// A symbol defined with more than one venue is an error.
+    Error,
+}
+
+/// Builds the map of raw symbol to exchange `Venue` from the DBN (optionally zstd-compressed)
+/// definitions file at `definitions_path`, such as a CME Globex `definition` schema file.
+///
+/// Definitions without an embedded exchange are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be decoded, a definition has an invalid symbol or
+/// exchange, or a duplicate symbol is found under [`DuplicateSymbolPolicy::Error`].
+pub fn build_glbx_exchange_map(
+    definitions_path: &Path,
+    duplicate_policy: DuplicateSymbolPolicy,
+) -> anyhow::Result<HashMap<Symbol, Venue>> {
+    let decoder = DynDecoder::from_file(definitions_path, dbn::VersionUpgradePolicy::Upgrade)?;
+    build_exchange_map_from_decoder(decoder, duplicate_policy)
+}
+
+/// Builds the map of raw symbol to exchange `Venue` from the definition records decoded by
+/// the given `decoder` (see [`build_glbx_exchange_map`]). Records other than definitions
+/// are ignored.
+///
+/// The definitions are read in the layout of the DBN version of the `decoder` metadata, so
+/// v1 files decoded without upgrading are supported.
+///
+/// # Errors
+///
+/// Returns an error if a record cannot be decoded, a definition has an invalid symbol or
+/// exchange, or a duplicate symbol is found under [`DuplicateSymbolPolicy::Error`].
+pub fn build_exchange_map_from_decoder<D: DecodeRecordRef + DbnMetadata>(
+    mut decoder: D,
+    duplicate_policy: DuplicateSymbolPolicy,
+) -> anyhow::Result<HashMap<Symbol, Venue>> {
+    let version = decoder.metadata().version;
+    let mut exchange_map = HashMap::new();
+
+    while let Some(record) = decoder.decode_record_ref()? {
+        let Some((raw_symbol, exchange, _)) = decode_definition_symbology(&record, version)? else {
+            continue;
+        };
+        if exchange.is_empty() {
+            tracing::debug!("Skipping definition for '{raw_symbol}' with no exchange");
+            continue;
+        }
+
+        let symbol = Symbol::new_checked(raw_symbol)?;
+        let venue = Venue::new_checked(exchange)?;
+        match exchange_map.insert(symbol, venue) {
+            Some(previous)
+                if previous != venue && duplicate_policy == DuplicateSymbolPolicy::Error =>
+            {
+                anyhow::bail!(
+                    "Duplicate definition for symbol '{symbol}' with venues {previous} and {venue}"
+                )
+            }
+            _ => {}
+        }
+    }
+
+    Ok(exchange_map)
+}
+
+/// Returns the publisher IDs mapped to the given `venue` by the `publisher_venue_map`.
+#[must_use]
+pub fn publishers_for_venue(
+    venue: Venue,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Vec<PublisherId> {
+    publisher_venue_map
+        .iter()
+        .filter(|(_, mapped_venue)| **mapped_venue == venue)
+        .map(|(publisher_id, _)| *publisher_id)
+        .collect()
+}
+
+/// Returns the publisher IDs of the `metadata` dataset which are missing from the
+/// `publisher_venue_map`, so the configuration can be fixed before decoding.
+///
+/// DBN metadata identifies the dataset rather than the individual publishers, so every
+/// publisher of the dataset is checked. No publisher IDs are returned for an unknown dataset.
+#[must_use]
+pub fn describe_unmapped(
+    metadata: &dbn::Metadata,
+    publisher_venue_map: &IndexMap<PublisherId, Venue>,
+) -> Vec<PublisherId> {
+    let Ok(dataset) = metadata.dataset.parse::<dbn::Dataset>() else {
+        return Vec::new();
+    };
+
+    (1..=dbn::publishers::PUBLISHER_COUNT as PublisherId)
+        .filter_map(|publisher_id| dbn::Publisher::try_from(publisher_id).ok())
+        .filter(|publisher| publisher.dataset() == dataset)
+        .map(PublisherId::from)
+        .filter(|publisher_id| !publisher_venue_map.contains_key(publisher_id))
+        .collect()
+}
+
+/// Returns a textual representation of the `publisher_venue_map` with one
+/// `{publisher_id} {publisher} -> {venue}` line per entry, sorted by publisher ID, so the
+/// output is the same regardless of the insertion order of the map.
+///
+/// Publisher IDs unknown to DBN are shown with a publisher of `UNKNOWN`.
+#[must_use]
+pub fn dump_venue_map(publisher_venue_map: &IndexMap<PublisherId, Venue>) -> String {
+    let mut entries: Vec<(&PublisherId, &Venue)> = publisher_venue_map.iter().collect();
+    entries.sort_unstable_by_key(|(publisher_id, _)| **publisher_id);
+
+    entries
+        .into_iter()
+        .map(|(publisher_id, venue)| {
+            let publisher = dbn::Publisher::try_from(*publisher_id)
+                .map_or("UNKNOWN", |publisher| publisher.as_str());
+            format!("{publisher_id} {publisher} -> {venue}")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[must_use]
+pub fn infer_symbology_type(symbol: &str) -> String {
+    if symbol.ends_with(".FUT") || symbol.ends_with(".OPT") {
+        return "parent".to_string();
+    }
+
+    let parts: Vec<&str> = symbol.split('.').collect();
+    if parts.len() == 3 && parts[2].chars().all(|c| c.is_ascii_digit()) {
+        return "continuous".to_string();
+    }
+
+    if symbol.chars().all(|c| c.is_ascii_digit()) {
+        return "instrument_id".to_string();
+    }
+
+    "raw_symbol".to_string()
+}
+
+pub fn check_consistent_symbology(symbols: &[&str]) -> anyhow::Result<()> {
+    check_slice_not_empty(symbols, stringify!(symbols)).unwrap();
+
+    // SAFETY: We checked len so know there must be at least one symbol
+    let first_symbol = symbols.first().unwrap();
+    let first_stype = infer_symbology_type(first_symbol);
+
+    for symbol in symbols {
+        let next_stype = infer_symbology_type(symbol);
+        if next_stype != first_stype {
+            return Err(anyhow::anyhow!(
+                "Inconsistent symbology types: '{}' for {} vs '{}' for {}",
+                first_stype,
+                first_symbol,
+                next_stype,
+                symbol
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, path::PathBuf};
+
+    use databento::dbn::decode::{dbn::Decoder, DbnMetadata, DecodeRecordRef};
+    use rstest::*;
+
+    use super::*;
+
+    pub const TEST_DATA_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/src/databento/test_data");
+
+    fn publisher_venue_map() -> IndexMap<PublisherId, Venue> {
+        IndexMap::from([
+            (
+                dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+                Venue::from("GLBX"),
+            ),
+            (
+                dbn::Publisher::XnasItchXnas as PublisherId,
+                Venue::from("XNAS"),
+            ),
+            (
+                dbn::Publisher::DbeqBasicDbeq as PublisherId,
+                Venue::from("DBEQ"),
+            ),
+        ])
+    }
+
+    /// Decodes the `InstrumentId` of the first record in the given test data file.
+    fn decode_first_instrument_id(file_name: &str) -> Result<InstrumentId, SymbologyError> {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/{file_name}"));
+        let mut decoder = Decoder::from_zstd_file(path)?;
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref()?.unwrap();
+        decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map())
+    }
+
+    #[rstest]
+    #[case("test_data.bbo-1s.dbn.zst", "ESM4.GLBX")]
+    #[case("test_data.bbo-1m.dbn.zst", "ESM4.GLBX")]
+    fn test_decode_nautilus_instrument_id_for_bbo(#[case] file_name: &str, #[case] expected: &str) {
+        let instrument_id = decode_first_instrument_id(file_name).unwrap();
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    /// Returns the imbalance test data metadata with the `ts_recv` and `instrument_id` of its
+    /// first record, for building consolidated records resolving to `SPOT`.
+    fn consolidated_fixture() -> (dbn::Metadata, u64, u32) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+        let msg = record.get::<dbn::ImbalanceMsg>().unwrap();
+        (metadata, msg.ts_recv, msg.hd.instrument_id)
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_cmbp1() {
+        let (metadata, ts_recv, instrument_id) = consolidated_fixture();
+        let mut msg = dbn::Cmbp1Msg::default_for_schema(dbn::Schema::Cmbp1);
+        msg.hd.publisher_id = dbn::Publisher::DbeqBasicDbeq as u16;
+        msg.hd.instrument_id = instrument_id;
+        msg.ts_recv = ts_recv;
+        let record = dbn::RecordRef::from(&msg);
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("SPOT.DBEQ"));
+    }
+
+    #[rstest]
+    #[case(dbn::Schema::Cbbo1S)]
+    #[case(dbn::Schema::Cbbo1M)]
+    fn test_decode_nautilus_instrument_id_for_cbbo(#[case] schema: dbn::Schema) {
+        let (metadata, ts_recv, instrument_id) = consolidated_fixture();
+        let mut msg = dbn::CbboMsg::default_for_schema(schema);
+        msg.hd.publisher_id = dbn::Publisher::DbeqBasicDbeq as u16;
+        msg.hd.instrument_id = instrument_id;
+        msg.ts_recv = ts_recv;
+        let record = dbn::RecordRef::from(&msg);
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("SPOT.DBEQ"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_halted_status() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.status.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let mut msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::StatusMsg>()
+            .unwrap()
+            .clone();
+        msg.action = dbn::StatusAction::Halt as u16;
+        msg.reason = dbn::StatusReason::Regulatory as u16;
+        msg.is_trading = b'N' as std::os::raw::c_char;
+        let record = dbn::RecordRef::from(&msg);
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("ESH1.GLBX"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_when_no_raw_symbol() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let mut msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        msg.hd.instrument_id = 1; // Not in the metadata symbol map
+        let record = dbn::RecordRef::from(&msg);
+
+        let result = decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map());
+
+        let e = result.unwrap_err();
+        assert_eq!(e.to_string(), "No raw symbol found for 1 on 2020-12-28");
+        assert!(matches!(
+            e,
+            SymbologyError::MissingRawSymbol { instrument_id: 1, date } if date.to_string() == "2020-12-28"
+        ));
+    }
+
+    #[rstest]
+    #[case(dbn::RecordEnum::System(dbn::SystemMsg::default()))]
+    #[case(dbn::RecordEnum::Error(dbn::ErrorMsg::default()))]
+    fn test_decode_nautilus_instrument_id_for_unsupported_schema(#[case] record: dbn::RecordEnum) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let metadata = Decoder::from_zstd_file(path).unwrap().metadata().clone();
+
+        let result = decode_nautilus_instrument_id(
+            &dbn::RecordRef::from(&record),
+            &metadata,
+            &publisher_venue_map(),
+        );
+
+        assert!(matches!(
+            result.unwrap_err(),
+            SymbologyError::UnsupportedSchema
+        ));
+    }
+
+    #[rstest]
+    #[case(
+        u64::MAX,
+        "Cannot resolve symbol for 5482: record timestamp is undefined"
+    )]
+    #[case(
+        u64::MAX - 1,
+        "Cannot resolve symbol for 5482: invalid record timestamp 18446744073709551614"
+    )]
+    fn test_decode_nautilus_instrument_id_with_invalid_timestamp(
+        #[case] ts_recv: u64,
+        #[case] expected: &str,
+    ) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let mut msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        msg.ts_recv = ts_recv;
+        let record = dbn::RecordRef::from(&msg);
+
+        let result = decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map());
+
+        let e = result.unwrap_err();
+        assert_eq!(e.to_string(), expected);
+        assert!(matches!(
+            e,
+            SymbologyError::TimestampOutOfRange { instrument_id: 5482, timestamp } if timestamp == ts_recv
+        ));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_before_metadata_start() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let mut metadata = decoder.metadata().clone();
+        let mut msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        // Start the metadata at midnight, so the record is dated the previous day
+        let start = metadata.start().date().midnight().assume_utc();
+        metadata.start = start.unix_timestamp_nanos() as u64;
+        msg.ts_recv = metadata.start - 1;
+        let record = dbn::RecordRef::from(&msg);
+        assert!(metadata
+            .symbol_map_for_date(start.date().previous_day().unwrap())
+            .is_err());
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("ESH1.GLBX"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_when_no_venue() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+        let publisher_venue_map = IndexMap::new();
+
+        let result = decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map);
+
+        let e = result.unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "`Venue` not found for `publisher_id` 1 (symbol ESH1)"
+        );
+        assert!(matches!(
+            e,
+            SymbologyError::MissingVenue { publisher_id: 1, symbol } if symbol == Symbol::from("ESH1")
+        ));
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_with_default_venue() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = IndexMap::new();
+        let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+            .with_default_venue(Venue::from("GLBX"));
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let instrument_id = resolver.resolve(&record).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("ESH1.GLBX"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_with_raw() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.bbo-1s.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let (instrument_id, raw_symbol) =
+            decode_nautilus_instrument_id_with_raw(&record, &metadata, &publisher_venue_map())
+                .unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("ESM4.GLBX"));
+        assert_eq!(raw_symbol, Ustr::from("ESM4"));
+    }
+
+    #[rstest]
+    fn test_try_decode_nautilus_instrument_id() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.bbo-1s.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let instrument_id =
+            try_decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map(), 0)
+                .unwrap();
+        let no_venue = try_decode_nautilus_instrument_id(&record, &metadata, &IndexMap::new(), 0);
+
+        assert_eq!(instrument_id, InstrumentId::from("ESM4.GLBX"));
+        assert!(no_venue
+            .unwrap_err()
+            .to_string()
+            .contains("`Venue` not found"));
+    }
+
+    #[rstest]
+    #[case::str(|| panic!("Invalid record"), "Invalid record")]
+    #[case::string(|| panic!("Invalid record {}", 7), "Invalid record 7")]
+    #[case::invalid_instrument_id(|| Ok(InstrumentId::from("ESM4")), "Error parsing `InstrumentId`")]
+    fn test_catch_decode_panic(
+        #[case] decode: fn() -> anyhow::Result<InstrumentId>,
+        #[case] expected: &str,
+    ) {
+        let result = catch_decode_panic(3, decode);
+
+        let msg = result.unwrap_err().to_string();
+        assert!(msg.starts_with("Panic decoding `InstrumentId` for record 3: "));
+        assert!(msg.contains(expected));
+    }
+
+    #[rstest]
+    #[case(Some(IndexMap::from([(dbn::Publisher::GlbxMdp3Glbx as PublisherId, Ustr::from("FUTURES"))])), Some("FUTURES"))]
+    #[case(Some(IndexMap::from([(dbn::Publisher::XnasItchXnas as PublisherId, Ustr::from("EQUITIES"))])), None)]
+    #[case(None, None)]
+    fn test_decode_resolved_instrument(
+        #[case] publisher_segment_map: Option<IndexMap<PublisherId, Ustr>>,
+        #[case] expected_segment: Option<&str>,
+    ) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.bbo-1s.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let resolved = decode_resolved_instrument(
+            &record,
+            &metadata,
+            &publisher_venue_map(),
+            publisher_segment_map.as_ref(),
+        )
+        .unwrap();
+
+        assert_eq!(resolved.instrument_id, InstrumentId::from("ESM4.GLBX"));
+        assert_eq!(resolved.venue, Venue::from("GLBX"));
+        assert_eq!(resolved.segment, expected_segment.map(Ustr::from));
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_resolve_instrument_with_segment() {
+        let (metadata, msg) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let publisher_venue_map = publisher_venue_map();
+        let publisher_segment_map = IndexMap::from([(msg.hd.publisher_id, Ustr::from("FUTURES"))]);
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_publisher_segment_map(&publisher_segment_map);
+
+        let resolved = resolver
+            .resolve_instrument(&dbn::RecordRef::from(&msg))
+            .unwrap();
+
+        assert_eq!(
+            resolved,
+            ResolvedInstrument {
+                instrument_id: InstrumentId::from("ESM4.GLBX"),
+                venue: Venue::from("GLBX"),
+                segment: Some(Ustr::from("FUTURES")),
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_ids() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let mut msgs = Vec::new();
+        while let Some(record) = decoder.decode_record_ref().unwrap() {
+            msgs.push(record.get::<dbn::ImbalanceMsg>().unwrap().clone());
+        }
+        let records: Vec<dbn::RecordRef> = msgs.iter().map(dbn::RecordRef::from).collect();
+
+        let instrument_ids =
+            decode_nautilus_instrument_ids(&records, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(
+            instrument_ids,
+            vec![
+                InstrumentId::from("SPOT.XNAS"),
+                InstrumentId::from("SPOT.XNAS")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_ids_returns_first_error() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        let mut unknown_msg = msg.clone();
+        unknown_msg.hd.instrument_id = 1; // Not in the metadata symbol map
+        let records = vec![
+            dbn::RecordRef::from(&msg),
+            dbn::RecordRef::from(&unknown_msg),
+            dbn::RecordRef::from(&msg),
+        ];
+
+        let result = decode_nautilus_instrument_ids(&records, &metadata, &publisher_venue_map());
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "No raw symbol found for 1 on 2020-12-28"
+        );
+    }
+
+    #[rstest]
+    fn test_decode_all_collecting_errors() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        let mut unknown_msg = msg.clone();
+        unknown_msg.hd.instrument_id = 1; // Not in the metadata symbol map
+        let mut unmapped_msg = msg.clone();
+        unmapped_msg.hd.publisher_id = dbn::Publisher::XnysPillarXnys as u16;
+        let records = vec![
+            dbn::RecordRef::from(&msg),
+            dbn::RecordRef::from(&unknown_msg),
+            dbn::RecordRef::from(&msg),
+            dbn::RecordRef::from(&unmapped_msg),
+        ];
+
+        let (instrument_ids, errors) =
+            decode_all_collecting_errors(&records, &metadata, &publisher_venue_map());
+
+        assert_eq!(
+            instrument_ids,
+            vec![
+                InstrumentId::from("ESH1.GLBX"),
+                InstrumentId::from("ESH1.GLBX")
+            ]
+        );
+        let errors: Vec<(usize, String)> = errors
+            .into_iter()
+            .map(|(index, e)| (index, e.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (1, "No raw symbol found for 1 on 2020-12-28".to_string()),
+                (
+                    3,
+                    format!(
+                        "`Venue` not found for `publisher_id` {} (symbol ESH1)",
+                        dbn::Publisher::XnysPillarXnys as u16
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_resolve_from_dbn_zst() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+
+        let instrument_ids = resolve_from_dbn_zst(&path, &publisher_venue_map(), None).unwrap();
+
+        assert_eq!(instrument_ids.len(), 2);
+        assert!(instrument_ids
+            .iter()
+            .all(|id| *id == InstrumentId::from("ESH1.GLBX")));
+    }
+
+    #[rstest]
+    fn test_resolve_from_dbn_zst_with_glbx_exchange_map() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let glbx_exchange_map = HashMap::from([(Symbol::from("ESH1"), Venue::from("XCME"))]);
+
+        let instrument_ids =
+            resolve_from_dbn_zst(&path, &publisher_venue_map(), Some(&glbx_exchange_map)).unwrap();
+
+        assert_eq!(instrument_ids[0], InstrumentId::from("ESH1.XCME"));
+    }
+
+    #[rstest]
+    fn test_resolve_from_dbn_zst_with_glbx_exchange_map_for_unmapped_publisher() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let glbx_exchange_map = HashMap::from([(Symbol::from("ESH1"), Venue::from("XCME"))]);
+
+        let instrument_ids =
+            resolve_from_dbn_zst(&path, &IndexMap::new(), Some(&glbx_exchange_map)).unwrap();
+
+        assert!(instrument_ids
+            .iter()
+            .all(|id| *id == InstrumentId::from("ESH1.XCME")));
+        assert!(resolve_from_dbn_zst(&path, &IndexMap::new(), None).is_err());
+    }
+
+    #[rstest]
+    fn test_resolve_from_dbn_zst_when_truncated() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let data = std::fs::read(path).unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data[..data.len() - 16]).unwrap();
+
+        let result = resolve_from_dbn_zst(file.path(), &publisher_venue_map(), None);
+
+        let e = result.unwrap_err().to_string();
+        assert!(e.contains("truncated or corrupt"), "{e}");
+        assert!(e.contains("incomplete frame"), "{e}");
+    }
+
+    #[rstest]
+    fn test_resolve_from_dbn_zst_when_corrupt() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"not a zstd stream").unwrap();
+
+        let result = resolve_from_dbn_zst(file.path(), &publisher_venue_map(), None);
+
+        let e = result.unwrap_err().to_string();
+        assert!(e.starts_with("Failed to read DBN metadata from"), "{e}");
+    }
+
+    #[rstest]
+    #[case(None, "ESH1.GLBX")]
+    #[case(Some("XCME"), "ESH1.XCME")]
+    fn test_resolve_instrument_id(#[case] exchange: Option<&str>, #[case] expected: &str) {
+        let glbx_exchange_map =
+            exchange.map(|exchange| HashMap::from([(Symbol::from("ESH1"), Venue::from(exchange))]));
+
+        let instrument_id = resolve_instrument_id(
+            "ESH1",
+            dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+            &publisher_venue_map(),
+            glbx_exchange_map.as_ref(),
+        )
+        .unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    #[rstest]
+    fn test_resolve_instrument_id_ignores_glbx_exchange_map_for_other_publishers() {
+        let glbx_exchange_map = HashMap::from([(Symbol::from("MSFT"), Venue::from("XCME"))]);
+
+        let instrument_id = resolve_instrument_id(
+            "MSFT",
+            dbn::Publisher::XnasItchXnas as PublisherId,
+            &publisher_venue_map(),
+            Some(&glbx_exchange_map),
+        )
+        .unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("MSFT.XNAS"));
+    }
+
+    #[rstest]
+    fn test_resolve_instrument_id_matches_record_decoding() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+        let (decoded, raw_symbol) =
+            decode_nautilus_instrument_id_with_raw(&record, &metadata, &publisher_venue_map())
+                .unwrap();
+
+        let resolved = resolve_instrument_id(
+            &raw_symbol,
+            record.header().publisher_id,
+            &publisher_venue_map(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(resolved, decoded);
+    }
+
+    #[rstest]
+    fn test_resolve_instrument_id_when_no_venue() {
+        let result = resolve_instrument_id(
+            "MSFT",
+            dbn::Publisher::XnysPillarXnys as PublisherId,
+            &publisher_venue_map(),
+            None,
+        );
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "`Venue` not found for `publisher_id` {} (symbol MSFT)",
+                dbn::Publisher::XnysPillarXnys as u16
+            )
+        );
+    }
+
+    #[rstest]
+    fn test_symbol_map_cache_reuses_map_for_same_date() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let date = time::Date::from_calendar_date(2021, time::Month::October, 4).unwrap();
+        let next_date = date.next_day().unwrap();
+        let mut cache = SymbolMapCache::new();
+
+        let first = cache.get(&metadata, date).unwrap() as *const PitSymbolMap;
+        let second = cache.get(&metadata, date).unwrap() as *const PitSymbolMap;
+        assert_eq!(first, second);
+        assert_eq!(cache.get(&metadata, date).unwrap()[9439], "SPOT");
+
+        // Date rolls over
+        assert_eq!(cache.get(&metadata, next_date).unwrap()[9444], "SPOT");
+        assert!(cache.get(&metadata, next_date).unwrap().get(9439).is_none());
+    }
+
+    #[rstest]
+    fn test_symbol_map_cache_keeps_map_when_date_unmapped() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let date = time::Date::from_calendar_date(2021, time::Month::October, 4).unwrap();
+        let unmapped_date = time::Date::from_calendar_date(2021, time::Month::October, 1).unwrap();
+        let mut cache = SymbolMapCache::new();
+
+        let first = cache.get(&metadata, date).unwrap() as *const PitSymbolMap;
+        assert!(cache.get(&metadata, unmapped_date).is_err());
+        let second = cache.get(&metadata, date).unwrap() as *const PitSymbolMap;
+
+        assert_eq!(first, second);
+    }
+
+    #[rstest]
+    #[case(2021, time::Month::October, 1, 9439, Some("SPOT"))] // Nearest date is 2021-10-04
+    #[case(2021, time::Month::October, 4, 9439, Some("SPOT"))]
+    #[case(2021, time::Month::October, 4, 9444, None)]
+    #[case(2022, time::Month::January, 10, 9878, Some("SPOT"))] // Nearest date is 2022-01-03
+    #[case(2022, time::Month::January, 10, 1, None)]
+    fn test_symbol_map_cache_get_or_nearest(
+        #[case] year: i32,
+        #[case] month: time::Month,
+        #[case] day: u8,
+        #[case] instrument_id: u32,
+        #[case] expected: Option<&str>,
+    ) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let date = time::Date::from_calendar_date(year, month, day).unwrap();
+        let mut cache = SymbolMapCache::new();
+
+        // Repeated lookups resolve the same, whether or not the date has a symbol map
+        for _ in 0..2 {
+            let raw_symbol = cache
+                .get_or_nearest(&metadata, date, instrument_id)
+                .unwrap()
+                .map(String::as_str);
+            assert_eq!(raw_symbol, expected);
+        }
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_resolve() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.imbalance.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map);
+
+        let mut instrument_ids = Vec::new();
+        while let Some(record) = decoder.decode_record_ref().unwrap() {
+            instrument_ids.push(resolver.resolve(&record).unwrap());
+        }
+
+        assert_eq!(instrument_ids.len(), 2);
+        assert!(instrument_ids
+            .iter()
+            .all(|id| *id == InstrumentId::from("SPOT.XNAS")));
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_resolves_again_on_date_rollover() {
+        let (mut metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let date = record_date(&dbn::RecordRef::from(&trade)).unwrap();
+        let next_date = date.next_day().unwrap();
+        let mapping = |raw_symbol: &str, start_date, end_date| dbn::SymbolMapping {
+            raw_symbol: raw_symbol.to_string(),
+            intervals: vec![dbn::MappingInterval {
+                start_date,
+                end_date,
+                symbol: trade.hd.instrument_id.to_string(),
+            }],
+        };
+        metadata.mappings = vec![
+            mapping("ESM4", date, next_date),
+            mapping("ESU4", next_date, next_date.next_day().unwrap()),
+        ];
+        metadata.end = std::num::NonZeroU64::new(trade.ts_recv + 2 * NANOS_PER_DAY);
+        let mut later = trade.clone();
+        later.ts_recv += NANOS_PER_DAY;
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map);
+
+        let resolved: Vec<InstrumentId> = [&trade, &trade, &later, &later, &trade]
+            .into_iter()
+            .map(|msg| resolver.resolve(&dbn::RecordRef::from(msg)).unwrap())
+            .collect();
+
+        let (esm4, esu4) = (
+            InstrumentId::from("ESM4.GLBX"),
+            InstrumentId::from("ESU4.GLBX"),
+        );
+        assert_eq!(resolved, vec![esm4, esm4, esu4, esu4, esm4]);
+    }
+
+    /// Returns the trades test data metadata and first record, with the metadata symbology
+    /// requested as `stype_in` for the given `symbol`.
+    fn trades_with_symbology(stype_in: dbn::SType, symbol: &str) -> (dbn::Metadata, dbn::TradeMsg) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let mut metadata = decoder.metadata().clone();
+        metadata.stype_in = Some(stype_in);
+        metadata.symbols = vec![symbol.to_string()];
+        for mapping in &mut metadata.mappings {
+            mapping.raw_symbol = symbol.to_string();
+        }
+        let msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::TradeMsg>()
+            .unwrap()
+            .clone();
+        (metadata, msg)
+    }
+
+    fn symbol_mapping(instrument_id: u32, raw_symbol: &str) -> dbn::SymbolMappingMsg {
+        dbn::SymbolMappingMsg::new(
+            instrument_id,
+            0,
+            dbn::SType::RawSymbol,
+            raw_symbol,
+            dbn::SType::RawSymbol,
+            raw_symbol,
+            0,
+            u64::MAX,
+        )
+        .unwrap()
+    }
 
-use super::types::PublisherId;
+    #[rstest]
+    fn test_symbology_resolver_symbol_mapping_remaps_later_records() {
+        let (metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let mut mapping = symbol_mapping(trade.hd.instrument_id, "ESU4");
+        mapping.hd.publisher_id = dbn::Publisher::GlbxMdp3Glbx as PublisherId;
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map);
 
-pub fn decode_nautilus_instrument_id(
-    record: &dbn::RecordRef,
-    metadata: &dbn::Metadata,
-    publisher_venue_map: &IndexMap<PublisherId, Venue>,
-) -> anyhow::Result<InstrumentId> {
-    let publisher = record.publisher().expect("Invalid `publisher` for record");
-    let publisher_id = publisher as PublisherId;
-    let venue = publisher_venue_map
-        .get(&publisher_id)
-        .ok_or_else(|| anyhow::anyhow!("`Venue` not found for `publisher_id` {publisher_id}"))?;
-    let instrument_id = get_nautilus_instrument_id_for_record(record, metadata, *venue)?;
+        let before = resolver.resolve(&dbn::RecordRef::from(&trade)).unwrap();
+        let remapped = resolver.resolve(&dbn::RecordRef::from(&mapping)).unwrap();
+        let after = resolver.resolve(&dbn::RecordRef::from(&trade)).unwrap();
 
-    Ok(instrument_id)
-}
+        assert_eq!(before, InstrumentId::from("ESM4.GLBX"));
+        assert_eq!(remapped, InstrumentId::from("ESU4.GLBX"));
+        assert_eq!(after, InstrumentId::from("ESU4.GLBX"));
+    }
 
-pub fn get_nautilus_instrument_id_for_record(
-    record: &dbn::RecordRef,
-    metadata: &dbn::Metadata,
-    venue: Venue,
-) -> anyhow::Result<InstrumentId> {
-    let (instrument_id, nanoseconds) = if let Some(msg) = record.get::<dbn::MboMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::TradeMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::Mbp1Msg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::Bbo1SMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::Bbo1MMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::Mbp10Msg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::OhlcvMsg>() {
-        (msg.hd.instrument_id, msg.hd.ts_event)
-    } else if let Some(msg) = record.get::<dbn::StatusMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::ImbalanceMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else if let Some(msg) = record.get::<dbn::StatMsg>() {
-        (msg.hd.instrument_id, msg.ts_recv)
-    } else {
-        anyhow::bail!("DBN message type is not currently supported")
-    };
+    #[rstest]
+    fn test_build_instrument_id_table_deduplicates() {
+        let (metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let mut other = trade.clone();
+        other.hd.instrument_id += 1;
+        let mut mapping = symbol_mapping(other.hd.instrument_id, "ESU4");
+        mapping.hd.publisher_id = dbn::Publisher::GlbxMdp3Glbx as PublisherId;
+        mapping.hd.ts_event = trade.ts_recv;
+        let records = vec![
+            dbn::RecordRef::from(&trade),
+            dbn::RecordRef::from(&mapping),
+            dbn::RecordRef::from(&other),
+            dbn::RecordRef::from(&trade),
+        ];
 
-    let duration = time::Duration::nanoseconds(nanoseconds as i64);
-    let datetime = time::OffsetDateTime::UNIX_EPOCH
-        .checked_add(duration)
-        .unwrap(); // SAFETY: Relying on correctness of record timestamps
-    let date = datetime.date();
-    let symbol_map = metadata.symbol_map_for_date(date)?;
-    let raw_symbol = symbol_map
-        .get(instrument_id)
-        .ok_or_else(|| anyhow::anyhow!("No raw symbol found for {instrument_id}"))?;
+        let table = build_instrument_id_table(&records, &metadata, &publisher_venue_map()).unwrap();
 
-    let symbol = Symbol::from_str_unchecked(raw_symbol);
+        assert_eq!(
+            table,
+            HashMap::from([
+                (trade.hd.instrument_id, InstrumentId::from("ESM4.GLBX")),
+                (other.hd.instrument_id, InstrumentId::from("ESU4.GLBX")),
+            ])
+        );
+    }
 
-    Ok(InstrumentId::new(symbol, venue))
-}
+    #[rstest]
+    fn test_build_instrument_id_table_with_id_reused_on_later_date() {
+        let (metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let next_day = trade.ts_recv + 86_400_000_000_000;
+        let mut mapping = symbol_mapping(trade.hd.instrument_id, "ESU4");
+        mapping.hd.publisher_id = dbn::Publisher::GlbxMdp3Glbx as PublisherId;
+        mapping.hd.ts_event = next_day;
+        let mut later = trade.clone();
+        later.ts_recv = next_day;
+        let records = vec![
+            dbn::RecordRef::from(&trade),
+            dbn::RecordRef::from(&mapping),
+            dbn::RecordRef::from(&later),
+        ];
 
-#[must_use]
-pub fn infer_symbology_type(symbol: &str) -> String {
-    if symbol.ends_with(".FUT") || symbol.ends_with(".OPT") {
-        return "parent".to_string();
+        let table = build_instrument_id_table(&records, &metadata, &publisher_venue_map()).unwrap();
+
+        assert_eq!(
+            table,
+            HashMap::from([(trade.hd.instrument_id, InstrumentId::from("ESU4.GLBX"))])
+        );
     }
 
-    let parts: Vec<&str> = symbol.split('.').collect();
-    if parts.len() == 3 && parts[2].chars().all(|c| c.is_ascii_digit()) {
-        return "continuous".to_string();
+    #[rstest]
+    fn test_build_instrument_id_table_with_conflicting_mapping() {
+        let (metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let mut mapping = symbol_mapping(trade.hd.instrument_id, "ESU4");
+        mapping.hd.publisher_id = dbn::Publisher::GlbxMdp3Glbx as PublisherId;
+        mapping.hd.ts_event = trade.ts_recv;
+        let records = vec![
+            dbn::RecordRef::from(&trade),
+            dbn::RecordRef::from(&mapping),
+            dbn::RecordRef::from(&trade),
+        ];
+
+        let result = build_instrument_id_table(&records, &metadata, &publisher_venue_map());
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "Conflicting `InstrumentId`s for {} on 2020-12-28: ESM4.GLBX and ESU4.GLBX (record 1)",
+                trade.hd.instrument_id
+            )
+        );
     }
 
-    if symbol.chars().all(|c| c.is_ascii_digit()) {
-        return "instrument_id".to_string();
+    #[rstest]
+    fn test_symbology_resolver_symbol_mapping_without_venue() {
+        let (metadata, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        // Symbol mappings are created without a publisher
+        let mapping = symbol_mapping(trade.hd.instrument_id, "ESU4");
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map);
+
+        let result = resolver.resolve(&dbn::RecordRef::from(&mapping));
+        let after = resolver.resolve(&dbn::RecordRef::from(&trade)).unwrap();
+
+        assert!(matches!(
+            result.unwrap_err(),
+            SymbologyError::MissingVenue { publisher_id: 0, symbol } if symbol == Symbol::from("ESU4")
+        ));
+        assert_eq!(after, InstrumentId::from("ESU4.GLBX"));
     }
 
-    "raw_symbol".to_string()
-}
+    /// Returns a default record of the record type received for the `schema`.
+    fn default_record(schema: dbn::Schema) -> dbn::RecordEnum {
+        match schema {
+            dbn::Schema::Mbo => dbn::RecordEnum::Mbo(dbn::MboMsg::default()),
+            dbn::Schema::Trades => dbn::RecordEnum::Trade(dbn::TradeMsg::default()),
+            dbn::Schema::Mbp1 | dbn::Schema::Tbbo => dbn::RecordEnum::Mbp1(dbn::Mbp1Msg::default()),
+            dbn::Schema::Mbp10 => dbn::RecordEnum::Mbp10(dbn::Mbp10Msg::default()),
+            dbn::Schema::Ohlcv1S
+            | dbn::Schema::Ohlcv1M
+            | dbn::Schema::Ohlcv1H
+            | dbn::Schema::Ohlcv1D
+            | dbn::Schema::OhlcvEod => {
+                dbn::RecordEnum::Ohlcv(dbn::OhlcvMsg::default_for_schema(schema))
+            }
+            dbn::Schema::Definition => {
+                dbn::RecordEnum::InstrumentDef(dbn::InstrumentDefMsg::default())
+            }
+            dbn::Schema::Statistics => dbn::RecordEnum::Stat(dbn::StatMsg::default()),
+            dbn::Schema::Status => dbn::RecordEnum::Status(dbn::StatusMsg::default()),
+            dbn::Schema::Imbalance => dbn::RecordEnum::Imbalance(dbn::ImbalanceMsg::default()),
+            dbn::Schema::Cmbp1 | dbn::Schema::Tcbbo => {
+                dbn::RecordEnum::Cmbp1(dbn::Cmbp1Msg::default_for_schema(schema))
+            }
+            dbn::Schema::Cbbo1S | dbn::Schema::Cbbo1M => {
+                dbn::RecordEnum::Cbbo(dbn::CbboMsg::default_for_schema(schema))
+            }
+            dbn::Schema::Bbo1S | dbn::Schema::Bbo1M => {
+                dbn::RecordEnum::Bbo(dbn::BboMsg::default_for_schema(schema))
+            }
+        }
+    }
 
-pub fn check_consistent_symbology(symbols: &[&str]) -> anyhow::Result<()> {
-    check_slice_not_empty(symbols, stringify!(symbols)).unwrap();
+    #[rstest]
+    fn test_supported_schemas_records_can_be_decoded() {
+        assert!(!supported_schemas().is_empty());
+        for schema in supported_schemas() {
+            let record = default_record(*schema);
+            assert!(
+                can_decode(&dbn::RecordRef::from(&record)),
+                "{schema} record not accepted"
+            );
+        }
+    }
 
-    // SAFETY: We checked len so know there must be at least one symbol
-    let first_symbol = symbols.first().unwrap();
-    let first_stype = infer_symbology_type(first_symbol);
+    #[rstest]
+    #[case(dbn::RecordEnum::System(dbn::SystemMsg::default()))]
+    #[case(dbn::RecordEnum::Error(dbn::ErrorMsg::default()))]
+    fn test_can_decode_rejects_other_records(#[case] record: dbn::RecordEnum) {
+        assert!(!can_decode(&dbn::RecordRef::from(&record)));
+    }
 
-    for symbol in symbols {
-        let next_stype = infer_symbology_type(symbol);
-        if next_stype != first_stype {
-            return Err(anyhow::anyhow!(
-                "Inconsistent symbology types: '{}' for {} vs '{}' for {}",
-                first_stype,
-                first_symbol,
-                next_stype,
-                symbol
-            ));
+    #[rstest]
+    fn test_symbol_map_cache_ignores_other_records() {
+        let (_, trade) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        let mut cache = SymbolMapCache::new();
+
+        assert!(!cache
+            .on_symbol_mapping(&dbn::RecordRef::from(&trade))
+            .unwrap());
+        assert!(cache.remapped(trade.hd.instrument_id).is_none());
+    }
+
+    #[rstest]
+    #[case(dbn::SType::RawSymbol, "ESH1", "ESH1.GLBX")]
+    #[case(dbn::SType::Continuous, "ES.c.0", "ES.c.0.GLBX")]
+    #[case(dbn::SType::Parent, "ES.FUT", "ES.FUT.GLBX")]
+    fn test_symbology_resolver_for_stype(
+        #[case] stype_in: dbn::SType,
+        #[case] symbol: &str,
+        #[case] expected: &str,
+    ) {
+        let (metadata, msg) = trades_with_symbology(stype_in, symbol);
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map);
+
+        let instrument_id = resolver.resolve(&dbn::RecordRef::from(&msg)).unwrap();
+
+        assert_eq!(resolver.stype(), stype_in);
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    /// Returns the definition test data metadata and first record, with the embedded
+    /// exchange replaced by the given `exchange`.
+    fn definition_with_exchange(exchange: &str) -> (dbn::Metadata, dbn::InstrumentDefMsg) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.definition.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let metadata = decoder.metadata().clone();
+        let mut msg = decoder
+            .decode_record_ref()
+            .unwrap()
+            .unwrap()
+            .get::<dbn::InstrumentDefMsg>()
+            .unwrap()
+            .clone();
+        msg.exchange = [0; 5];
+        for (dst, src) in msg.exchange.iter_mut().zip(exchange.bytes()) {
+            *dst = src as std::ffi::c_char;
         }
+        (metadata, msg)
     }
 
-    Ok(())
-}
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_definition_uses_exchange() {
+        let (metadata, msg) = definition_with_exchange("XCME");
+        let record = dbn::RecordRef::from(&msg);
 
-////////////////////////////////////////////////////////////////////////////////
-// Tests
-////////////////////////////////////////////////////////////////////////////////
-#[cfg(test)]
-mod tests {
-    use rstest::*;
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &IndexMap::new()).unwrap();
 
-    use super::*;
+        assert_eq!(instrument_id.symbol.as_str(), msg.raw_symbol().unwrap());
+        assert_eq!(instrument_id.venue, Venue::from("XCME"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_definition_without_exchange() {
+        let (metadata, msg) = definition_with_exchange("");
+        let record = dbn::RecordRef::from(&msg);
+        let publisher_venue_map = IndexMap::from([(msg.hd.publisher_id, Venue::from("GLBX"))]);
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &publisher_venue_map).unwrap();
+
+        assert_eq!(instrument_id.venue, Venue::from("GLBX"));
+        assert!(decode_nautilus_instrument_id(&record, &metadata, &IndexMap::new()).is_err());
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_definition_with_venue_aliases() {
+        let (metadata, msg) = definition_with_exchange("xcme");
+        let record = dbn::RecordRef::from(&msg);
+        let aliases = VenueAliases::new([("XCME", Venue::from("CME"))]);
+
+        let publisher_venue_map = IndexMap::new();
+        let instrument_id = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_venue_aliases(&aliases)
+            .resolve(&record)
+            .unwrap();
+
+        assert_eq!(instrument_id.venue, Venue::from("CME"));
+    }
+
+    #[rstest]
+    #[case::v1_as_is("test_data.definition.v1.dbn.zst", dbn::VersionUpgradePolicy::AsIs, 1)]
+    #[case::v1_upgraded(
+        "test_data.definition.v1.dbn.zst",
+        dbn::VersionUpgradePolicy::Upgrade,
+        2
+    )]
+    #[case::v2("test_data.definition.dbn.zst", dbn::VersionUpgradePolicy::AsIs, 2)]
+    fn test_decode_nautilus_instrument_id_for_definition_versions(
+        #[case] file_name: &str,
+        #[case] upgrade_policy: dbn::VersionUpgradePolicy,
+        #[case] expected_version: u8,
+    ) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/{file_name}"));
+        let mut decoder = DynDecoder::from_file(path, upgrade_policy).unwrap();
+        let metadata = decoder.metadata().clone();
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let instrument_id =
+            decode_nautilus_instrument_id(&record, &metadata, &IndexMap::new()).unwrap();
+
+        assert_eq!(metadata.version, expected_version);
+        assert_eq!(instrument_id, InstrumentId::from("MSFT.XNAS"));
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_definition_with_version_mismatch() {
+        let (mut metadata, msg) = definition_with_exchange("XNAS");
+        metadata.version = 1;
+        let record = dbn::RecordRef::from(&msg);
+
+        let result = decode_nautilus_instrument_id(&record, &metadata, &IndexMap::new());
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "Invalid instrument definition for DBN v1: expected {} bytes, found {} bytes",
+                std::mem::size_of::<dbn::compat::InstrumentDefMsgV1>(),
+                std::mem::size_of::<dbn::InstrumentDefMsg>(),
+            )
+        );
+    }
+
+    /// Returns the definition test data metadata along with definitions of the same raw
+    /// symbol on the same exchange, distinguished only by the given `raw_instrument_ids`.
+    fn definitions_with_raw_instrument_ids(
+        raw_instrument_ids: &[u32],
+    ) -> (dbn::Metadata, Vec<dbn::InstrumentDefMsg>) {
+        let (metadata, msg) = definition_with_exchange("XNAS");
+        let msgs = raw_instrument_ids
+            .iter()
+            .map(|raw_instrument_id| {
+                let mut msg = msg.clone();
+                msg.raw_instrument_id = *raw_instrument_id;
+                msg
+            })
+            .collect();
+        (metadata, msgs)
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_with_raw_instrument_venues() {
+        let (metadata, msgs) = definitions_with_raw_instrument_ids(&[1001, 1002, 1003]);
+        let publisher_venue_map = publisher_venue_map();
+        let raw_instrument_venues =
+            RawInstrumentVenues::new([(1001, Venue::from("XBOX")), (1002, Venue::from("XCBO"))]);
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_raw_instrument_venues(&raw_instrument_venues);
+
+        let instrument_ids: Vec<InstrumentId> = msgs
+            .iter()
+            .map(|msg| resolver.resolve(&dbn::RecordRef::from(msg)).unwrap())
+            .collect();
+
+        // An unmapped raw instrument ID falls back to the embedded exchange
+        assert_eq!(
+            instrument_ids,
+            vec![
+                InstrumentId::from("MSFT.XBOX"),
+                InstrumentId::from("MSFT.XCBO"),
+                InstrumentId::from("MSFT.XNAS"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_symbology_resolver_with_raw_instrument_venues_and_venue_aliases() {
+        let (metadata, msgs) = definitions_with_raw_instrument_ids(&[1001]);
+        let publisher_venue_map = publisher_venue_map();
+        let raw_instrument_venues = RawInstrumentVenues::new([(1001, Venue::from("XBOX"))]);
+        let aliases = VenueAliases::new([("XBOX", Venue::from("BOX"))]);
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_raw_instrument_venues(&raw_instrument_venues)
+            .with_venue_aliases(&aliases);
+
+        let instrument_id = resolver.resolve(&dbn::RecordRef::from(&msgs[0])).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("MSFT.BOX"));
+    }
+
+    #[rstest]
+    #[case(DuplicateSymbolPolicy::KeepLast)]
+    #[case(DuplicateSymbolPolicy::Error)]
+    fn test_build_glbx_exchange_map(#[case] duplicate_policy: DuplicateSymbolPolicy) {
+        // The fixture defines the same symbol twice on the same exchange
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.definition.dbn.zst"));
+
+        let exchange_map = build_glbx_exchange_map(&path, duplicate_policy).unwrap();
+
+        assert_eq!(
+            exchange_map,
+            HashMap::from([(Symbol::from("MSFT"), Venue::from("XNAS"))])
+        );
+    }
+
+    /// Encodes definitions of the same symbol with each of the given `exchanges` into
+    /// an in-memory DBN stream.
+    fn encode_definitions(exchanges: &[&str]) -> Vec<u8> {
+        let (metadata, _) = definition_with_exchange("");
+        let mut buffer = Vec::new();
+        let mut encoder = dbn::encode::dbn::Encoder::new(&mut buffer, &metadata).unwrap();
+        for exchange in exchanges {
+            let (_, msg) = definition_with_exchange(exchange);
+            dbn::encode::EncodeRecord::encode_record(&mut encoder, &msg).unwrap();
+        }
+        drop(encoder);
+        buffer
+    }
+
+    #[rstest]
+    fn test_build_exchange_map_from_decoder_keeps_last() {
+        let buffer = encode_definitions(&["XCME", "", "XCBT"]);
+        let decoder = Decoder::new(buffer.as_slice()).unwrap();
+
+        let exchange_map =
+            build_exchange_map_from_decoder(decoder, DuplicateSymbolPolicy::KeepLast).unwrap();
+
+        assert_eq!(
+            exchange_map,
+            HashMap::from([(Symbol::from("MSFT"), Venue::from("XCBT"))])
+        );
+    }
+
+    #[rstest]
+    fn test_build_exchange_map_from_decoder_with_duplicate_error() {
+        let buffer = encode_definitions(&["XCME", "XCBT"]);
+        let decoder = Decoder::new(buffer.as_slice()).unwrap();
+
+        let result = build_exchange_map_from_decoder(decoder, DuplicateSymbolPolicy::Error);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Duplicate definition for symbol 'MSFT' with venues XCME and XCBT"
+        );
+    }
+
+    #[rstest]
+    fn test_build_glbx_exchange_map_missing_file() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/missing.definition.dbn"));
+        assert!(build_glbx_exchange_map(&path, DuplicateSymbolPolicy::KeepLast).is_err());
+    }
+
+    #[rstest]
+    fn test_resolved_symbology_type_when_inverse() {
+        let (mut metadata, _) = trades_with_symbology(dbn::SType::InstrumentId, "5482");
+        metadata.stype_out = dbn::SType::Continuous;
+        assert_eq!(resolved_symbology_type(&metadata), dbn::SType::Continuous);
+    }
+
+    #[rstest]
+    fn test_decode_nautilus_instrument_id_for_imbalance() {
+        let instrument_id = decode_first_instrument_id("test_data.imbalance.dbn.zst").unwrap();
+        assert_eq!(instrument_id, InstrumentId::from("SPOT.XNAS"));
+    }
 
     #[rstest]
     #[case("1", "instrument_id")]
@@ -175,4 +2759,332 @@ mod tests {
         let result = check_consistent_symbology(&symbols);
         assert!(result.is_ok());
     }
+
+    #[rstest]
+    #[case("XNAS", "XNAS")]
+    #[case("xnas", "XNAS")]
+    #[case("NASDAQ", "XNAS")]
+    #[case("nasdaq", "XNAS")]
+    #[case("BINANCE", "BINANCE")]
+    fn test_venue_aliases_canonical(#[case] venue: &str, #[case] expected: &str) {
+        let aliases = VenueAliases::new([("Nasdaq", Venue::from("XNAS"))]);
+        assert_eq!(aliases.canonical(Venue::from(venue)), Venue::from(expected));
+    }
+
+    #[rstest]
+    #[case("glbx")]
+    #[case("CME")]
+    fn test_symbology_resolver_with_venue_aliases(#[case] configured_venue: &str) {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let mut decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = IndexMap::from([(
+            dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+            Venue::from(configured_venue),
+        )]);
+        let venue_aliases = VenueAliases::new([("cme", Venue::from("GLBX"))]);
+        let resolver = SymbologyResolver::new(decoder.metadata().clone(), &publisher_venue_map)
+            .with_venue_aliases(&venue_aliases);
+        let record = decoder.decode_record_ref().unwrap().unwrap();
+
+        let instrument_id = resolver.resolve(&record).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from("ESH1.GLBX"));
+    }
+
+    fn glbx_venue_ranges() -> GlbxVenueRanges {
+        GlbxVenueRanges::new([
+            (5483..=5999, Venue::from("XCBT")),
+            (5000..=5482, Venue::from("XCME")),
+        ])
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case(4999, None)]
+    #[case(5000, Some("XCME"))]
+    #[case(5482, Some("XCME"))]
+    #[case(5483, Some("XCBT"))]
+    #[case(5999, Some("XCBT"))]
+    #[case(6000, None)]
+    fn test_glbx_venue_ranges_venue(#[case] instrument_id: u32, #[case] expected: Option<&str>) {
+        assert_eq!(
+            glbx_venue_ranges().venue(instrument_id),
+            expected.map(Venue::from)
+        );
+    }
+
+    #[rstest]
+    #[case(vec![(0..=10, "XCME"), (10..=20, "XCBT")], "Instrument ID range 10..=20 for XCBT overlaps 0..=10 for XCME")]
+    #[case(vec![(10..=20, "XCBT"), (0..=30, "XCME")], "Instrument ID range 10..=20 for XCBT overlaps 0..=30 for XCME")]
+    #[allow(clippy::reversed_empty_ranges)]
+    #[case(vec![(20..=10, "XCME")], "Empty instrument ID range 20..=10 for XCME")]
+    fn test_glbx_venue_ranges_invalid(
+        #[case] ranges: Vec<(RangeInclusive<u32>, &str)>,
+        #[case] expected: &str,
+    ) {
+        let result = GlbxVenueRanges::new(
+            ranges
+                .into_iter()
+                .map(|(range, venue)| (range, Venue::from(venue))),
+        );
+        assert_eq!(result.unwrap_err().to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(5482, "ESH1.XCME")]
+    #[case(5483, "ESH1.XCBT")]
+    #[case(6000, "ESH1.GLBX")]
+    fn test_symbology_resolver_with_glbx_venue_ranges(
+        #[case] instrument_id: u32,
+        #[case] expected: &str,
+    ) {
+        let (mut metadata, mut msg) = trades_with_symbology(dbn::SType::RawSymbol, "ESH1");
+        for mapping in &mut metadata.mappings {
+            for interval in &mut mapping.intervals {
+                interval.symbol = instrument_id.to_string();
+            }
+        }
+        msg.hd.instrument_id = instrument_id;
+        let publisher_venue_map = publisher_venue_map();
+        let ranges = glbx_venue_ranges();
+        let resolver =
+            SymbologyResolver::new(metadata, &publisher_venue_map).with_glbx_venue_ranges(&ranges);
+
+        let instrument_id = resolver.resolve(&dbn::RecordRef::from(&msg)).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    #[rstest]
+    #[case("ESH1", "ESH1.XNYM")]
+    #[case("ESM1", "ESH1.XCBT")]
+    fn test_symbology_resolver_with_glbx_exchange_map(
+        #[case] mapped_symbol: &str,
+        #[case] expected: &str,
+    ) {
+        let (mut metadata, mut msg) = trades_with_symbology(dbn::SType::RawSymbol, "ESH1");
+        for mapping in &mut metadata.mappings {
+            for interval in &mut mapping.intervals {
+                interval.symbol = "5483".to_string();
+            }
+        }
+        msg.hd.instrument_id = 5483;
+        let publisher_venue_map = publisher_venue_map();
+        let glbx_exchange_map = HashMap::from([(Symbol::from(mapped_symbol), Venue::from("XNYM"))]);
+        let ranges = glbx_venue_ranges();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_glbx_exchange_map(&glbx_exchange_map)
+            .with_glbx_venue_ranges(&ranges);
+
+        let instrument_id = resolver.resolve(&dbn::RecordRef::from(&msg)).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    #[rstest]
+    #[case("GLBX.MDP3", "ESM4.XCME")]
+    #[case("IFUS.IMPACT", "ESM4.IFUS")]
+    #[case("XNAS.ITCH", "ESM4.GLBX")]
+    fn test_symbology_resolver_with_dataset_venue_maps(
+        #[case] dataset: &str,
+        #[case] expected: &str,
+    ) {
+        let (mut metadata, msg) = trades_with_symbology(dbn::SType::RawSymbol, "ESM4");
+        metadata.dataset = dataset.to_string();
+        // Both datasets scope the publisher of the record to a different venue
+        let publisher_id = msg.hd.publisher_id;
+        let mut dataset_venue_maps = DatasetVenueMaps::new();
+        dataset_venue_maps.insert("GLBX.MDP3", publisher_id, Venue::from("XCME"));
+        dataset_venue_maps.insert("IFUS.IMPACT", publisher_id, Venue::from("IFUS"));
+        let publisher_venue_map = publisher_venue_map();
+        let resolver = SymbologyResolver::new(metadata, &publisher_venue_map)
+            .with_dataset_venue_maps(&dataset_venue_maps);
+
+        let instrument_id = resolver.resolve(&dbn::RecordRef::from(&msg)).unwrap();
+
+        assert_eq!(instrument_id, InstrumentId::from(expected));
+    }
+
+    #[rstest]
+    fn test_dataset_venue_maps_publisher_venue_map() {
+        let xnas = dbn::Publisher::XnasItchXnas as PublisherId;
+        let mut dataset_venue_maps = DatasetVenueMaps::new();
+        assert_eq!(
+            dataset_venue_maps.insert("XNAS.ITCH", xnas, Venue::from("NASDAQ")),
+            None
+        );
+        let publisher_venue_map = publisher_venue_map();
+
+        let scoped = dataset_venue_maps.publisher_venue_map("XNAS.ITCH", &publisher_venue_map);
+        let unscoped = dataset_venue_maps.publisher_venue_map("DBEQ.BASIC", &publisher_venue_map);
+
+        assert_eq!(scoped.get(&xnas), Some(&Venue::from("NASDAQ")));
+        assert_eq!(scoped.len(), publisher_venue_map.len());
+        assert!(matches!(unscoped, Cow::Borrowed(_)));
+        assert_eq!(
+            dataset_venue_maps.venue("XNAS.ITCH", xnas),
+            Some(Venue::from("NASDAQ"))
+        );
+        assert_eq!(dataset_venue_maps.venue("DBEQ.BASIC", xnas), None);
+    }
+
+    #[rstest]
+    fn test_instrument_id_stream() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.mbo.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = publisher_venue_map();
+
+        let results: Vec<(InstrumentId, dbn::RecordEnum)> =
+            InstrumentIdStream::new(decoder, &publisher_venue_map)
+                .collect::<anyhow::Result<_>>()
+                .unwrap();
+
+        assert_eq!(results.len(), 2);
+        for (instrument_id, record) in &results {
+            assert_eq!(*instrument_id, InstrumentId::from("ESH1.GLBX"));
+            assert!(matches!(record, dbn::RecordEnum::Mbo(_)));
+        }
+    }
+
+    #[rstest]
+    fn test_instrument_id_stream_next_record() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = publisher_venue_map();
+        let mut stream = InstrumentIdStream::new(decoder, &publisher_venue_map);
+
+        let mut count = 0;
+        while let Some(result) = stream.next_record() {
+            let (instrument_id, record) = result.unwrap();
+            assert_eq!(instrument_id, InstrumentId::from("ESH1.GLBX"));
+            assert!(record.get::<dbn::TradeMsg>().is_some());
+            count += 1;
+        }
+
+        assert_eq!(count, 2);
+        assert!(stream.next_record().is_none());
+    }
+
+    #[rstest]
+    fn test_instrument_id_stream_continues_after_resolve_error() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let publisher_venue_map = IndexMap::new();
+
+        let results: Vec<_> = InstrumentIdStream::new(decoder, &publisher_venue_map).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .starts_with("`Venue` not found for `publisher_id`")));
+    }
+
+    #[rstest]
+    fn test_instrument_id_stream_ends_on_decode_error() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.mbo.dbn"));
+        let mut bytes = std::fs::read(path).unwrap();
+        // Corrupt the length of the last record
+        let last_record = bytes.len() - std::mem::size_of::<dbn::MboMsg>();
+        bytes[last_record] = 0;
+        let decoder = Decoder::new(bytes.as_slice()).unwrap();
+        let publisher_venue_map = publisher_venue_map();
+
+        let results: Vec<_> = InstrumentIdStream::new(decoder, &publisher_venue_map).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Error decoding DBN record"));
+    }
+
+    #[rstest]
+    fn test_publishers_for_venue() {
+        let mut publisher_venue_map = publisher_venue_map();
+        publisher_venue_map.insert(
+            dbn::Publisher::XnasBasicXnas as PublisherId,
+            Venue::from("XNAS"),
+        );
+
+        assert_eq!(
+            publishers_for_venue(Venue::from("XNAS"), &publisher_venue_map),
+            vec![
+                dbn::Publisher::XnasItchXnas as PublisherId,
+                dbn::Publisher::XnasBasicXnas as PublisherId,
+            ]
+        );
+        assert!(publishers_for_venue(Venue::from("XCME"), &publisher_venue_map).is_empty());
+    }
+
+    #[rstest]
+    fn test_dump_venue_map_is_independent_of_insertion_order() {
+        let entries = [
+            (
+                dbn::Publisher::XnasItchXnas as PublisherId,
+                Venue::from("XNAS"),
+            ),
+            (u16::MAX, Venue::from("TEST")),
+            (
+                dbn::Publisher::GlbxMdp3Glbx as PublisherId,
+                Venue::from("GLBX"),
+            ),
+        ];
+        let forward: IndexMap<PublisherId, Venue> = entries.iter().copied().collect();
+        let reverse: IndexMap<PublisherId, Venue> = entries.iter().rev().copied().collect();
+
+        let dump = dump_venue_map(&forward);
+
+        assert_eq!(dump, dump_venue_map(&reverse));
+        assert_eq!(
+            dump,
+            "1 GLBX.MDP3.GLBX -> GLBX\n2 XNAS.ITCH.XNAS -> XNAS\n65535 UNKNOWN -> TEST"
+        );
+    }
+
+    #[rstest]
+    fn test_dump_venue_map_when_empty() {
+        assert!(dump_venue_map(&IndexMap::new()).is_empty());
+    }
+
+    #[rstest]
+    fn test_describe_unmapped_when_all_mapped() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+
+        assert!(describe_unmapped(decoder.metadata(), &publisher_venue_map()).is_empty());
+    }
+
+    #[rstest]
+    fn test_describe_unmapped() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let mut metadata = decoder.metadata().clone();
+        metadata.dataset = "DBEQ.BASIC".to_string();
+
+        let unmapped = describe_unmapped(&metadata, &publisher_venue_map());
+
+        assert_eq!(
+            unmapped,
+            vec![
+                dbn::Publisher::DbeqBasicXchi as PublisherId,
+                dbn::Publisher::DbeqBasicXcis as PublisherId,
+                dbn::Publisher::DbeqBasicIexg as PublisherId,
+                dbn::Publisher::DbeqBasicEprl as PublisherId,
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_describe_unmapped_unknown_dataset() {
+        let path = PathBuf::from(format!("{TEST_DATA_PATH}/test_data.trades.dbn.zst"));
+        let decoder = Decoder::from_zstd_file(path).unwrap();
+        let mut metadata = decoder.metadata().clone();
+        metadata.dataset = "UNKNOWN.DATASET".to_string();
+
+        assert!(describe_unmapped(&metadata, &IndexMap::new()).is_empty());
+    }
 }
@@ -58,43 +58,38 @@ impl InstrumentId {
 impl FromStr for InstrumentId {
     type Err = anyhow::Error;
 
+    /// Parses an [`InstrumentId`] from its canonical `"{symbol}.{venue}"` form.
+    ///
+    /// The string is split on the last `.`, so the symbol may itself contain dots
+    /// (e.g. `"ES.c.0.GLBX"`), while the venue cannot.
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        match s.rsplit_once('.') {
-            Some((symbol_part, venue_part)) => {
-                check_valid_string(symbol_part, stringify!(value))?;
-                check_valid_string(venue_part, stringify!(value))?;
-                Ok(Self {
-                    symbol: Symbol::new(symbol_part),
-                    venue: Venue::new(venue_part),
-                })
-            }
-            None => {
-                anyhow::bail!(err_message(
-                    s,
-                    "Missing '.' separator between symbol and venue components".to_string()
-                ))
-            }
-        }
+        let (symbol_part, venue_part) = s.rsplit_once('.').ok_or_else(|| {
+            anyhow::anyhow!(err_message(
+                s,
+                "Missing '.' separator between symbol and venue components".to_string()
+            ))
+        })?;
+        check_valid_string(symbol_part, "symbol")
+            .map_err(|e| anyhow::anyhow!(err_message(s, e.to_string())))?;
+        check_valid_string(venue_part, "venue")
+            .map_err(|e| anyhow::anyhow!(err_message(s, e.to_string())))?;
+
+        Ok(Self {
+            symbol: Symbol::new(symbol_part),
+            venue: Venue::new(venue_part),
+        })
     }
 }
 
 impl From<&str> for InstrumentId {
+    /// Parses an [`InstrumentId`] with [`InstrumentId::from_str`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics:
+    /// - If `s` is not a valid `"{symbol}.{venue}"` string.
     fn from(s: &str) -> Self {
-        match s.rsplit_once('.') {
-            Some((symbol_part, venue_part)) => Self {
-                symbol: Symbol::new(symbol_part),
-                venue: Venue::new(venue_part),
-            },
-            None => {
-                panic!(
-                    "{}",
-                    err_message(
-                        s,
-                        "Missing '.' separator between symbol and venue components".to_string()
-                    )
-                )
-            }
-        }
+        Self::from_str(s).unwrap_or_else(|e| panic!("{e}"))
     }
 }
 
@@ -125,7 +120,7 @@ impl<'de> Deserialize<'de> for InstrumentId {
         D: Deserializer<'de>,
     {
         let instrument_id_str = String::deserialize(deserializer)?;
-        Ok(Self::from(instrument_id_str.as_str()))
+        Self::from_str(&instrument_id_str).map_err(serde::de::Error::custom)
     }
 }
 
@@ -139,10 +134,12 @@ fn err_message(s: &str, e: String) -> String {
 #[cfg(test)]
 mod tests {
 
+    use std::str::FromStr;
+
     use rstest::rstest;
 
     use super::InstrumentId;
-    use crate::identifiers::stubs::*;
+    use crate::identifiers::{stubs::*, Symbol, Venue};
 
     #[rstest]
     fn test_instrument_id_parse_success(instrument_id_eth_usdt_binance: InstrumentId) {
@@ -164,4 +161,47 @@ mod tests {
         assert_eq!(id.to_string(), "ETH/USDT.BINANCE");
         assert_eq!(format!("{id}"), "ETH/USDT.BINANCE");
     }
+
+    #[rstest]
+    #[case("ETHUSDT.BINANCE", "ETHUSDT", "BINANCE")]
+    #[case("ES.c.0.GLBX", "ES.c.0", "GLBX")]
+    #[case("BRK.B.XNYS", "BRK.B", "XNYS")]
+    #[case("ETH/USDT.BINANCE", "ETH/USDT", "BINANCE")]
+    fn test_from_str_round_trip(#[case] input: &str, #[case] symbol: &str, #[case] venue: &str) {
+        let id = InstrumentId::from_str(input).unwrap();
+
+        assert_eq!(
+            id,
+            InstrumentId::new(Symbol::new(symbol), Venue::new(venue))
+        );
+        assert_eq!(id.to_string().parse::<InstrumentId>().unwrap(), id);
+    }
+
+    #[rstest]
+    #[case(
+        "ESVENUE",
+        "Error parsing `InstrumentId` from 'ESVENUE': Missing '.' separator between symbol and venue components"
+    )]
+    #[case(
+        ".GLBX",
+        "Error parsing `InstrumentId` from '.GLBX': invalid string for 'symbol', was empty"
+    )]
+    #[case(
+        "ESH4.",
+        "Error parsing `InstrumentId` from 'ESH4.': invalid string for 'venue', was empty"
+    )]
+    #[case(
+        " .GLBX",
+        "Error parsing `InstrumentId` from ' .GLBX': invalid string for 'symbol', was all whitespace"
+    )]
+    fn test_from_str_malformed(#[case] input: &str, #[case] expected: &str) {
+        let result = InstrumentId::from_str(input);
+        assert_eq!(result.unwrap_err().to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_deserialize_malformed() {
+        let result = serde_json::from_str::<InstrumentId>("\".GLBX\"");
+        assert!(result.is_err());
+    }
 }
@@ -133,6 +133,28 @@ impl From<Ustr> for Symbol {
     }
 }
 
+/// Statistics for the interned string pool backing [`Symbol`] values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SymbolCacheStats {
+    /// The number of distinct interned strings.
+    pub count: usize,
+    /// The approximate memory used by the interned strings in bytes.
+    pub approx_bytes: usize,
+}
+
+/// Returns statistics for the interned string pool backing [`Symbol`] values.
+///
+/// The pool is shared by every `Ustr` backed identifier (venues, trader IDs etc.),
+/// and interned strings are never freed. A count which keeps growing during a long
+/// replay therefore indicates symbol churn.
+#[must_use]
+pub fn symbol_cache_stats() -> SymbolCacheStats {
+    SymbolCacheStats {
+        count: ustr::num_entries(),
+        approx_bytes: ustr::total_allocated(),
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Tests
 ////////////////////////////////////////////////////////////////////////////////
@@ -140,6 +162,7 @@ impl From<Ustr> for Symbol {
 mod tests {
     use rstest::rstest;
 
+    use super::symbol_cache_stats;
     use crate::identifiers::{stubs::*, Symbol};
 
     #[rstest]
@@ -180,4 +203,19 @@ mod tests {
         let symbol = Symbol::new(input);
         assert_eq!(symbol.topic(), expected_topic);
     }
+
+    #[rstest]
+    fn test_symbol_cache_stats() {
+        const N: usize = 100;
+        let before = symbol_cache_stats();
+
+        for i in 0..N {
+            let _ = Symbol::new(&format!("CACHE-STATS-TEST-{i}"));
+        }
+
+        // Other tests may intern strings concurrently
+        let after = symbol_cache_stats();
+        assert!(after.count >= before.count + N);
+        assert!(after.approx_bytes > before.approx_bytes);
+    }
 }
@@ -23,7 +23,7 @@ use std::{
 use nautilus_core::correctness::{check_valid_string, FAILED};
 use ustr::Ustr;
 
-use crate::venues::VENUE_MAP;
+use crate::venues::{is_known_mic, VENUE_MAP};
 
 pub const SYNTHETIC_VENUE: &str = "SYNTH";
 
@@ -94,6 +94,29 @@ impl Venue {
             .ok_or_else(|| anyhow::anyhow!("Unknown venue code: {code}"))
     }
 
+    /// Creates a new [`Venue`] instance from an ISO 10383 market identifier code (MIC).
+    ///
+    /// The `mic` is normalized to uppercase, and must be one of the known MICs (see
+    /// [`crate::venues::KNOWN_MICS`]). Use [`Venue::new`] for custom venues.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If `mic` is not a known market identifier code.
+    pub fn from_mic(mic: &str) -> anyhow::Result<Self> {
+        let mic = mic.trim().to_ascii_uppercase();
+        if !is_known_mic(&mic) {
+            anyhow::bail!("Unknown ISO 10383 market identifier code: '{mic}'");
+        }
+        Ok(Self(Ustr::from(&mic)))
+    }
+
+    /// Returns whether the venue is a known ISO 10383 market identifier code (MIC).
+    #[must_use]
+    pub fn is_known(&self) -> bool {
+        is_known_mic(self.as_str())
+    }
+
     #[must_use]
     pub fn synthetic() -> Self {
         // SAFETY: Unwrap safe as using known synthetic venue constant
@@ -132,4 +155,32 @@ mod tests {
         assert_eq!(venue_binance.as_str(), "BINANCE");
         assert_eq!(format!("{venue_binance}"), "BINANCE");
     }
+
+    #[rstest]
+    #[case("XNAS", "XNAS")]
+    #[case("glbx", "GLBX")]
+    #[case(" xCme ", "XCME")]
+    fn test_from_mic(#[case] mic: &str, #[case] expected: &str) {
+        let venue = Venue::from_mic(mic).unwrap();
+        assert_eq!(venue.as_str(), expected);
+        assert!(venue.is_known());
+    }
+
+    #[rstest]
+    #[case("XNA")]
+    #[case("XXXX")]
+    #[case("BINANCE")]
+    #[case("")]
+    fn test_from_mic_unknown(#[case] mic: &str) {
+        let result = Venue::from_mic(mic);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unknown ISO 10383 market identifier code"));
+    }
+
+    #[rstest]
+    fn test_is_known_custom_venue(venue_binance: Venue) {
+        assert!(!venue_binance.is_known());
+    }
 }
@@ -80,3 +80,116 @@ pub static VENUE_MAP: Lazy<Mutex<HashMap<&str, Venue>>> = Lazy::new(|| {
     map.insert(Venue::XNYM().inner().as_str(), Venue::XNYM());
     Mutex::new(map)
 });
+
+/// The ISO 10383 market identifier codes (MICs) of known venues, sorted for binary search.
+///
+/// This is a subset of the ISO 10383 registry covering the exchanges, trading
+/// platforms and trade reporting facilities which adapters commonly connect to.
+/// Codes should be added here (keeping the order) as new venues are supported.
+pub static KNOWN_MICS: &[&str] = &[
+    "AMXO", // NYSE American Options
+    "ARCO", // NYSE Arca Options
+    "ARCX", // NYSE Arca
+    "BATO", // Cboe BZX Options
+    "BATS", // Cboe BZX
+    "BATY", // Cboe BYX
+    "BVMF", // B3
+    "C2OX", // Cboe C2 Options
+    "CBCM", // CME Globex (BrokerTec)
+    "EDGA", // Cboe EDGA
+    "EDGO", // Cboe EDGX Options
+    "EDGX", // Cboe EDGX
+    "EMLD", // MIAX Emerald
+    "EPRL", // MIAX Pearl Equities
+    "FINC", // FINRA/Nasdaq TRF Chicago
+    "FINN", // FINRA/Nasdaq TRF Carteret
+    "FINY", // FINRA/NYSE TRF
+    "GLBX", // CME Globex
+    "GMNI", // Nasdaq ISE Gemini
+    "IEXG", // Investors Exchange
+    "IFEU", // ICE Futures Europe
+    "IFUS", // ICE Futures US
+    "LTSE", // Long-Term Stock Exchange
+    "MCRY", // Nasdaq ISE Mercury
+    "MEMX", // Members Exchange
+    "MPRL", // MIAX Pearl Options
+    "NDEX", // ICE Endex
+    "NYUM", // CME Globex (NYMEX)
+    "XAMS", // Euronext Amsterdam
+    "XASE", // NYSE American
+    "XASX", // ASX
+    "XBOM", // BSE
+    "XBOS", // Nasdaq BX
+    "XBOX", // BOX Options Exchange
+    "XBRU", // Euronext Brussels
+    "XCBF", // Cboe Futures Exchange
+    "XCBO", // Cboe Options
+    "XCBT", // CBOT
+    "XCEC", // COMEX
+    "XCHI", // NYSE Chicago
+    "XCIS", // NYSE National
+    "XCME", // CME
+    "XCSE", // Nasdaq Copenhagen
+    "XDUB", // Euronext Dublin
+    "XETR", // Xetra
+    "XEUR", // Eurex
+    "XFRA", // Frankfurt Stock Exchange
+    "XFXS", // CME FX Link
+    "XHEL", // Nasdaq Helsinki
+    "XHKG", // Hong Kong Exchanges
+    "XISX", // Nasdaq ISE
+    "XJSE", // Johannesburg Stock Exchange
+    "XKRX", // Korea Exchange
+    "XLIS", // Euronext Lisbon
+    "XLON", // London Stock Exchange
+    "XMAD", // Bolsa de Madrid
+    "XMEX", // Bolsa Mexicana de Valores
+    "XMIL", // Borsa Italiana
+    "XMIO", // MIAX Options
+    "XNAS", // Nasdaq
+    "XNDQ", // Nasdaq Options
+    "XNSE", // National Stock Exchange of India
+    "XNYM", // NYMEX
+    "XNYS", // NYSE
+    "XOSE", // Osaka Exchange
+    "XOSL", // Oslo Bors
+    "XPAR", // Euronext Paris
+    "XPHO", // Nasdaq PHLX Options
+    "XPSX", // Nasdaq PSX
+    "XSES", // Singapore Exchange
+    "XSHE", // Shenzhen Stock Exchange
+    "XSHG", // Shanghai Stock Exchange
+    "XSTO", // Nasdaq Stockholm
+    "XSWX", // SIX Swiss Exchange
+    "XTKS", // Tokyo Stock Exchange
+    "XTSE", // Toronto Stock Exchange
+    "XTSX", // TSX Venture Exchange
+    "XWBO", // Wiener Borse
+];
+
+/// Returns whether the `mic` is a known ISO 10383 market identifier code.
+#[must_use]
+pub fn is_known_mic(mic: &str) -> bool {
+    KNOWN_MICS.binary_search(&mic).is_ok()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn test_known_mics_sorted_and_unique() {
+        assert!(KNOWN_MICS.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[rstest]
+    fn test_venue_constants_are_known_mics() {
+        let venues = VENUE_MAP.lock().unwrap();
+        assert!(venues.keys().all(|mic| is_known_mic(mic)));
+    }
+}
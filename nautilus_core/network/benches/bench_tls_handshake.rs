@@ -0,0 +1,144 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+use std::{net::SocketAddr, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nautilus_network::tls::{default_rustls_config, tcp_tls, Connector};
+use rcgen::{CertifiedKey, KeyPair};
+use rustls::{
+    crypto::ring,
+    pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+    ClientConfig, RootCertStore, ServerConfig,
+};
+use rustls_native_certs::load_native_certs;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    runtime::Runtime,
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, stream::Mode};
+
+/// Spawns a TLS echo server with a self-signed certificate for `localhost`.
+async fn start_server() -> (SocketAddr, RootCertStore) {
+    let key_pair = KeyPair::generate().unwrap();
+    let cert = rcgen::CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .self_signed(&key_pair)
+        .unwrap();
+    let CertifiedKey { cert, key_pair } = CertifiedKey { cert, key_pair };
+
+    let mut root_store = RootCertStore::empty();
+    root_store.add(cert.der().clone()).unwrap();
+
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], key)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut stream) = acceptor.accept(stream).await {
+                    let mut buf = [0u8; 64];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    (addr, root_store)
+}
+
+/// Connects, completes the handshake and one round trip (to receive session tickets).
+async fn connect(addr: SocketAddr, connector: Connector) {
+    let request = format!("wss://localhost:{}", addr.port())
+        .into_client_request()
+        .unwrap();
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut stream = tcp_tls(&request, Mode::Tls, stream, Some(connector), None, None)
+        .await
+        .unwrap();
+    stream.write_all(b"ping").await.unwrap();
+    let mut buf = [0u8; 4];
+    stream.read_exact(&mut buf).await.unwrap();
+}
+
+fn handshake_bench(c: &mut Criterion) {
+    let _ = ring::default_provider().install_default();
+    let rt = Runtime::new().unwrap();
+    let (addr, root_store) = rt.block_on(start_server());
+
+    let mut group = c.benchmark_group("tls_handshake");
+
+    group.bench_function("cold", |b| {
+        b.iter(|| {
+            // A fresh session cache per connection forces a full handshake
+            let connector = Connector::rustls_with_session_cache(root_store.clone(), 64);
+            rt.block_on(connect(addr, connector));
+        });
+    });
+
+    let connector = Connector::rustls_with_session_cache(root_store.clone(), 64);
+    rt.block_on(connect(addr, connector.clone()));
+
+    group.bench_function("resumed", |b| {
+        b.iter(|| rt.block_on(connect(addr, connector.clone())));
+    });
+
+    group.finish();
+}
+
+fn default_config_bench(c: &mut Criterion) {
+    let _ = ring::default_provider().install_default();
+
+    let mut group = c.benchmark_group("tls_default_config");
+
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            // Loading the native certificates for every connection
+            let mut root_store = RootCertStore::empty();
+            root_store.add_parsable_certificates(load_native_certs().certs);
+            Arc::new(
+                ClientConfig::builder()
+                    .with_root_certificates(root_store)
+                    .with_no_client_auth(),
+            )
+        });
+    });
+
+    // Load the native certificates into the cache up front
+    let _ = default_rustls_config();
+
+    group.bench_function("cached", |b| {
+        b.iter(default_rustls_config);
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, handshake_bench, default_config_bench);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures_util::{SinkExt, StreamExt};
+use nautilus_network::{
+    socket::SocketOptions,
+    websocket::{BackpressurePolicy, WebSocketClient, WebSocketConfig},
+};
+use pyo3::{prelude::*, prepare_freethreaded_python, types::PyCFunction};
+use tokio::{
+    net::TcpListener,
+    runtime::Runtime,
+    sync::{mpsc, Notify},
+    task,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+/// Counts the allocations made by the benchmark.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const FRAMES: usize = 10_000;
+const FRAME_SIZE: usize = 512;
+const HEADER_SIZE: usize = 16;
+
+/// Serves a single connection, sending a burst of binary frames (as from a high-rate feed)
+/// for each frame received from the client.
+async fn serve(listener: TcpListener) {
+    let (conn, _) = listener.accept().await.unwrap();
+    let mut websocket = accept_async(conn).await.unwrap();
+    while let Some(Ok(_)) = websocket.next().await {
+        for i in 0..FRAMES {
+            let frame = Message::Binary(vec![i as u8; FRAME_SIZE]);
+            websocket.feed(frame).await.unwrap();
+        }
+        websocket.flush().await.unwrap();
+    }
+}
+
+/// Returns the config of a client connected to the server on the `port`, passing binary
+/// frames to the `handler` unless a `binary_consumer` is given.
+fn config(
+    port: u16,
+    handler: PyObject,
+    binary_consumer: Option<mpsc::Sender<Bytes>>,
+) -> WebSocketConfig {
+    WebSocketConfig {
+        url: format!("ws://127.0.0.1:{port}"),
+        handler,
+        headers: vec![],
+        heartbeat: None,
+        heartbeat_msg: None,
+        ping_handler: None,
+        heartbeat_timeout: None,
+        ping_payload: None,
+        reconnect_delay_initial_ms: None,
+        reconnect_delay_max_ms: None,
+        reconnect_jitter_ms: None,
+        reconnect_max_attempts: None,
+        connector: None,
+        socket_options: SocketOptions::default(),
+        send_queue_capacity: None,
+        backpressure_policy: BackpressurePolicy::default(),
+        parked_capacity: None,
+        parked_policy: BackpressurePolicy::default(),
+        max_message_size: None,
+        max_frame_size: None,
+        read_buffer_size: None,
+        write_buffer_size: None,
+        cancellation_token: None,
+        frame_redactor: None,
+        dns_cache: None,
+        handshake_limit: None,
+        subscription_ack_timeout_ms: None,
+        subscription_ack_parser: None,
+        binary_consumer,
+    }
+}
+
+/// A client receiving bursts of binary frames through its read task.
+struct Receiver {
+    client: WebSocketClient,
+    consumer: Option<mpsc::Receiver<Bytes>>,
+    received: Arc<AtomicUsize>,
+    burst_received: Arc<Notify>,
+}
+
+impl Receiver {
+    /// Connects a client which passes binary frames to a Python handler (copying each
+    /// payload into a Python `bytes` object), or to a binary consumer if `zero_copy`.
+    async fn connect(zero_copy: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        task::spawn(serve(listener));
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let burst_received = Arc::new(Notify::new());
+        let handler = Python::with_gil(|py| {
+            let received = received.clone();
+            let burst_received = burst_received.clone();
+            PyCFunction::new_closure_bound(py, None, None, move |_args, _kwargs| {
+                if received.fetch_add(1, Ordering::Relaxed) + 1 == FRAMES {
+                    burst_received.notify_one();
+                }
+            })
+            .unwrap()
+            .into_py(py)
+        });
+        let (tx, rx) = mpsc::channel(1024);
+        let (binary_consumer, consumer) = if zero_copy {
+            (Some(tx), Some(rx))
+        } else {
+            (None, None)
+        };
+
+        let client = WebSocketClient::connect(
+            config(port, handler, binary_consumer),
+            None,
+            None,
+            None,
+            vec![],
+            None,
+        )
+        .await
+        .unwrap();
+
+        Self {
+            client,
+            consumer,
+            received,
+            burst_received,
+        }
+    }
+
+    /// Requests a burst of frames and returns the bytes received once all are consumed.
+    async fn receive_burst(&mut self) -> usize {
+        self.received.store(0, Ordering::Relaxed);
+        self.client.send_bytes(vec![0], None).await.unwrap();
+
+        match &mut self.consumer {
+            Some(consumer) => {
+                let mut len = 0;
+                for _ in 0..FRAMES {
+                    let payload = consumer.recv().await.unwrap();
+                    len += black_box(payload.slice(HEADER_SIZE..)).len();
+                }
+                len
+            }
+            None => {
+                self.burst_received.notified().await;
+                FRAMES * (FRAME_SIZE - HEADER_SIZE)
+            }
+        }
+    }
+}
+
+/// Returns the number of allocations made while receiving a burst of frames.
+///
+/// This includes the frames sent by the in-process server, but not the Python `bytes`
+/// objects the handler is passed, which are allocated by the interpreter.
+fn count_allocations(runtime: &Runtime, receiver: &mut Receiver) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(runtime.block_on(receiver.receive_burst()));
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn binary_receive_bench(c: &mut Criterion) {
+    prepare_freethreaded_python();
+    let runtime = Runtime::new().unwrap();
+    let mut handler = runtime.block_on(Receiver::connect(false));
+    let mut consumer = runtime.block_on(Receiver::connect(true));
+
+    println!(
+        "Allocations per {FRAMES} frames: handler {}, binary consumer {}",
+        count_allocations(&runtime, &mut handler),
+        count_allocations(&runtime, &mut consumer),
+    );
+
+    let mut group = c.benchmark_group("websocket_binary_receive");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+
+    group.bench_function("handler", |b| {
+        b.iter(|| runtime.block_on(handler.receive_burst()));
+    });
+
+    group.bench_function("binary_consumer", |b| {
+        b.iter(|| runtime.block_on(consumer.receive_burst()));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, binary_receive_bench);
+criterion_main!(benches);
@@ -0,0 +1,161 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Exponential backoff for retrying failed connection attempts.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Computes the delays between consecutive retry attempts.
+///
+/// The delay starts at `delay_initial` and doubles after every attempt up to `delay_max`. A
+/// random jitter of up to `jitter_ms` milliseconds is added to each delay, so that many clients
+/// disconnected at the same time do not all retry in lockstep.
+#[derive(Clone, Debug)]
+pub struct ExponentialBackoff {
+    delay_initial: Duration,
+    delay_max: Duration,
+    delay_current: Duration,
+    jitter_ms: u64,
+    max_attempts: Option<u32>,
+    attempts: u32,
+}
+
+impl ExponentialBackoff {
+    /// Creates a new [`ExponentialBackoff`] instance.
+    ///
+    /// If `max_attempts` is `None` the backoff never gives up.
+    #[must_use]
+    pub fn new(
+        delay_initial: Duration,
+        delay_max: Duration,
+        jitter_ms: u64,
+        max_attempts: Option<u32>,
+    ) -> Self {
+        Self {
+            delay_initial,
+            delay_max,
+            delay_current: delay_initial.min(delay_max),
+            jitter_ms,
+            max_attempts,
+            attempts: 0,
+        }
+    }
+
+    /// Returns the delay to wait before the next attempt, or `None` once the maximum number
+    /// of attempts has been reached.
+    pub fn next_duration(&mut self) -> Option<Duration> {
+        if self
+            .max_attempts
+            .is_some_and(|max_attempts| self.attempts >= max_attempts)
+        {
+            return None;
+        }
+        self.attempts += 1;
+
+        let jitter = rand::thread_rng().gen_range(0..=self.jitter_ms);
+        let delay = self.delay_current + Duration::from_millis(jitter);
+        self.delay_current = self.delay_current.saturating_mul(2).min(self.delay_max);
+
+        Some(delay)
+    }
+
+    /// Returns the number of attempts made since the last reset.
+    #[must_use]
+    pub const fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Resets the delay and attempts, e.g. after a successful connection.
+    pub fn reset(&mut self) {
+        self.delay_current = self.delay_initial.min(self.delay_max);
+        self.attempts = 0;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn test_next_duration_doubles_up_to_max() {
+        let mut backoff = ExponentialBackoff::new(
+            Duration::from_millis(100),
+            Duration::from_millis(500),
+            0,
+            None,
+        );
+
+        let delays: Vec<u128> = (0..5)
+            .map(|_| backoff.next_duration().unwrap().as_millis())
+            .collect();
+
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+        assert_eq!(backoff.attempts(), 5);
+    }
+
+    #[rstest]
+    fn test_next_duration_adds_jitter() {
+        let mut backoff = ExponentialBackoff::new(
+            Duration::from_millis(100),
+            Duration::from_millis(100),
+            50,
+            None,
+        );
+
+        for _ in 0..100 {
+            let delay = backoff.next_duration().unwrap();
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(150));
+        }
+    }
+
+    #[rstest]
+    fn test_next_duration_stops_after_max_attempts() {
+        let mut backoff = ExponentialBackoff::new(
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+            0,
+            Some(2),
+        );
+
+        assert!(backoff.next_duration().is_some());
+        assert!(backoff.next_duration().is_some());
+        assert!(backoff.next_duration().is_none());
+    }
+
+    #[rstest]
+    fn test_reset() {
+        let mut backoff = ExponentialBackoff::new(
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+            0,
+            Some(2),
+        );
+        backoff.next_duration();
+        backoff.next_duration();
+
+        backoff.reset();
+
+        assert_eq!(backoff.attempts(), 0);
+        assert_eq!(backoff.next_duration(), Some(Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,318 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Caching of DNS resolutions for the connect path.
+//!
+//! Clients reconnecting to a venue resolve the same hostname over and over. A [`DnsCache`]
+//! shared by clients keeps the resolved addresses for a TTL, and briefly remembers failed
+//! resolutions (e.g. NXDOMAIN) so a client retrying in a loop does not hammer the resolver.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use tokio::net::{lookup_host, TcpStream};
+
+use crate::happy_eyeballs::connect_addrs;
+
+/// The default time successful resolutions are cached for.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// The default time failed resolutions are cached for.
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+/// Resolves a host and port to socket addresses.
+pub trait Resolver: Debug + Send + Sync {
+    /// Resolves the `host` and `port` to the addresses to connect to.
+    fn resolve(&self, host: &str, port: u16) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>>;
+}
+
+/// Resolves hosts with the operating system resolver.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str, port: u16) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+        let host = host.to_string();
+        Box::pin(async move { Ok(lookup_host((host, port)).await?.collect()) })
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    expires_at: Instant,
+    result: Result<Vec<SocketAddr>, (ErrorKind, String)>,
+}
+
+/// Caches the addresses hosts resolve to for a TTL.
+///
+/// Failed resolutions are cached for the (shorter) negative TTL, during which resolving the
+/// host again returns the same error without asking the resolver.
+#[derive(Debug)]
+pub struct DnsCache {
+    resolver: Arc<dyn Resolver>,
+    ttl: Duration,
+    negative_ttl: Duration,
+    entries: Mutex<HashMap<(String, u16), CacheEntry>>,
+}
+
+impl Default for DnsCache {
+    /// Creates a new cache resolving with the [`SystemResolver`] and the default TTLs.
+    fn default() -> Self {
+        Self::new(Arc::new(SystemResolver), DEFAULT_TTL, DEFAULT_NEGATIVE_TTL)
+    }
+}
+
+impl DnsCache {
+    /// Creates a new [`DnsCache`] instance.
+    #[must_use]
+    pub fn new(resolver: Arc<dyn Resolver>, ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            resolver,
+            ttl,
+            negative_ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the addresses the `host` and `port` resolve to, from the cache while the
+    /// cached resolution has not expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the resolution fails (or a cached resolution failed), or resolves
+    /// to no addresses.
+    pub async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let key = (host.to_string(), port);
+        if let Some(result) = self.cached(&key) {
+            tracing::debug!("Resolved {host}:{port} from cache");
+            return result;
+        }
+
+        let result = match self.resolver.resolve(host, port).await {
+            Ok(addrs) if addrs.is_empty() => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("No addresses resolved for {host}:{port}"),
+            )),
+            result => result,
+        };
+        let (ttl, entry) = match &result {
+            Ok(addrs) => (self.ttl, Ok(addrs.clone())),
+            Err(e) => (self.negative_ttl, Err((e.kind(), e.to_string()))),
+        };
+        self.entries.lock().unwrap().insert(
+            key,
+            CacheEntry {
+                expires_at: Instant::now() + ttl,
+                result: entry,
+            },
+        );
+        result
+    }
+
+    /// Resolves the `host` and `port` (see [`DnsCache::resolve`]) and connects to the first of
+    /// its addresses to accept the connection (see [`connect_addrs`]).
+    ///
+    /// The cached resolution is evicted if no address accepts the connection, so the next
+    /// attempt resolves the host again (e.g. after the venue moved addresses).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host cannot be resolved, or no address accepts the connection.
+    pub async fn connect(
+        &self,
+        host: &str,
+        port: u16,
+        attempt_delay: Duration,
+    ) -> io::Result<TcpStream> {
+        let addrs = self.resolve(host, port).await?;
+        let result = connect_addrs(&addrs, attempt_delay).await;
+        if result.is_err() {
+            self.evict(host, port);
+        }
+        result
+    }
+
+    /// Removes any cached resolution of the `host` and `port`.
+    pub fn evict(&self, host: &str, port: u16) {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(host.to_string(), port));
+    }
+
+    fn cached(&self, key: &(String, u16)) -> Option<io::Result<Vec<SocketAddr>>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(key)?;
+        if entry.expires_at <= Instant::now() {
+            entries.remove(key);
+            return None;
+        }
+        Some(match &entry.result {
+            Ok(addrs) => Ok(addrs.clone()),
+            Err((kind, msg)) => Err(io::Error::new(*kind, msg.clone())),
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::{net::TcpListener, time::sleep};
+
+    use super::*;
+    use crate::happy_eyeballs::DEFAULT_ATTEMPT_DELAY;
+
+    /// Resolves every host to the `addrs` (fails as NXDOMAIN if empty), counting lookups.
+    #[derive(Debug, Default)]
+    struct StubResolver {
+        addrs: Vec<SocketAddr>,
+        lookups: AtomicUsize,
+    }
+
+    impl StubResolver {
+        fn new(addrs: Vec<SocketAddr>) -> Arc<Self> {
+            Arc::new(Self {
+                addrs,
+                lookups: AtomicUsize::new(0),
+            })
+        }
+
+        fn lookups(&self) -> usize {
+            self.lookups.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Resolver for StubResolver {
+        fn resolve(
+            &self,
+            host: &str,
+            _port: u16,
+        ) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            let result = if self.addrs.is_empty() {
+                Err(io::Error::new(
+                    ErrorKind::Other,
+                    format!("failed to lookup address information: {host}: NXDOMAIN"),
+                ))
+            } else {
+                Ok(self.addrs.clone())
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_hits_cache_within_ttl() {
+        let addr: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let resolver = StubResolver::new(vec![addr]);
+        let cache = DnsCache::new(
+            resolver.clone(),
+            Duration::from_secs(60),
+            DEFAULT_NEGATIVE_TTL,
+        );
+
+        for _ in 0..3 {
+            let addrs = cache.resolve("venue.example", 443).await.unwrap();
+            assert_eq!(addrs, vec![addr]);
+        }
+        cache.resolve("venue.example", 8443).await.unwrap();
+
+        // One lookup per distinct host and port
+        assert_eq!(resolver.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_after_ttl_expired() {
+        let resolver = StubResolver::new(vec!["10.0.0.1:443".parse().unwrap()]);
+        let cache = DnsCache::new(
+            resolver.clone(),
+            Duration::from_millis(50),
+            DEFAULT_NEGATIVE_TTL,
+        );
+
+        cache.resolve("venue.example", 443).await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+        cache.resolve("venue.example", 443).await.unwrap();
+
+        assert_eq!(resolver.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_caches_failure_for_negative_ttl() {
+        let resolver = StubResolver::new(vec![]);
+        let cache = DnsCache::new(resolver.clone(), DEFAULT_TTL, Duration::from_millis(50));
+
+        for _ in 0..3 {
+            let err = cache.resolve("missing.example", 443).await.unwrap_err();
+            assert!(err.to_string().contains("NXDOMAIN"));
+        }
+        assert_eq!(resolver.lookups(), 1);
+
+        sleep(Duration::from_millis(100)).await;
+        cache.resolve("missing.example", 443).await.unwrap_err();
+
+        assert_eq!(resolver.lookups(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_connect_hits_cache() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let resolver = StubResolver::new(vec![addr]);
+        let cache = DnsCache::new(resolver.clone(), DEFAULT_TTL, DEFAULT_NEGATIVE_TTL);
+
+        for _ in 0..2 {
+            let stream = cache
+                .connect("venue.example", addr.port(), DEFAULT_ATTEMPT_DELAY)
+                .await
+                .unwrap();
+            assert_eq!(stream.peer_addr().unwrap(), addr);
+        }
+
+        assert_eq!(resolver.lookups(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connect_evicts_when_refused() {
+        // Bind then drop the listener, so its port refuses connections
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let resolver = StubResolver::new(vec![addr]);
+        let cache = DnsCache::new(resolver.clone(), DEFAULT_TTL, DEFAULT_NEGATIVE_TTL);
+
+        for _ in 0..2 {
+            let result = cache
+                .connect("venue.example", addr.port(), DEFAULT_ATTEMPT_DELAY)
+                .await;
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+        }
+
+        assert_eq!(resolver.lookups(), 2);
+    }
+}
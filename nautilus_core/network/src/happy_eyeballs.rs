@@ -0,0 +1,204 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Happy eyeballs (RFC 8305) TCP connection establishment.
+//!
+//! Venue hostnames often resolve to both IPv4 and IPv6 addresses. Rather than waiting for a
+//! connection attempt to an unreachable address to time out before trying the next, attempts
+//! are raced: a new attempt starts whenever the previous one fails, or has not completed
+//! within the attempt delay, and the first connection established wins.
+
+use std::{
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use tokio::{
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+    time::sleep,
+};
+
+/// The default delay before starting the next connection attempt (as recommended by RFC 8305).
+pub const DEFAULT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves the `host` (e.g. `("example.com", 443)` or `"example.com:443"`) and connects to
+/// the first of its addresses to accept the connection, racing attempts started every
+/// `attempt_delay` (see [`connect_addrs`]).
+///
+/// # Errors
+///
+/// Returns an error if the `host` cannot be resolved, or no address accepts the connection.
+pub async fn connect_host(
+    host: impl ToSocketAddrs,
+    attempt_delay: Duration,
+) -> io::Result<TcpStream> {
+    let addrs: Vec<SocketAddr> = lookup_host(host).await?.collect();
+    tracing::debug!("Resolved addresses {addrs:?}");
+    connect_addrs(&addrs, attempt_delay).await
+}
+
+/// Connects to the first of the given `addrs` to accept the connection.
+///
+/// The addresses are interleaved by family, starting with the family of the first address,
+/// so an unreachable family only delays the connection by a single `attempt_delay`. The next
+/// attempt starts as soon as the previous attempt fails, or after the `attempt_delay` while
+/// it is still in progress. Attempts still in progress once a connection is established
+/// are dropped.
+///
+/// # Errors
+///
+/// Returns an error if `addrs` is empty, or the error of the last failed attempt if no
+/// address accepts the connection.
+pub async fn connect_addrs(addrs: &[SocketAddr], attempt_delay: Duration) -> io::Result<TcpStream> {
+    let mut pending = interleave_families(addrs).into_iter();
+    let Some(first) = pending.next() else {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "No addresses to connect to",
+        ));
+    };
+
+    let mut attempts = FuturesUnordered::new();
+    attempts.push(connect_addr(first));
+
+    loop {
+        tokio::select! {
+            Some((addr, result)) = attempts.next() => match result {
+                Ok(stream) => {
+                    tracing::debug!("Connected to {addr}");
+                    return Ok(stream);
+                }
+                Err(e) => {
+                    tracing::debug!("Connection attempt to {addr} failed: {e}");
+                    match pending.next() {
+                        Some(next) => attempts.push(connect_addr(next)),
+                        None if attempts.is_empty() => return Err(e),
+                        None => {}
+                    }
+                }
+            },
+            () = sleep(attempt_delay), if pending.len() > 0 => {
+                if let Some(next) = pending.next() {
+                    attempts.push(connect_addr(next));
+                }
+            }
+        }
+    }
+}
+
+async fn connect_addr(addr: SocketAddr) -> (SocketAddr, io::Result<TcpStream>) {
+    (addr, TcpStream::connect(addr).await)
+}
+
+/// Returns the `addrs` interleaved by address family, starting with the family of the
+/// first address and otherwise preserving the resolved order.
+fn interleave_families(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return Vec::new();
+    };
+    let (preferred, other): (Vec<SocketAddr>, Vec<SocketAddr>) = addrs
+        .iter()
+        .partition(|addr| addr.is_ipv6() == first.is_ipv6());
+
+    let mut interleaved = Vec::with_capacity(addrs.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => break,
+            (a, b) => interleaved.extend(a.into_iter().chain(b)),
+        }
+    }
+    interleaved
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tokio::{net::TcpListener, time::Instant};
+
+    use super::*;
+
+    #[rstest]
+    fn test_interleave_families() {
+        let addrs: Vec<SocketAddr> = ["[::1]:1", "[::2]:1", "[::3]:1", "10.0.0.1:1", "10.0.0.2:1"]
+            .iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect();
+
+        let interleaved: Vec<String> = interleave_families(&addrs)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(
+            interleaved,
+            vec!["[::1]:1", "10.0.0.1:1", "[::2]:1", "10.0.0.2:1", "[::3]:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_addrs_when_first_address_unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap();
+        // A TEST-NET-1 address (RFC 5737), which is never routed
+        let unreachable: SocketAddr = "192.0.2.1:443".parse().unwrap();
+
+        let start = Instant::now();
+        let stream = connect_addrs(&[unreachable, reachable], Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        assert_eq!(stream.peer_addr().unwrap(), reachable);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_connect_addrs_when_all_refused() {
+        // Bind then drop listeners, so their ports refuse connections
+        let mut addrs = Vec::new();
+        for _ in 0..2 {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            addrs.push(listener.local_addr().unwrap());
+        }
+
+        let result = connect_addrs(&addrs, Duration::from_millis(50)).await;
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_connect_addrs_when_empty() {
+        let result = connect_addrs(&[], DEFAULT_ATTEMPT_DELAY).await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_connect_host() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let stream = connect_host(("127.0.0.1", port), DEFAULT_ATTEMPT_DELAY)
+            .await
+            .unwrap();
+
+        assert_eq!(stream.peer_addr().unwrap().port(), port);
+    }
+}
@@ -20,19 +20,26 @@ use std::{
     error::Error,
     fmt::Display,
     hash::{Hash, Hasher},
-    sync::Arc,
-    time::Duration,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
 
 use bytes::Bytes;
 use futures_util::{stream, StreamExt};
+use nautilus_core::time::get_atomic_clock_realtime;
 use pyo3::{exceptions::PyException, prelude::*, types::PyBytes};
 use reqwest::{
-    header::{HeaderMap, HeaderName},
-    Method, Response, Url,
+    header::{HeaderMap, HeaderName, ACCEPT_ENCODING, CONTENT_ENCODING, RETRY_AFTER},
+    Method, Request, Response, StatusCode, Url,
 };
+use ring::hmac;
 
-use crate::ratelimiter::{clock::MonotonicClock, quota::Quota, RateLimiter};
+use crate::{
+    backoff::ExponentialBackoff,
+    ratelimiter::{clock::MonotonicClock, quota::Quota, RateLimiter},
+    tls::Connector,
+};
 
 /// Represents the HTTP methods supported by the `HttpClient`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -79,6 +86,20 @@ pub struct HttpResponse {
     pub(crate) body: Bytes,
 }
 
+impl HttpResponse {
+    /// Returns the retained response headers.
+    #[must_use]
+    pub const fn headers(&self) -> &HashMap<String, String> {
+        &self.headers
+    }
+
+    /// Returns the body of the response.
+    #[must_use]
+    pub const fn body(&self) -> &Bytes {
+        &self.body
+    }
+}
+
 /// A high-performance HTTP client with rate limiting and timeout capabilities.
 ///
 /// This struct is designed to handle HTTP requests efficiently, providing
@@ -96,6 +117,408 @@ pub struct HttpClient {
     pub(crate) client: InnerHttpClient,
 }
 
+impl HttpClient {
+    /// Creates a new [`HttpClient`] instance.
+    ///
+    /// TLS connections are established with the `connector`, so the CA and client
+    /// certificate configuration can be shared with the websocket clients. The
+    /// default `reqwest` TLS configuration is used if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying client cannot be built with the `connector`.
+    pub fn new(
+        header_keys: Vec<String>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+        connector: Option<Connector>,
+    ) -> Result<Self, HttpClientError> {
+        let client = match connector {
+            Some(connector) => InnerHttpClient::with_connector(connector, header_keys)?,
+            None => InnerHttpClient {
+                client: reqwest::Client::new(),
+                header_keys,
+                signer: None,
+                retry_policy: None,
+                decompress: true,
+            },
+        };
+        let rate_limiter = Arc::new(RateLimiter::new_with_quota(default_quota, keyed_quotas));
+
+        Ok(Self {
+            rate_limiter,
+            client,
+        })
+    }
+
+    /// Creates a new [`HttpClient`] instance which sends its requests over the connections of
+    /// the `pool`, reusing the idle connections of every other client created from the `pool`
+    /// with the same `connector`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying client cannot be built with the `connector`.
+    pub fn new_with_pool(
+        header_keys: Vec<String>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+        connector: Option<Connector>,
+        pool: &ConnectionPool,
+    ) -> Result<Self, HttpClientError> {
+        let client = InnerHttpClient {
+            client: pool.client(connector)?,
+            header_keys,
+            signer: None,
+            retry_policy: None,
+            decompress: true,
+        };
+        let rate_limiter = Arc::new(RateLimiter::new_with_quota(default_quota, keyed_quotas));
+
+        Ok(Self {
+            rate_limiter,
+            client,
+        })
+    }
+
+    /// Sets the `signer` which signs every request before it is sent.
+    #[must_use]
+    pub fn with_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.client.signer = Some(signer);
+        self
+    }
+
+    /// Sets the `retry_policy` for requests throttled by the server.
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.client.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets whether gzip and brotli encoded response bodies are decoded (enabled by default),
+    /// otherwise bodies are returned as received.
+    #[must_use]
+    pub const fn with_decompression(mut self, decompress: bool) -> Self {
+        self.client.decompress = decompress;
+        self
+    }
+
+    /// Sends an HTTP request once the rate limiting quota of every key in `keys` allows it.
+    ///
+    /// See [`InnerHttpClient::send_request`] for the other parameters.
+    pub async fn request(
+        &self,
+        method: Method,
+        url: String,
+        headers: HashMap<String, String>,
+        body: Option<Vec<u8>>,
+        keys: Vec<String>,
+        timeout_secs: Option<u64>,
+    ) -> Result<HttpResponse, HttpClientError> {
+        for key in &keys {
+            self.rate_limiter.until_key_ready(key).await;
+        }
+        self.client
+            .send_request(method, url, headers, body, timeout_secs)
+            .await
+    }
+}
+
+/// Signs HTTP requests before they are sent, e.g. to authenticate with an exchange.
+///
+/// The signer is called with the fully built request (URL, headers and body), and
+/// may add query parameters or headers to it.
+pub trait RequestSigner: Send + Sync {
+    /// Adds the authentication to the `req`.
+    fn sign(&self, req: &mut Request);
+}
+
+/// A [`RequestSigner`] which signs the query string and body with HMAC-SHA256.
+///
+/// A timestamp nonce (milliseconds since the UNIX epoch) is appended to the query
+/// string, which is then signed together with the body. The hex encoded signature
+/// is appended as the last query parameter, as required by e.g. Binance.
+pub struct HmacSha256Signer {
+    key: hmac::Key,
+    timestamp_param: String,
+    signature_param: String,
+}
+
+impl HmacSha256Signer {
+    /// Creates a new [`HmacSha256Signer`] instance keyed by the `secret`.
+    ///
+    /// The `timestamp` and `signature` query parameter names are used by default.
+    #[must_use]
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            timestamp_param: "timestamp".to_string(),
+            signature_param: "signature".to_string(),
+        }
+    }
+
+    /// Sets the name of the timestamp query parameter.
+    #[must_use]
+    pub fn with_timestamp_param(mut self, name: &str) -> Self {
+        self.timestamp_param = name.to_string();
+        self
+    }
+
+    /// Sets the name of the signature query parameter.
+    #[must_use]
+    pub fn with_signature_param(mut self, name: &str) -> Self {
+        self.signature_param = name.to_string();
+        self
+    }
+
+    /// Returns the hex encoded HMAC-SHA256 signature of the `payload`.
+    #[must_use]
+    pub fn signature(&self, payload: &[u8]) -> String {
+        hex::encode(hmac::sign(&self.key, payload).as_ref())
+    }
+
+    /// Signs the `req` with the given `timestamp_ms` nonce.
+    pub fn sign_with_timestamp(&self, req: &mut Request, timestamp_ms: u64) {
+        req.url_mut()
+            .query_pairs_mut()
+            .append_pair(&self.timestamp_param, &timestamp_ms.to_string());
+
+        let mut payload = req.url().query().unwrap_or_default().as_bytes().to_vec();
+        if let Some(body) = req.body().and_then(|body| body.as_bytes()) {
+            payload.extend_from_slice(body);
+        }
+
+        let signature = self.signature(&payload);
+        req.url_mut()
+            .query_pairs_mut()
+            .append_pair(&self.signature_param, &signature);
+    }
+}
+
+impl RequestSigner for HmacSha256Signer {
+    fn sign(&self, req: &mut Request) {
+        let timestamp_ms = get_atomic_clock_realtime().get_time_ms();
+        self.sign_with_timestamp(req, timestamp_ms);
+    }
+}
+
+/// The policy for retrying requests which the server rejected with HTTP 429 (Too Many
+/// Requests) or 503 (Service Unavailable).
+///
+/// A retry waits for the delay given by the `Retry-After` header of the response,
+/// or for an exponential backoff delay if the header is absent. The `Retry-After` delay
+/// is capped at `retry_after_max` (60 seconds by default), so a server cannot stall a
+/// request indefinitely. Only requests with idempotent methods are retried, unless
+/// configured otherwise.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    delay_initial: Duration,
+    delay_max: Duration,
+    retry_after_max: Duration,
+    retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    pub const DELAY_INITIAL_MS: u64 = 500;
+    pub const DELAY_MAX_MS: u64 = 10_000;
+    pub const RETRY_AFTER_MAX_MS: u64 = 60_000;
+    pub const JITTER_MS: u64 = 100;
+
+    /// Creates a new [`RetryPolicy`] instance which retries a request up to `max_retries` times.
+    #[must_use]
+    pub const fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            delay_initial: Duration::from_millis(Self::DELAY_INITIAL_MS),
+            delay_max: Duration::from_millis(Self::DELAY_MAX_MS),
+            retry_after_max: Duration::from_millis(Self::RETRY_AFTER_MAX_MS),
+            retry_non_idempotent: false,
+        }
+    }
+
+    /// Sets the backoff delays used when a response has no `Retry-After` header.
+    #[must_use]
+    pub const fn with_delays(mut self, delay_initial: Duration, delay_max: Duration) -> Self {
+        self.delay_initial = delay_initial;
+        self.delay_max = delay_max;
+        self
+    }
+
+    /// Sets the maximum delay waited for when a response has a `Retry-After` header.
+    #[must_use]
+    pub const fn with_retry_after_max(mut self, retry_after_max: Duration) -> Self {
+        self.retry_after_max = retry_after_max;
+        self
+    }
+
+    /// Sets whether requests with non-idempotent methods (e.g. `POST`) are retried.
+    #[must_use]
+    pub const fn with_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    /// Returns whether requests with the `method` are retried.
+    #[must_use]
+    pub fn allows(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || method.is_idempotent()
+    }
+
+    /// Returns the delay before retrying a response with the `headers`, given the
+    /// `backoff_delay` used when the response has no `Retry-After` header.
+    fn delay(&self, headers: &HeaderMap, backoff_delay: Duration) -> Duration {
+        retry_after(headers).map_or(backoff_delay, |delay| delay.min(self.retry_after_max))
+    }
+
+    fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff::new(
+            self.delay_initial,
+            self.delay_max,
+            Self::JITTER_MS,
+            Some(self.max_retries),
+        )
+    }
+}
+
+/// Returns whether a response with the `status` may be retried.
+const fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    )
+}
+
+/// Returns the delay requested by the `Retry-After` header, given either in seconds or
+/// as an HTTP-date.
+#[must_use]
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// The configuration of the keep-alive connections held by a [`ConnectionPool`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// How long an idle connection is kept before it is closed (`None` keeps it indefinitely).
+    pub idle_timeout: Option<Duration>,
+    /// The maximum number of idle connections kept per host.
+    pub max_idle_per_host: usize,
+}
+
+impl PoolConfig {
+    pub const IDLE_TIMEOUT_SECS: u64 = 90;
+
+    /// Creates a new [`PoolConfig`] instance.
+    #[must_use]
+    pub const fn new(idle_timeout: Option<Duration>, max_idle_per_host: usize) -> Self {
+        Self {
+            idle_timeout,
+            max_idle_per_host,
+        }
+    }
+
+    fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder
+            .pool_idle_timeout(self.idle_timeout)
+            .pool_max_idle_per_host(self.max_idle_per_host)
+    }
+}
+
+impl Default for PoolConfig {
+    /// Creates a new default [`PoolConfig`] instance, keeping idle connections for 90 seconds
+    /// without limiting their number per host.
+    fn default() -> Self {
+        Self::new(
+            Some(Duration::from_secs(Self::IDLE_TIMEOUT_SECS)),
+            usize::MAX,
+        )
+    }
+}
+
+/// A pool of keep-alive connections shared by the HTTP clients created from it.
+///
+/// Idle connections are keyed by host, port and connector: a request reuses an idle
+/// connection to the same host and port which was established with the same connector
+/// (connectors cloned from one another share their TLS configuration, and so their
+/// connections). A connection closed by the server while idle is detected and discarded
+/// rather than reused, and a request sent on a connection which is closed concurrently is
+/// retried on a new connection.
+#[derive(Debug, Default)]
+pub struct ConnectionPool {
+    config: PoolConfig,
+    clients: Mutex<HashMap<usize, (Option<Connector>, reqwest::Client)>>,
+}
+
+impl ConnectionPool {
+    /// Creates a new [`ConnectionPool`] instance.
+    #[must_use]
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the configuration of the pooled connections.
+    #[must_use]
+    pub const fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Returns the client holding the pooled connections of the `connector`, building it
+    /// on first use.
+    fn client(&self, connector: Option<Connector>) -> Result<reqwest::Client, HttpClientError> {
+        let key = connector_key(connector.as_ref());
+        let mut clients = self.clients.lock().expect("Pool lock poisoned");
+        if let Some((_, client)) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let mut builder = self.config.apply(reqwest::Client::builder());
+        if let Some(connector) = connector.clone() {
+            builder = tls_builder(builder, connector);
+        }
+        let client = builder.build().map_err(HttpClientError::from)?;
+        // The connector is retained so its address cannot be reused as the key of another
+        clients.insert(key, (connector, client.clone()));
+
+        Ok(client)
+    }
+}
+
+/// Returns the key identifying the TLS configuration of the `connector` (by address, so
+/// clones of a connector share the key), with `0` for the default `reqwest` configuration.
+fn connector_key(connector: Option<&Connector>) -> usize {
+    match connector {
+        None | Some(Connector::Plain) => 0,
+        Some(Connector::Rustls(config)) => Arc::as_ptr(config) as usize,
+        #[cfg(feature = "native-tls")]
+        Some(Connector::NativeTls(connector)) => Arc::as_ptr(connector) as usize,
+        #[cfg(feature = "native-tls")]
+        Some(Connector::AutoFallback(config, _)) => Arc::as_ptr(config) as usize,
+    }
+}
+
+/// Configures the `builder` to establish TLS connections with the `connector`.
+fn tls_builder(builder: reqwest::ClientBuilder, connector: Connector) -> reqwest::ClientBuilder {
+    match connector {
+        Connector::Plain => builder,
+        Connector::Rustls(config) => builder.use_preconfigured_tls((*config).clone()),
+        #[cfg(feature = "native-tls")]
+        Connector::NativeTls(connector) => builder.use_preconfigured_tls((*connector).clone()),
+        #[cfg(feature = "native-tls")]
+        Connector::AutoFallback(config, _) => builder.use_preconfigured_tls((*config).clone()),
+    }
+}
+
 /// Represents errors that can occur when using the `HttpClient`.
 ///
 /// This enum provides variants for general HTTP errors and timeout errors,
@@ -133,13 +556,48 @@ impl From<String> for HttpClientError {
 ///
 /// The client returns an [`HttpResponse`]. The client filters only the key value
 /// for the give `header_keys`.
+///
+/// If a `signer` is set every request is signed before it is sent, and if a
+/// `retry_policy` is set throttled requests are retried (and signed again).
+///
+/// If `decompress` is set, requests accept gzip and brotli encoded responses (unless the
+/// `Accept-Encoding` header is given), and encoded bodies are decoded as they are received
+/// according to their `Content-Encoding`.
 #[derive(Clone)]
 pub struct InnerHttpClient {
     pub(crate) client: reqwest::Client,
     pub(crate) header_keys: Vec<String>,
+    pub(crate) signer: Option<Arc<dyn RequestSigner>>,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) decompress: bool,
 }
 
 impl InnerHttpClient {
+    /// Creates a new [`InnerHttpClient`] which establishes TLS connections with the `connector`.
+    ///
+    /// A [`Connector::Plain`] keeps the default `reqwest` TLS configuration, since
+    /// only `http` URLs are sent without TLS.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client cannot be built with the `connector`.
+    pub fn with_connector(
+        connector: Connector,
+        header_keys: Vec<String>,
+    ) -> Result<Self, HttpClientError> {
+        let client = tls_builder(reqwest::Client::builder(), connector)
+            .build()
+            .map_err(HttpClientError::from)?;
+
+        Ok(Self {
+            client,
+            header_keys,
+            signer: None,
+            retry_policy: None,
+            decompress: true,
+        })
+    }
+
     /// Sends an HTTP request with the specified method, URL, headers, and body.
     ///
     /// - `method`: The HTTP method to use (e.g., GET, POST).
@@ -170,6 +628,16 @@ impl InnerHttpClient {
             );
         }
 
+        if self.decompress && !header_map.contains_key(ACCEPT_ENCODING) {
+            header_map.insert(ACCEPT_ENCODING, ACCEPT_ENCODING_DECODED.parse().unwrap());
+        }
+
+        let mut retry = self
+            .retry_policy
+            .as_ref()
+            .filter(|policy| policy.allows(&method))
+            .map(|policy| (policy, policy.backoff()));
+
         let mut request_builder = self.client.request(method, reqwest_url).headers(header_map);
 
         if let Some(timeout_secs) = timeout_secs {
@@ -184,15 +652,40 @@ impl InnerHttpClient {
             None => request_builder.build().map_err(HttpClientError::from)?,
         };
 
-        tracing::trace!("{request:?}");
+        loop {
+            let mut attempt = request
+                .try_clone()
+                .ok_or_else(|| HttpClientError::from("Request cannot be cloned".to_string()))?;
 
-        let response = self
-            .client
-            .execute(request)
-            .await
-            .map_err(HttpClientError::from)?;
+            if let Some(signer) = &self.signer {
+                signer.sign(&mut attempt);
+            }
+
+            tracing::trace!("{attempt:?}");
+
+            let response = self
+                .client
+                .execute(attempt)
+                .await
+                .map_err(HttpClientError::from)?;
+
+            if let Some((policy, backoff)) =
+                retry.as_mut().filter(|_| is_retryable(response.status()))
+            {
+                if let Some(delay) = backoff.next_duration() {
+                    let delay = policy.delay(response.headers(), delay);
+                    tracing::warn!(
+                        "Received {}, retry {} in {delay:?}",
+                        response.status(),
+                        backoff.attempts()
+                    );
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            }
 
-        self.to_response(response).await
+            return self.to_response(response).await;
+        }
     }
 
     /// Converts a `reqwest::Response` into an `HttpResponse`.
@@ -207,7 +700,11 @@ impl InnerHttpClient {
             .map(|(k, v)| (k.clone(), v.to_owned()))
             .collect();
         let status = response.status().as_u16();
-        let body = response.bytes().await.map_err(HttpClientError::from)?;
+        let body = if self.decompress {
+            decode_body(response).await?
+        } else {
+            response.bytes().await.map_err(HttpClientError::from)?
+        };
 
         Ok(HttpResponse {
             status,
@@ -226,24 +723,127 @@ impl Default for InnerHttpClient {
         Self {
             client,
             header_keys: Default::default(),
+            signer: None,
+            retry_policy: None,
+            decompress: true,
         }
     }
 }
 
+/// The `Accept-Encoding` of requests sent by clients decoding response bodies.
+const ACCEPT_ENCODING_DECODED: &str = "gzip, br";
+
+/// Decodes a response body as its chunks are received.
+enum BodyDecoder {
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+}
+
+impl BodyDecoder {
+    /// Returns the decoder for the `Content-Encoding` of the `headers`, or `None` if the body
+    /// is not encoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the encoding is not supported (including a body encoded more than
+    /// once).
+    fn new(headers: &HeaderMap) -> Result<Option<Self>, HttpClientError> {
+        let Some(value) = headers.get(CONTENT_ENCODING) else {
+            return Ok(None);
+        };
+        let encoding = value
+            .to_str()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match encoding.as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Self::Gzip(flate2::write::GzDecoder::new(Vec::new())))),
+            "br" => Ok(Some(Self::Brotli(Box::new(
+                brotli::DecompressorWriter::new(Vec::new(), 4096),
+            )))),
+            _ => Err(HttpClientError::from(format!(
+                "Unsupported response `Content-Encoding` '{}'",
+                String::from_utf8_lossy(value.as_bytes())
+            ))),
+        }
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip(_) => "gzip",
+            Self::Brotli(_) => "brotli",
+        }
+    }
+
+    fn write(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Gzip(decoder) => decoder.write_all(chunk),
+            Self::Brotli(decoder) => decoder.write_all(chunk),
+        }
+    }
+
+    /// Returns the decoded body, failing if the encoded stream is incomplete.
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip(decoder) => decoder.finish(),
+            Self::Brotli(mut decoder) => {
+                decoder.close()?;
+                decoder.into_inner().map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "Incomplete brotli stream",
+                    )
+                })
+            }
+        }
+    }
+}
+
+/// Reads the body of the `response`, decoding it chunk by chunk according to its
+/// `Content-Encoding` (see [`BodyDecoder`]).
+async fn decode_body(mut response: Response) -> Result<Bytes, HttpClientError> {
+    let Some(mut decoder) = BodyDecoder::new(response.headers())? else {
+        return response.bytes().await.map_err(HttpClientError::from);
+    };
+    let decode_error =
+        |name: &str, e| HttpClientError::from(format!("Error decoding {name} response body: {e}"));
+
+    while let Some(chunk) = response.chunk().await.map_err(HttpClientError::from)? {
+        decoder
+            .write(&chunk)
+            .map_err(|e| decode_error(decoder.name(), e))?;
+    }
+    let name = decoder.name();
+    decoder
+        .finish()
+        .map(Bytes::from)
+        .map_err(|e| decode_error(name, e))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Tests
 ////////////////////////////////////////////////////////////////////////////////
 #[cfg(test)]
 mod tests {
-    use std::net::{SocketAddr, TcpListener};
+    use std::{
+        net::{SocketAddr, TcpListener},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
 
     use axum::{
+        extract::ConnectInfo,
         routing::{delete, get, patch, post},
         serve, Router,
     };
     use http::status::StatusCode;
+    use rstest::rstest;
+    use rustls::{ClientConfig, RootCertStore, ServerConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsAcceptor;
 
     use super::*;
+    use crate::test_util::{install_crypto_provider, TestPki, TestTlsServer};
 
     fn get_unique_port() -> u16 {
         // Create a temporary TcpListener to get an available port
@@ -263,6 +863,10 @@ mod tests {
             .route("/post", post(|| async { StatusCode::OK }))
             .route("/patch", patch(|| async { StatusCode::OK }))
             .route("/delete", delete(|| async { StatusCode::OK }))
+            .route(
+                "/query",
+                post(|uri: http::Uri| async move { uri.query().unwrap_or_default().to_string() }),
+            )
     }
 
     async fn start_test_server() -> Result<SocketAddr, Box<dyn std::error::Error + Send + Sync>> {
@@ -393,4 +997,646 @@ mod tests {
 
         assert_eq!(response.status, StatusCode::OK);
     }
+
+    /// Spawns a TLS server which answers every request with `hello-world!`, or never
+    /// answers if `respond` is false.
+    async fn start_tls_server(config: ServerConfig, respond: bool) -> SocketAddr {
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    if !respond {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                    let response = "HTTP/1.1 200 OK\r\ncontent-length: 12\r\nx-test: tls\r\nconnection: close\r\n\r\nhello-world!";
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    fn rustls_connector(root_store: RootCertStore) -> Connector {
+        Connector::Rustls(Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(root_store)
+                .with_no_client_auth(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_request_with_connector_custom_ca() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let server_config = TestTlsServer::new(&pki).config();
+        let root_store = pki.root_store();
+        let addr = start_tls_server(server_config, true).await;
+
+        let client = HttpClient::new(
+            vec!["x-test".to_string()],
+            vec![],
+            None,
+            Some(rustls_connector(root_store)),
+        )
+        .unwrap();
+        let response = client
+            .request(
+                Method::GET,
+                format!("https://localhost:{}/get", addr.port()),
+                HashMap::new(),
+                None,
+                vec![],
+                Some(5),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.headers().get("x-test").unwrap(), "tls");
+        assert_eq!(response.body().as_ref(), b"hello-world!");
+    }
+
+    #[tokio::test]
+    async fn test_request_without_custom_ca_is_rejected() {
+        install_crypto_provider();
+        let server_config = TestTlsServer::new(&TestPki::new()).config();
+        let addr = start_tls_server(server_config, true).await;
+
+        let client = HttpClient::new(vec![], vec![], None, None).unwrap();
+        let result = client
+            .request(
+                Method::GET,
+                format!("https://localhost:{}/get", addr.port()),
+                HashMap::new(),
+                None,
+                vec![],
+                Some(5),
+            )
+            .await;
+
+        assert!(matches!(result, Err(HttpClientError::Error(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_with_connector_times_out() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let server_config = TestTlsServer::new(&pki).config();
+        let root_store = pki.root_store();
+        let addr = start_tls_server(server_config, false).await;
+
+        let client =
+            HttpClient::new(vec![], vec![], None, Some(rustls_connector(root_store))).unwrap();
+        let result = client
+            .request(
+                Method::GET,
+                format!("https://localhost:{}/get", addr.port()),
+                HashMap::new(),
+                None,
+                vec![],
+                Some(1),
+            )
+            .await;
+
+        assert!(matches!(result, Err(HttpClientError::TimeoutError(_))));
+    }
+
+    /// Spawns a server which answers `/peer` with the port of the client connection.
+    async fn start_peer_server() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = Router::new().route(
+            "/peer",
+            get(
+                |ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.port().to_string() },
+            ),
+        );
+
+        tokio::spawn(async move {
+            serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        addr
+    }
+
+    /// Spawns a server which answers one request per connection with the port of the client
+    /// connection, then closes the connection despite the response keeping it alive.
+    async fn start_closing_peer_server() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, peer) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let body = peer.port().to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: keep-alive\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    async fn request_peer_port(client: &HttpClient, addr: SocketAddr) -> String {
+        let response = client
+            .request(
+                Method::GET,
+                format!("http://{addr}/peer"),
+                HashMap::new(),
+                None,
+                vec![],
+                Some(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        String::from_utf8_lossy(response.body()).to_string()
+    }
+
+    #[rstest]
+    #[case::reused(PoolConfig::default(), Duration::ZERO, true)]
+    #[case::no_idle_connections(PoolConfig::new(None, 0), Duration::ZERO, false)]
+    #[case::idle_timeout_elapsed(
+        PoolConfig::new(Some(Duration::from_millis(50)), usize::MAX),
+        Duration::from_millis(500),
+        false
+    )]
+    #[tokio::test]
+    async fn test_pooled_sequential_requests(
+        #[case] config: PoolConfig,
+        #[case] pause: Duration,
+        #[case] reused: bool,
+    ) {
+        let addr = start_peer_server().await;
+        let pool = ConnectionPool::new(config);
+        let client = HttpClient::new_with_pool(vec![], vec![], None, None, &pool).unwrap();
+
+        let first = request_peer_port(&client, addr).await;
+        tokio::time::sleep(pause).await;
+        let second = request_peer_port(&client, addr).await;
+
+        assert_eq!(first == second, reused);
+    }
+
+    #[tokio::test]
+    async fn test_pooled_clients_share_connections_per_connector() {
+        install_crypto_provider();
+        let addr = start_peer_server().await;
+        let pool = ConnectionPool::default();
+        let connector = rustls_connector(RootCertStore::empty());
+        let client_a =
+            HttpClient::new_with_pool(vec![], vec![], None, Some(connector.clone()), &pool)
+                .unwrap();
+        let client_b =
+            HttpClient::new_with_pool(vec![], vec![], None, Some(connector), &pool).unwrap();
+        let client_c = HttpClient::new_with_pool(
+            vec![],
+            vec![],
+            None,
+            Some(rustls_connector(RootCertStore::empty())),
+            &pool,
+        )
+        .unwrap();
+
+        let first = request_peer_port(&client_a, addr).await;
+
+        assert_eq!(request_peer_port(&client_b, addr).await, first);
+        assert_ne!(request_peer_port(&client_c, addr).await, first);
+    }
+
+    #[tokio::test]
+    async fn test_pooled_connection_closed_by_server_is_not_reused() {
+        let addr = start_closing_peer_server().await;
+        let pool = ConnectionPool::default();
+        let client = HttpClient::new_with_pool(vec![], vec![], None, None, &pool).unwrap();
+
+        let first = request_peer_port(&client, addr).await;
+        // Give the pool time to observe the closed connection
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let second = request_peer_port(&client, addr).await;
+
+        assert_ne!(first, second);
+    }
+
+    const BINANCE_SECRET: &str = "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j";
+
+    #[rstest]
+    fn test_hmac_sha256_signature_known_vector() {
+        // Example from the Binance API documentation
+        let signer = HmacSha256Signer::new(BINANCE_SECRET);
+        let payload = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+
+        assert_eq!(
+            signer.signature(payload.as_bytes()),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "timestamp",
+        None,
+        "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559&signature=c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+    )]
+    #[case(
+        "nonce",
+        None,
+        "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&nonce=1499827319559&signature=2bfc08a218498961fc796f0d18ce7ea71807cc11a68b5e0ea0ae65b88af8866e"
+    )]
+    #[case(
+        "timestamp",
+        Some("quantity=1&price=0.1&recvWindow=5000"),
+        "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&timestamp=1499827319559&signature=9fe05e0baad80489b9402396276418ebdca1519e3b68c021d18babcbadee7194"
+    )]
+    fn test_hmac_sha256_signer_sign_with_timestamp(
+        #[case] timestamp_param: &str,
+        #[case] body: Option<&str>,
+        #[case] expected_query: &str,
+    ) {
+        let query = if body.is_some() {
+            "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC"
+        } else {
+            "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000"
+        };
+        let url = Url::parse(&format!("https://api.binance.com/api/v3/order?{query}")).unwrap();
+        let mut request = Request::new(Method::POST, url);
+        if let Some(body) = body {
+            *request.body_mut() = Some(body.to_string().into());
+        }
+
+        HmacSha256Signer::new(BINANCE_SECRET)
+            .with_timestamp_param(timestamp_param)
+            .sign_with_timestamp(&mut request, 1_499_827_319_559);
+
+        assert_eq!(request.url().query().unwrap(), expected_query);
+    }
+
+    #[tokio::test]
+    async fn test_request_with_signer() {
+        let addr = start_test_server().await.unwrap();
+
+        let signer = HmacSha256Signer::new("secret").with_signature_param("sig");
+        let client = HttpClient::new(vec![], vec![], None, None)
+            .unwrap()
+            .with_signer(Arc::new(signer));
+        let response = client
+            .request(
+                Method::POST,
+                format!("http://{addr}/query?symbol=ETHUSDT"),
+                HashMap::new(),
+                Some(b"side=BUY".to_vec()),
+                vec![],
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The server echoes the query string it received
+        let query = String::from_utf8(response.body().to_vec()).unwrap();
+        let (unsigned, signature) = query.split_once("&sig=").unwrap();
+        assert!(unsigned.starts_with("symbol=ETHUSDT&timestamp="));
+        let payload = format!("{unsigned}side=BUY");
+        assert_eq!(
+            signature,
+            HmacSha256Signer::new("secret").signature(payload.as_bytes())
+        );
+    }
+
+    #[rstest]
+    #[case(Some("2"), Some(Duration::from_secs(2)))]
+    #[case(Some(" 0 "), Some(Duration::ZERO))]
+    #[case(Some("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO))]
+    #[case(Some("soon"), None)]
+    #[case(None, None)]
+    fn test_retry_after(#[case] value: Option<&str>, #[case] expected: Option<Duration>) {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = value {
+            headers.insert(RETRY_AFTER, value.parse().unwrap());
+        }
+
+        assert_eq!(retry_after(&headers), expected);
+    }
+
+    #[rstest]
+    fn test_retry_after_future_http_date() {
+        let date = SystemTime::now() + Duration::from_secs(30);
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, httpdate::fmt_http_date(date).parse().unwrap());
+
+        // HTTP-dates have a resolution of one second
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+    }
+
+    #[rstest]
+    #[case(Method::GET, false, true)]
+    #[case(Method::DELETE, false, true)]
+    #[case(Method::POST, false, false)]
+    #[case(Method::PATCH, false, false)]
+    #[case(Method::POST, true, true)]
+    fn test_retry_policy_allows(
+        #[case] method: Method,
+        #[case] retry_non_idempotent: bool,
+        #[case] expected: bool,
+    ) {
+        let policy = RetryPolicy::new(1).with_non_idempotent(retry_non_idempotent);
+        assert_eq!(policy.allows(&method), expected);
+    }
+
+    /// Spawns a server which rejects the first `failures` requests with `status` (and a
+    /// `Retry-After` header if given), returning its address and the request counter.
+    async fn start_throttled_server(
+        failures: usize,
+        status: StatusCode,
+        retry_after: Option<String>,
+    ) -> (SocketAddr, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let handler = move || {
+            let counter = counter.clone();
+            let retry_after = retry_after.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    if let Some(retry_after) = retry_after {
+                        headers.insert(RETRY_AFTER, retry_after.parse().unwrap());
+                    }
+                    (status, headers, "throttled")
+                } else {
+                    (StatusCode::OK, headers, "hello-world!")
+                }
+            }
+        };
+        let router = Router::new().route("/throttled", get(handler.clone()).post(handler));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            serve(listener, router).await.unwrap();
+        });
+
+        (addr, requests)
+    }
+
+    fn retrying_client(policy: RetryPolicy) -> HttpClient {
+        HttpClient::new(vec![], vec![], None, None)
+            .unwrap()
+            .with_retry_policy(
+                policy.with_delays(Duration::from_millis(10), Duration::from_millis(50)),
+            )
+    }
+
+    async fn request_throttled(
+        client: &HttpClient,
+        method: Method,
+        addr: SocketAddr,
+    ) -> HttpResponse {
+        client
+            .request(
+                method,
+                format!("http://{addr}/throttled"),
+                HashMap::new(),
+                None,
+                vec![],
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_numeric_retry_after() {
+        let (addr, requests) =
+            start_throttled_server(1, StatusCode::TOO_MANY_REQUESTS, Some("1".to_string())).await;
+        let client = retrying_client(RetryPolicy::new(3));
+
+        let start = std::time::Instant::now();
+        let response = request_throttled(&client, Method::GET, addr).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_date_retry_after() {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(2));
+        let (addr, requests) =
+            start_throttled_server(1, StatusCode::SERVICE_UNAVAILABLE, Some(date)).await;
+        let client = retrying_client(RetryPolicy::new(3));
+
+        let start = std::time::Instant::now();
+        let response = request_throttled(&client, Method::GET, addr).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_capped() {
+        let (addr, requests) =
+            start_throttled_server(1, StatusCode::TOO_MANY_REQUESTS, Some("3600".to_string()))
+                .await;
+        let client =
+            retrying_client(RetryPolicy::new(3).with_retry_after_max(Duration::from_millis(100)));
+
+        let start = std::time::Instant::now();
+        let response = request_throttled(&client, Method::GET, addr).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_missing_retry_after_backs_off() {
+        let (addr, requests) = start_throttled_server(2, StatusCode::TOO_MANY_REQUESTS, None).await;
+        let client = retrying_client(RetryPolicy::new(3));
+
+        let response = request_throttled(&client, Method::GET, addr).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_after_max_retries() {
+        let (addr, requests) = start_throttled_server(5, StatusCode::TOO_MANY_REQUESTS, None).await;
+        let client = retrying_client(RetryPolicy::new(2));
+
+        let response = request_throttled(&client, Method::GET, addr).await;
+
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[rstest]
+    #[case(false, StatusCode::TOO_MANY_REQUESTS, 1)]
+    #[case(true, StatusCode::OK, 2)]
+    #[tokio::test]
+    async fn test_retry_non_idempotent(
+        #[case] retry_non_idempotent: bool,
+        #[case] expected_status: StatusCode,
+        #[case] expected_requests: usize,
+    ) {
+        let (addr, requests) = start_throttled_server(1, StatusCode::TOO_MANY_REQUESTS, None).await;
+        let client = retrying_client(RetryPolicy::new(3).with_non_idempotent(retry_non_idempotent));
+
+        let response = request_throttled(&client, Method::POST, addr).await;
+
+        assert_eq!(response.status, expected_status);
+        assert_eq!(requests.load(Ordering::SeqCst), expected_requests);
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+        encoder.write_all(data).unwrap();
+        encoder.into_inner()
+    }
+
+    /// Spawns a server responding with the `body` and `Content-Encoding`, which also
+    /// returns the `Accept-Encoding` of the request in a header.
+    async fn start_encoded_server(encoding: &'static str, body: Vec<u8>) -> SocketAddr {
+        let handler = move |request_headers: HeaderMap| async move {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_ENCODING, encoding.parse().unwrap());
+            if let Some(accept) = request_headers.get(ACCEPT_ENCODING) {
+                headers.insert("x-accept-encoding", accept.clone());
+            }
+            (headers, body)
+        };
+        let router = Router::new().route("/encoded", get(handler));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            serve(listener, router).await.unwrap();
+        });
+
+        addr
+    }
+
+    async fn request_encoded(
+        client: &HttpClient,
+        addr: SocketAddr,
+    ) -> Result<HttpResponse, HttpClientError> {
+        client
+            .request(
+                Method::GET,
+                format!("http://{addr}/encoded"),
+                HashMap::new(),
+                None,
+                vec![],
+                None,
+            )
+            .await
+    }
+
+    #[rstest]
+    #[case::gzip("gzip", gzip)]
+    #[case::x_gzip("x-gzip", gzip)]
+    #[case::brotli("br", brotli)]
+    #[tokio::test]
+    async fn test_request_decodes_encoded_body(
+        #[case] encoding: &'static str,
+        #[case] encode: fn(&[u8]) -> Vec<u8>,
+    ) {
+        // Large enough to be received in several chunks
+        let data = "hello-world!".repeat(100_000);
+        let addr = start_encoded_server(encoding, encode(data.as_bytes())).await;
+        let client =
+            HttpClient::new(vec!["x-accept-encoding".to_string()], vec![], None, None).unwrap();
+
+        let response = request_encoded(&client, addr).await.unwrap();
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, data.as_bytes());
+        assert_eq!(response.headers["x-accept-encoding"], "gzip, br");
+    }
+
+    #[tokio::test]
+    async fn test_request_without_decompression_returns_encoded_body() {
+        let encoded = gzip(b"hello-world!");
+        let addr = start_encoded_server("gzip", encoded.clone()).await;
+        let client = HttpClient::new(vec!["x-accept-encoding".to_string()], vec![], None, None)
+            .unwrap()
+            .with_decompression(false);
+
+        let response = request_encoded(&client, addr).await.unwrap();
+
+        assert_eq!(response.body, encoded);
+        assert!(!response.headers.contains_key("x-accept-encoding"));
+    }
+
+    #[tokio::test]
+    async fn test_request_with_unknown_encoding() {
+        let addr = start_encoded_server("zstd", b"hello-world!".to_vec()).await;
+        let client = HttpClient::new(vec![], vec![], None, None).unwrap();
+
+        let result = request_encoded(&client, addr).await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "HTTP error occurred: Unsupported response `Content-Encoding` 'zstd'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_with_corrupt_encoded_body() {
+        let mut encoded = gzip(b"hello-world!");
+        encoded.truncate(encoded.len() - 4);
+        let addr = start_encoded_server("gzip", encoded).await;
+        let client = HttpClient::new(vec![], vec![], None, None).unwrap();
+
+        let result = request_encoded(&client, addr).await;
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("HTTP error occurred: Error decoding gzip response body"));
+    }
 }
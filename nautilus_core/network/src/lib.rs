@@ -25,14 +25,26 @@
 //! for the main `nautilus_trader` Python package, or as part of a Rust only build.
 //!
 //! - `python`: Enables Python bindings from `pyo3`
+//! - `native-tls`: Enables the `Connector::NativeTls` variant backed by the platform TLS stack
+//! - `dangerous-tls`: Enables connectors which skip server certificate verification (testing only)
+//! - `tls-keylog`: Enables logging TLS session secrets to the `SSLKEYLOGFILE` (debugging only)
+//! - `pkcs12`: Enables loading client credentials from PKCS#12 bundles (requires OpenSSL)
 
 #![allow(warnings)] // TODO: non-local `impl` definition, temporary allow until pyo3 upgrade
 
+pub mod backoff;
+pub mod dns;
+pub mod happy_eyeballs;
 pub mod http;
+pub mod proxy;
 #[allow(dead_code)]
 mod ratelimiter;
 pub mod socket;
-mod tls;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod tls;
+#[cfg(unix)]
+pub mod unix;
 pub mod websocket;
 
 #[cfg(feature = "python")]
@@ -121,6 +121,9 @@ impl HttpClient {
         let client = InnerHttpClient {
             client,
             header_keys,
+            signer: None,
+            retry_policy: None,
+            decompress: true,
         };
 
         Self {
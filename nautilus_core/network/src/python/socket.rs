@@ -28,7 +28,7 @@ use tokio_tungstenite::tungstenite::stream::Mode;
 use crate::{
     http::{HttpClient, HttpMethod, HttpResponse, InnerHttpClient},
     ratelimiter::{quota::Quota, RateLimiter},
-    socket::{SocketClient, SocketConfig},
+    socket::{SocketClient, SocketConfig, SocketOptions},
 };
 
 #[pymethods]
@@ -40,6 +40,8 @@ impl SocketConfig {
         suffix: Vec<u8>,
         handler: PyObject,
         heartbeat: Option<(u64, Vec<u8>)>,
+        length_prefix: Option<usize>,
+        max_frame_len: Option<usize>,
     ) -> Self {
         let mode = if ssl { Mode::Tls } else { Mode::Plain };
         Self {
@@ -48,6 +50,12 @@ impl SocketConfig {
             suffix,
             handler,
             heartbeat,
+            length_prefix,
+            max_frame_len,
+            connector: None,
+            socket_options: SocketOptions::default(),
+            dns_cache: None,
+            handshake_limit: None,
         }
     }
 }
@@ -121,11 +129,11 @@ impl SocketClient {
     #[pyo3(name = "send")]
     fn py_send<'py>(
         slf: PyRef<'_, Self>,
-        mut data: Vec<u8>,
+        data: Vec<u8>,
         py: Python<'py>,
     ) -> PyResult<Bound<'py, PyAny>> {
         let writer = slf.writer.clone();
-        data.extend(&slf.suffix);
+        let data = slf.framing.encode(&data).map_err(to_pyruntime_err)?;
 
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
             let mut writer = writer.lock().await;
@@ -150,7 +158,7 @@ mod tests {
     use tokio_tungstenite::tungstenite::stream::Mode;
     use tracing_test::traced_test;
 
-    use crate::socket::{SocketClient, SocketConfig};
+    use crate::socket::{SocketClient, SocketConfig, SocketOptions};
 
     struct TestServer {
         task: JoinHandle<()>,
@@ -260,6 +268,12 @@ counter = Counter()",
             mode: Mode::Plain,
             suffix: b"\r\n".to_vec(),
             heartbeat: None,
+            length_prefix: None,
+            max_frame_len: None,
+            connector: None,
+            socket_options: SocketOptions::default(),
+            dns_cache: None,
+            handshake_limit: None,
         };
         let client: SocketClient = SocketClient::connect(config, None, None, None)
             .await
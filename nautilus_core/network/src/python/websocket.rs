@@ -28,7 +28,10 @@ use tokio_tungstenite::tungstenite::Message;
 use crate::{
     http::{HttpClient, HttpMethod, HttpResponse, InnerHttpClient},
     ratelimiter::{quota::Quota, RateLimiter},
-    websocket::{WebSocketClient, WebSocketConfig},
+    socket::SocketOptions,
+    websocket::{
+        redact_secrets, BackpressurePolicy, FrameRedactor, WebSocketClient, WebSocketConfig,
+    },
 };
 
 /// Python exception class for websocket errors.
@@ -48,6 +51,21 @@ impl WebSocketConfig {
         heartbeat: Option<u64>,
         heartbeat_msg: Option<String>,
         ping_handler: Option<PyObject>,
+        reconnect_delay_initial_ms: Option<u64>,
+        reconnect_delay_max_ms: Option<u64>,
+        reconnect_jitter_ms: Option<u64>,
+        reconnect_max_attempts: Option<u32>,
+        heartbeat_timeout: Option<u64>,
+        ping_payload: Option<Vec<u8>>,
+        send_queue_capacity: Option<usize>,
+        backpressure_policy: Option<BackpressurePolicy>,
+        max_message_size: Option<usize>,
+        max_frame_size: Option<usize>,
+        read_buffer_size: Option<usize>,
+        write_buffer_size: Option<usize>,
+        trace_frames: Option<bool>,
+        parked_capacity: Option<usize>,
+        parked_policy: Option<BackpressurePolicy>,
     ) -> Self {
         Self {
             url,
@@ -56,6 +74,31 @@ impl WebSocketConfig {
             heartbeat,
             heartbeat_msg,
             ping_handler,
+            reconnect_delay_initial_ms,
+            reconnect_delay_max_ms,
+            reconnect_jitter_ms,
+            reconnect_max_attempts,
+            heartbeat_timeout,
+            ping_payload,
+            connector: None,
+            socket_options: SocketOptions::default(),
+            send_queue_capacity,
+            backpressure_policy: backpressure_policy.unwrap_or_default(),
+            max_message_size,
+            max_frame_size,
+            read_buffer_size,
+            write_buffer_size,
+            cancellation_token: None,
+            frame_redactor: trace_frames
+                .unwrap_or_default()
+                .then_some(redact_secrets as FrameRedactor),
+            dns_cache: None,
+            handshake_limit: None,
+            subscription_ack_timeout_ms: None,
+            subscription_ack_parser: None,
+            binary_consumer: None,
+            parked_capacity,
+            parked_policy: parked_policy.unwrap_or_default(),
         }
     }
 }
@@ -68,12 +111,14 @@ impl WebSocketClient {
     ///
     /// - Throws an Exception if it is unable to make websocket connection
     #[staticmethod]
-    #[pyo3(name = "connect")]
+    #[pyo3(name = "connect", signature = (config, post_connection = None, post_reconnection = None, post_disconnection = None, keyed_quotas = Vec::new(), default_quota = None))]
     fn py_connect(
         config: WebSocketConfig,
         post_connection: Option<PyObject>,
         post_reconnection: Option<PyObject>,
         post_disconnection: Option<PyObject>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
         py: Python<'_>,
     ) -> PyResult<Bound<PyAny>> {
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
@@ -82,12 +127,58 @@ impl WebSocketClient {
                 post_connection,
                 post_reconnection,
                 post_disconnection,
+                keyed_quotas,
+                default_quota,
             )
             .await
             .map_err(to_websocket_pyerr)
         })
     }
 
+    /// Create a websocket client with a warm connection, parked until `activate` is called.
+    ///
+    /// Data frames received while parked are held, and passed to the handler on activation.
+    ///
+    /// # Safety
+    ///
+    /// - Throws an Exception if it is unable to make websocket connection
+    #[staticmethod]
+    #[pyo3(name = "warm_connect", signature = (config, post_connection = None, post_reconnection = None, post_disconnection = None, keyed_quotas = Vec::new(), default_quota = None))]
+    fn py_warm_connect(
+        config: WebSocketConfig,
+        post_connection: Option<PyObject>,
+        post_reconnection: Option<PyObject>,
+        post_disconnection: Option<PyObject>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+        py: Python<'_>,
+    ) -> PyResult<Bound<PyAny>> {
+        pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
+            Self::warm_connect(
+                config,
+                post_connection,
+                post_reconnection,
+                post_disconnection,
+                keyed_quotas,
+                default_quota,
+            )
+            .await
+            .map_err(to_websocket_pyerr)
+        })
+    }
+
+    /// Activates a connection parked by `warm_connect`.
+    #[pyo3(name = "activate")]
+    fn py_activate(slf: PyRef<'_, Self>) {
+        slf.activate();
+    }
+
+    /// Returns whether the connection is active (not parked by `warm_connect`).
+    #[pyo3(name = "is_active")]
+    fn py_is_active(slf: PyRef<'_, Self>) -> bool {
+        slf.is_active()
+    }
+
     /// Closes the client heart beat and reader task.
     ///
     /// The connection is not completely closed the till all references
@@ -106,6 +197,52 @@ impl WebSocketClient {
         })
     }
 
+    /// Closes the connection with the `code` and `reason`.
+    ///
+    /// The client sends a `Close` frame and waits (with a timeout) for the
+    /// server's `Close` reply before shutting down the connection.
+    ///
+    /// # Safety
+    ///
+    /// - The client should not be used after closing it
+    #[pyo3(name = "close")]
+    fn py_close<'py>(
+        slf: PyRef<'_, Self>,
+        code: u16,
+        reason: String,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let close_frame = slf.close_frame.clone();
+        let disconnect_mode = slf.disconnect_mode.clone();
+        pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
+            Self::request_close(&close_frame, code, reason);
+            disconnect_mode.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+    }
+
+    /// Closes the connection once the messages already received are delivered.
+    ///
+    /// The client stops reading new frames and passes the frames already received
+    /// to the handler before shutting down the connection.
+    ///
+    /// # Safety
+    ///
+    /// - The client should not be used after closing it
+    #[pyo3(name = "drain_and_close")]
+    fn py_drain_and_close<'py>(
+        slf: PyRef<'_, Self>,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let drain_on_close = slf.drain_on_close.clone();
+        let disconnect_mode = slf.disconnect_mode.clone();
+        pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
+            drain_on_close.store(true, Ordering::SeqCst);
+            disconnect_mode.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+    }
+
     /// Check if the client is still alive.
     ///
     /// Even if the connection is disconnected the client will still be alive
@@ -123,6 +260,8 @@ impl WebSocketClient {
 
     /// Send bytes data to the server.
     ///
+    /// The send waits for the rate limiting quotas of the given `keys`.
+    ///
     /// # Errors
     ///
     /// - Raises PyRuntimeError if not able to send data.
@@ -130,21 +269,31 @@ impl WebSocketClient {
     fn py_send<'py>(
         slf: PyRef<'_, Self>,
         data: Vec<u8>,
+        keys: Option<Vec<String>>,
         py: Python<'py>,
     ) -> PyResult<Bound<'py, PyAny>> {
         tracing::trace!("Sending binary: {data:?}");
         let writer = slf.writer.clone();
+        let rate_limiter = slf.rate_limiter.clone();
+        let send_queue = slf.send_queue.clone();
+        let frame_redactor = slf.frame_redactor;
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
-            let mut guard = writer.lock().await;
-            guard
-                .send(Message::Binary(data))
-                .await
-                .map_err(to_websocket_pyerr)
+            Self::await_rate_limit(&rate_limiter, keys).await;
+            Self::send_message(
+                &writer,
+                send_queue.as_deref(),
+                Message::Binary(data),
+                frame_redactor,
+            )
+            .await
+            .map_err(to_websocket_pyerr)
         })
     }
 
     /// Send UTF-8 encoded bytes as text data to the server.
     ///
+    /// The send waits for the rate limiting quotas of the given `keys`.
+    ///
     /// # Errors
     ///
     /// - Raises PyRuntimeError if not able to send data.
@@ -152,20 +301,127 @@ impl WebSocketClient {
     fn py_send_text<'py>(
         slf: PyRef<'_, Self>,
         data: Vec<u8>,
+        keys: Option<Vec<String>>,
         py: Python<'py>,
     ) -> PyResult<Bound<'py, PyAny>> {
         let data = String::from_utf8(data).map_err(to_pyvalue_err)?;
         tracing::trace!("Sending text: {data}");
         let writer = slf.writer.clone();
+        let rate_limiter = slf.rate_limiter.clone();
+        let send_queue = slf.send_queue.clone();
+        let frame_redactor = slf.frame_redactor;
         pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
-            let mut guard = writer.lock().await;
-            guard
-                .send(Message::Text(data))
-                .await
-                .map_err(to_websocket_pyerr)
+            Self::await_rate_limit(&rate_limiter, keys).await;
+            Self::send_message(
+                &writer,
+                send_queue.as_deref(),
+                Message::Text(data),
+                frame_redactor,
+            )
+            .await
+            .map_err(to_websocket_pyerr)
+        })
+    }
+
+    /// Send the subscribe text `data` for the `topic` and track it, so that it is
+    /// resent after a reconnect.
+    ///
+    /// Nothing is sent if the `topic` is already subscribed.
+    ///
+    /// # Errors
+    ///
+    /// - Raises PyRuntimeError if not able to send data.
+    #[pyo3(name = "subscribe")]
+    fn py_subscribe<'py>(
+        slf: PyRef<'_, Self>,
+        topic: String,
+        data: Vec<u8>,
+        keys: Option<Vec<String>>,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let data = String::from_utf8(data).map_err(to_pyvalue_err)?;
+        if !slf
+            .subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .subscribe(topic.clone(), data.clone(), keys.clone())
+        {
+            tracing::debug!("Already subscribed to {topic}");
+            return pyo3_asyncio_0_21::tokio::future_into_py(py, async { Ok(()) });
+        }
+        tracing::trace!("Subscribing to {topic}: {data}");
+        let writer = slf.writer.clone();
+        let rate_limiter = slf.rate_limiter.clone();
+        let send_queue = slf.send_queue.clone();
+        let frame_redactor = slf.frame_redactor;
+        pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
+            Self::await_rate_limit(&rate_limiter, keys).await;
+            Self::send_message(
+                &writer,
+                send_queue.as_deref(),
+                Message::Text(data),
+                frame_redactor,
+            )
+            .await
+            .map_err(to_websocket_pyerr)
+        })
+    }
+
+    /// Send the unsubscribe text `data` for the `topic` and stop tracking it.
+    ///
+    /// Nothing is sent if the `topic` is not subscribed.
+    ///
+    /// # Errors
+    ///
+    /// - Raises PyRuntimeError if not able to send data.
+    #[pyo3(name = "unsubscribe")]
+    fn py_unsubscribe<'py>(
+        slf: PyRef<'_, Self>,
+        topic: String,
+        data: Vec<u8>,
+        keys: Option<Vec<String>>,
+        py: Python<'py>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let data = String::from_utf8(data).map_err(to_pyvalue_err)?;
+        if !slf
+            .subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .unsubscribe(&topic)
+        {
+            tracing::debug!("Not subscribed to {topic}");
+            return pyo3_asyncio_0_21::tokio::future_into_py(py, async { Ok(()) });
+        }
+        tracing::trace!("Unsubscribing from {topic}: {data}");
+        let writer = slf.writer.clone();
+        let rate_limiter = slf.rate_limiter.clone();
+        let send_queue = slf.send_queue.clone();
+        let frame_redactor = slf.frame_redactor;
+        pyo3_asyncio_0_21::tokio::future_into_py(py, async move {
+            Self::await_rate_limit(&rate_limiter, keys).await;
+            Self::send_message(
+                &writer,
+                send_queue.as_deref(),
+                Message::Text(data),
+                frame_redactor,
+            )
+            .await
+            .map_err(to_websocket_pyerr)
         })
     }
 
+    /// Returns the subscribed topics in the order they were subscribed.
+    #[pyo3(name = "subscriptions")]
+    fn py_subscriptions(slf: PyRef<'_, Self>) -> Vec<String> {
+        slf.subscriptions()
+    }
+
+    /// Returns the number of messages waiting in the send queue.
+    #[pyo3(name = "send_queue_depth")]
+    fn py_send_queue_depth(slf: PyRef<'_, Self>) -> usize {
+        slf.send_queue_depth()
+    }
+
     /// Send pong bytes data to the server.
     ///
     /// # Errors
@@ -195,23 +451,46 @@ impl WebSocketClient {
 ////////////////////////////////////////////////////////////////////////////////
 #[cfg(test)]
 mod tests {
+    use std::{
+        num::NonZeroU32,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
     use futures_util::{SinkExt, StreamExt};
     use pyo3::{prelude::*, prepare_freethreaded_python};
+    use rstest::rstest;
+    use serde::Deserialize;
     use tokio::{
         net::TcpListener,
         task::{self, JoinHandle},
-        time::{sleep, Duration},
+        time::{sleep, Duration, Instant},
     };
     use tokio_tungstenite::{
-        accept_hdr_async,
+        accept_async, accept_hdr_async,
         tungstenite::{
             handshake::server::{self, Callback},
             http::HeaderValue,
+            protocol::frame::coding::CloseCode,
+            Error, Message,
         },
+        MaybeTlsStream,
     };
+    use tokio_util::sync::CancellationToken;
     use tracing_test::traced_test;
 
-    use crate::websocket::{WebSocketClient, WebSocketConfig};
+    use crate::{
+        ratelimiter::quota::Quota,
+        socket::SocketOptions,
+        tls::is_cancelled,
+        websocket::{
+            binary_payload, redact_secrets, BackpressurePolicy, ClientMetrics, RecvJsonError,
+            SendQueue, SubscriptionManager, TrySendError, WebSocketClient, WebSocketClientInner,
+            WebSocketConfig,
+        },
+    };
 
     struct TestServer {
         task: JoinHandle<()>,
@@ -336,14 +615,29 @@ counter = Counter()",
             None,
             None,
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
-        let client = WebSocketClient::connect(config, None, None, None)
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
             .await
             .unwrap();
 
         // Send messages that increment the count
         for _ in 0..N {
-            if client.send_bytes(b"ping".to_vec()).await.is_ok() {
+            if client.send_bytes(b"ping".to_vec(), None).await.is_ok() {
                 success_count += 1;
             };
         }
@@ -372,7 +666,7 @@ counter = Counter()",
         // Send messages that increment the count
         sleep(Duration::from_secs(2)).await;
         for _ in 0..N {
-            if client.send_bytes(b"ping".to_vec()).await.is_ok() {
+            if client.send_bytes(b"ping".to_vec(), None).await.is_ok() {
                 success_count += 1;
             };
         }
@@ -440,8 +734,23 @@ checker = Checker()",
             Some(1),
             Some("heartbeat message".to_string()),
             None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
         );
-        let client = WebSocketClient::connect(config, None, None, None)
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
             .await
             .unwrap();
 
@@ -462,4 +771,1490 @@ checker = Checker()",
         client.disconnect().await;
         assert!(client.is_disconnected());
     }
+
+    /// Spawns a server which closes the first connection, then drops the next `drops`
+    /// connection attempts before echoing messages again.
+    async fn setup_dropping_server(drops: usize) -> (JoinHandle<()>, u16, Arc<AtomicUsize>) {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        let task = task::spawn(async move {
+            loop {
+                let (conn, _) = server.accept().await.unwrap();
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                if n == 0 {
+                    let mut websocket = accept_async(conn).await.unwrap();
+                    let _ = websocket.close(None).await;
+                } else if n <= drops {
+                    drop(conn);
+                } else {
+                    let mut websocket = accept_async(conn).await.unwrap();
+                    task::spawn(async move {
+                        while let Some(Ok(msg)) = websocket.next().await {
+                            if msg.is_binary() || msg.is_text() {
+                                websocket.send(msg).await.unwrap();
+                            }
+                        }
+                    });
+                }
+            }
+        });
+
+        (task, port, connections)
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn reconnect_with_backoff_test() {
+        prepare_freethreaded_python();
+
+        const DROPS: usize = 3;
+        let (server, port, connections) = setup_dropping_server(DROPS).await;
+
+        let (counter, handler, post_reconnection) = Python::with_gil(|py| {
+            let pymod = PyModule::from_code(
+                py,
+                r"
+class Counter:
+    def __init__(self):
+        self.count = 0
+        self.reconnects = 0
+
+    def handler(self, bytes):
+        if bytes.decode() == 'ping':
+            self.count = self.count + 1
+
+    def post_reconnection(self):
+        self.reconnects = self.reconnects + 1
+
+counter = Counter()",
+                "",
+                "",
+            )
+            .unwrap();
+
+            let counter = pymod.getattr("counter").unwrap().into_py(py);
+            let handler = counter.getattr(py, "handler").unwrap().into_py(py);
+            let post_reconnection = counter
+                .getattr(py, "post_reconnection")
+                .unwrap()
+                .into_py(py);
+
+            (counter, handler, post_reconnection)
+        });
+
+        let config = WebSocketConfig::py_new(
+            format!("ws://127.0.0.1:{port}"),
+            handler,
+            vec![],
+            None,
+            None,
+            None,
+            Some(10),
+            Some(50),
+            Some(0),
+            Some(5),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let client =
+            WebSocketClient::connect(config, None, Some(post_reconnection), None, vec![], None)
+                .await
+                .unwrap();
+
+        // The server closes the first connection and drops the following attempts
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(connections.load(Ordering::SeqCst), DROPS + 2);
+
+        client.send_bytes(b"ping".to_vec(), None).await.unwrap();
+        sleep(Duration::from_millis(500)).await;
+
+        let (count, reconnects): (usize, usize) = Python::with_gil(|py| {
+            let counter = counter.bind(py);
+            (
+                counter.getattr("count").unwrap().extract().unwrap(),
+                counter.getattr("reconnects").unwrap().extract().unwrap(),
+            )
+        });
+        assert_eq!(count, 1);
+        assert_eq!(reconnects, 1);
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+        server.abort();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn heartbeat_timeout_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        let pings = Arc::new(AtomicUsize::new(0));
+        let ping_counter = pings.clone();
+
+        // The first connection is never read from, so pings are never answered
+        let server_task = task::spawn(async move {
+            let mut silent = Vec::new();
+            loop {
+                let (conn, _) = server.accept().await.unwrap();
+                let websocket = accept_async(conn).await.unwrap();
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    silent.push(websocket);
+                } else {
+                    let ping_counter = ping_counter.clone();
+                    task::spawn(async move {
+                        let mut websocket = websocket;
+                        while let Some(Ok(msg)) = websocket.next().await {
+                            if msg == Message::Ping(b"heartbeat".to_vec()) {
+                                ping_counter.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                    });
+                }
+            }
+        });
+
+        let handler =
+            Python::with_gil(|py| py.eval("lambda _: None", None, None).unwrap().into_py(py));
+        let config = WebSocketConfig::py_new(
+            format!("ws://127.0.0.1:{port}"),
+            handler,
+            vec![],
+            Some(1),
+            None,
+            None,
+            Some(10),
+            Some(50),
+            Some(0),
+            None,
+            Some(2),
+            Some(b"heartbeat".to_vec()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        // The client detects the stale connection and reconnects
+        sleep(Duration::from_secs(4)).await;
+        assert!(logs_contain("No message received within 2s - terminating"));
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert!(pings.load(Ordering::SeqCst) > 0);
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rate_limited_send_test() {
+        prepare_freethreaded_python();
+
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+
+        let handler =
+            Python::with_gil(|py| py.eval("lambda _: None", None, None).unwrap().into_py(py));
+        let config = WebSocketConfig::py_new(
+            format!("ws://127.0.0.1:{}", server.port),
+            handler,
+            vec![(header_key, header_value)],
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let quota = Quota::per_second(NonZeroU32::new(5).unwrap());
+        let client = WebSocketClient::connect(
+            config,
+            None,
+            None,
+            None,
+            vec![("subscribe".to_string(), quota)],
+            None,
+        )
+        .await
+        .unwrap();
+
+        // The burst of 5 is sent immediately, then one message every 200ms
+        let start = Instant::now();
+        for _ in 0..10 {
+            client
+                .send_bytes(b"subscribe".to_vec(), Some(vec!["subscribe".to_string()]))
+                .await
+                .unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(900));
+
+        // Messages without keys are not limited when there is no default quota
+        let start = Instant::now();
+        for _ in 0..10 {
+            client.send_bytes(b"ping".to_vec(), None).await.unwrap();
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+    }
+
+    fn silent_config(port: u16) -> WebSocketConfig {
+        let handler =
+            Python::with_gil(|py| py.eval("lambda _: None", None, None).unwrap().into_py(py));
+        WebSocketConfig::py_new(
+            format!("ws://127.0.0.1:{port}"),
+            handler,
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn close_handshake_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        // Keep reading so the server replies to the close frame
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            let mut tx = Some(tx);
+            while let Some(Ok(message)) = websocket.next().await {
+                if let Message::Close(frame) = message {
+                    if let Some(tx) = tx.take() {
+                        tx.send(frame).unwrap();
+                    }
+                }
+            }
+        });
+
+        let client = WebSocketClient::connect(silent_config(port), None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        client.close(1000, "Session ended".to_string()).await;
+
+        let frame = rx.await.unwrap().unwrap();
+        assert_eq!(frame.code, CloseCode::Normal);
+        assert_eq!(frame.reason, "Session ended");
+        assert!(client.is_disconnected());
+        assert!(logs_contain("Close handshake completed"));
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn close_without_reply_test() {
+        prepare_freethreaded_python();
+
+        // The server never reads, so it never replies to the close frame
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let _websocket = accept_async(conn).await.unwrap();
+            sleep(Duration::from_secs(60)).await;
+        });
+
+        let client = WebSocketClient::connect(silent_config(port), None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        client.close(1001, "Going away".to_string()).await;
+
+        assert!(start.elapsed() < Duration::from_secs(4));
+        assert!(client.is_disconnected());
+        assert!(logs_contain("No close reply received within"));
+        server_task.abort();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn drain_and_close_test() {
+        prepare_freethreaded_python();
+
+        const N: usize = 500;
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+
+        // Sends every frame at once, so they are received faster than they are handled
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            for i in 0..N {
+                websocket
+                    .send(Message::Text(format!("message-{i}")))
+                    .await
+                    .unwrap();
+            }
+            while let Some(Ok(_)) = websocket.next().await {}
+        });
+
+        let (received, handler) = Python::with_gil(|py| {
+            let pymod = PyModule::from_code(
+                py,
+                r"
+import time
+
+received = []
+
+def handler(data):
+    time.sleep(0.001)
+    received.append(data.decode())",
+                "",
+                "",
+            )
+            .unwrap();
+
+            let received = pymod.getattr("received").unwrap().into_py(py);
+            let handler = pymod.getattr("handler").unwrap().into_py(py);
+
+            (received, handler)
+        });
+        let mut config = silent_config(port);
+        config.handler = handler;
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        // Starts draining while most frames are still buffered
+        sleep(Duration::from_millis(50)).await;
+        client.drain_and_close().await;
+
+        let received: Vec<String> = Python::with_gil(|py| received.extract(py).unwrap());
+        let expected: Vec<String> = (0..N).map(|i| format!("message-{i}")).collect();
+        assert_eq!(received, expected);
+        assert!(client.is_disconnected());
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn warm_connect_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+
+        // Greets the client as soon as connected, then echoes data frames
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            websocket
+                .send(Message::Text("welcome".to_string()))
+                .await
+                .unwrap();
+            while let Some(Ok(msg)) = websocket.next().await {
+                if msg.is_binary() || msg.is_text() {
+                    websocket.send(msg).await.unwrap();
+                }
+            }
+        });
+
+        let (received, handler) = Python::with_gil(|py| {
+            let pymod = PyModule::from_code(
+                py,
+                r"
+received = []
+
+def handler(data):
+    received.append(data.decode())",
+                "",
+                "",
+            )
+            .unwrap();
+
+            let received = pymod.getattr("received").unwrap().into_py(py);
+            let handler = pymod.getattr("handler").unwrap().into_py(py);
+
+            (received, handler)
+        });
+        let received_len = || Python::with_gil(|py| received.bind(py).len().unwrap());
+        let mut config = silent_config(port);
+        config.handler = handler;
+        let client = WebSocketClient::warm_connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        // The greeting is held while the connection is parked
+        sleep(Duration::from_millis(100)).await;
+        assert!(!client.is_active());
+        assert_eq!(received_len(), 0);
+
+        client.activate();
+        client.send_bytes(b"order".to_vec(), None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while received_len() < 2 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let received: Vec<String> = Python::with_gil(|py| received.extract(py).unwrap());
+        assert_eq!(received, vec!["welcome", "order"]);
+        assert!(client.is_active());
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    #[rstest]
+    #[case(BackpressurePolicy::Block, vec!["1", "2", "3"])]
+    #[case(BackpressurePolicy::DropOldest, vec!["2", "3"])]
+    #[tokio::test]
+    async fn parked_capacity_test(#[case] policy: BackpressurePolicy, #[case] expected: Vec<&str>) {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+
+        // Sends three frames as soon as connected, more than the client can hold while parked
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            for data in ["1", "2", "3"] {
+                websocket
+                    .send(Message::Text(data.to_string()))
+                    .await
+                    .unwrap();
+            }
+            while websocket.next().await.is_some() {}
+        });
+
+        let (received, handler) = Python::with_gil(|py| {
+            let pymod = PyModule::from_code(
+                py,
+                r"
+received = []
+
+def handler(data):
+    received.append(data.decode())",
+                "",
+                "",
+            )
+            .unwrap();
+
+            let received = pymod.getattr("received").unwrap().into_py(py);
+            let handler = pymod.getattr("handler").unwrap().into_py(py);
+
+            (received, handler)
+        });
+        let received_len = || Python::with_gil(|py| received.bind(py).len().unwrap());
+        let mut config = silent_config(port);
+        config.handler = handler;
+        config.parked_capacity = Some(2);
+        config.parked_policy = policy;
+        let client = WebSocketClient::warm_connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        sleep(Duration::from_millis(100)).await;
+        client.activate();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while received_len() < expected.len() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        sleep(Duration::from_millis(50)).await;
+
+        let received: Vec<String> = Python::with_gil(|py| received.extract(py).unwrap());
+        assert_eq!(received, expected);
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn metrics_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+
+        // Echoes two data frames followed by a pong, then drops the connection
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            for _ in 0..2 {
+                let msg = websocket.next().await.unwrap().unwrap();
+                websocket.send(msg).await.unwrap();
+            }
+            websocket.send(Message::Pong(b"hb".to_vec())).await.unwrap();
+            drop(websocket);
+
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            while websocket.next().await.is_some() {}
+        });
+
+        let mut config = silent_config(port);
+        config.reconnect_delay_initial_ms = Some(10);
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+        assert_eq!(client.metrics(), ClientMetrics::default());
+
+        client.send_bytes(b"hello".to_vec(), None).await.unwrap();
+        client.send_bytes(b"world!".to_vec(), None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while client.metrics().last_pong_at.is_none() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.frames_sent, 2);
+        assert_eq!(metrics.bytes_sent, 11);
+        assert_eq!(metrics.frames_recv, 3);
+        assert_eq!(metrics.bytes_recv, 13);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.metrics().reconnects == 0 {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let after = client.metrics();
+        assert_eq!(after.reconnects, 1);
+        assert_eq!(after.frames_sent, metrics.frames_sent);
+        assert_eq!(after.last_pong_at, metrics.last_pong_at);
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    /// Spawns a server replying to each data frame received with the next of the `replies`
+    /// as a text frame, returning the port it is listening on.
+    async fn start_reply_server(replies: Vec<&'static str>) -> (u16, JoinHandle<()>) {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            let mut replies = replies.into_iter();
+            while let Some(Ok(msg)) = websocket.next().await {
+                if msg.is_binary() || msg.is_text() {
+                    let Some(reply) = replies.next() else { break };
+                    websocket
+                        .send(Message::Text(reply.to_string()))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+        (port, server_task)
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(tag = "e")]
+    enum VenueEvent {
+        #[serde(rename = "trade")]
+        Trade {
+            #[serde(rename = "s")]
+            symbol: String,
+            #[serde(rename = "p")]
+            price: String,
+            #[serde(rename = "q")]
+            quantity: String,
+        },
+    }
+
+    #[tokio::test]
+    async fn recv_json_test() {
+        prepare_freethreaded_python();
+        let (port, server_task) = start_reply_server(vec![
+            r#"{"e":"trade","E":1672515782136,"s":"BNBBTC","t":12345,"p":"0.001","q":"100"}"#,
+        ])
+        .await;
+        let client = WebSocketClient::connect(silent_config(port), None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        // The receive is registered when first polled, ahead of the request being sent
+        let (event, sent) = tokio::join!(
+            client.recv_json::<VenueEvent>(Some("/e")),
+            client.send_bytes(b"subscribe".to_vec(), None),
+        );
+
+        sent.unwrap();
+        assert_eq!(
+            event.unwrap(),
+            VenueEvent::Trade {
+                symbol: "BNBBTC".to_string(),
+                price: "0.001".to_string(),
+                quantity: "100".to_string(),
+            }
+        );
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn recv_json_error_includes_payload_snippet_test() {
+        prepare_freethreaded_python();
+        let payload = r#"{"e":"trade","s":"BNBBTC","p":0.001}"#;
+        let (port, server_task) = start_reply_server(vec![payload]).await;
+        let client = WebSocketClient::connect(silent_config(port), None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        let (result, sent) = tokio::join!(
+            client.recv_json::<VenueEvent>(Some("/e")),
+            client.send_bytes(b"subscribe".to_vec(), None),
+        );
+
+        sent.unwrap();
+        let e = result.unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("Error deserializing text frame tagged `trade`: "));
+        assert!(e.to_string().ends_with(&format!("in `{payload}`")), "{e}");
+        assert!(matches!(
+            e,
+            RecvJsonError::Deserialize { tag: Some(tag), snippet, .. } if tag == "trade" && snippet == payload
+        ));
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    #[rstest]
+    #[case(("X-MBX-APIKEY", "key\u{e9}"), "Invalid value for websocket header `X-MBX-APIKEY`: values must be visible ASCII")]
+    #[case(("X MBX APIKEY", "key"), "Invalid websocket header name `X MBX APIKEY`")]
+    #[tokio::test]
+    async fn invalid_header_test(#[case] header: (&str, &str), #[case] expected: &str) {
+        prepare_freethreaded_python();
+
+        let handler =
+            Python::with_gil(|py| py.eval("lambda _: None", None, None).unwrap().into_py(py));
+        let config = WebSocketConfig::py_new(
+            "ws://127.0.0.1:1".to_string(),
+            handler,
+            vec![(header.0.to_string(), header.1.to_string())],
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+
+        let result = WebSocketClient::connect(config, None, None, None, vec![], None).await;
+
+        match result {
+            Err(Error::Io(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+                assert_eq!(e.to_string(), expected);
+            }
+            _ => panic!("Expected invalid header error"),
+        }
+    }
+
+    #[rstest]
+    #[case(BackpressurePolicy::Block)]
+    #[case(BackpressurePolicy::DropOldest)]
+    #[case(BackpressurePolicy::Error)]
+    #[tokio::test]
+    async fn send_queue_backpressure_test(#[case] policy: BackpressurePolicy) {
+        prepare_freethreaded_python();
+
+        // The server never reads, so the socket buffers fill and the queue backs up
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let _websocket = accept_async(conn).await.unwrap();
+            sleep(Duration::from_secs(60)).await;
+        });
+
+        let handler =
+            Python::with_gil(|py| py.eval("lambda _: None", None, None).unwrap().into_py(py));
+        let config = WebSocketConfig::py_new(
+            format!("ws://127.0.0.1:{port}"),
+            handler,
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(2),
+            Some(policy),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        const SIZE: usize = 1024 * 1024;
+        const ATTEMPTS: usize = 256;
+        let mut result = Ok(());
+        for _ in 0..ATTEMPTS {
+            let send = client.send_bytes(vec![0; SIZE], None);
+            result = match tokio::time::timeout(Duration::from_millis(500), send).await {
+                Ok(result) => result,
+                Err(_) => {
+                    result = Err(Error::Io(std::io::ErrorKind::TimedOut.into()));
+                    break;
+                }
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+
+        assert_eq!(client.send_queue_depth(), 2);
+        match policy {
+            BackpressurePolicy::Block => match result {
+                Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::TimedOut),
+                _ => panic!("Expected send to block"),
+            },
+            BackpressurePolicy::DropOldest => assert!(result.is_ok()),
+            BackpressurePolicy::Error => match result {
+                Err(Error::Io(e)) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock);
+                    assert_eq!(e.to_string(), "Send queue full (2 messages)");
+                }
+                _ => panic!("Expected send queue full error"),
+            },
+        }
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn try_send_full_queue_test() {
+        prepare_freethreaded_python();
+
+        // The server never reads, so the socket buffers fill and the queue backs up
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let _websocket = accept_async(conn).await.unwrap();
+            sleep(Duration::from_secs(60)).await;
+        });
+
+        let mut config = silent_config(port);
+        config.send_queue_capacity = Some(2);
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        const SIZE: usize = 1024 * 1024;
+        const ATTEMPTS: usize = 256;
+        let mut result = Ok(());
+        for _ in 0..ATTEMPTS {
+            let start = Instant::now();
+            result = client.try_send(Message::Binary(vec![0; SIZE]));
+            assert!(start.elapsed() < Duration::from_millis(100));
+            if result.is_err() {
+                break;
+            }
+            // Let the send task drain the queue into the socket buffers
+            task::yield_now().await;
+        }
+
+        assert_eq!(client.send_queue_depth(), 2);
+        match result {
+            Err(e @ TrySendError::Full(_)) => {
+                assert_eq!(e.to_string(), "Send queue full");
+                assert_eq!(e.into_message().len(), SIZE);
+            }
+            _ => panic!("Expected send queue full error"),
+        }
+
+        server_task.abort();
+    }
+
+    #[rstest]
+    #[case(BackpressurePolicy::Block, false)]
+    #[case(BackpressurePolicy::Error, false)]
+    #[case(BackpressurePolicy::DropOldest, true)]
+    fn try_push_checks_capacity_before_admitting_test(
+        #[case] policy: BackpressurePolicy,
+        #[case] expected_admitted: bool,
+    ) {
+        let send_queue = SendQueue::new(1, policy);
+        let admitted = AtomicUsize::new(0);
+        let admit = |_: &Message| {
+            admitted.fetch_add(1, Ordering::SeqCst);
+            true
+        };
+        send_queue
+            .try_push(Message::Text("first".to_string()), admit)
+            .unwrap();
+
+        let result = send_queue.try_push(Message::Text("second".to_string()), admit);
+
+        assert_eq!(result.is_ok(), expected_admitted);
+        assert_eq!(
+            admitted.load(Ordering::SeqCst),
+            1 + usize::from(expected_admitted)
+        );
+    }
+
+    #[rstest]
+    fn try_push_not_admitted_test() {
+        let send_queue = SendQueue::new(1, BackpressurePolicy::Error);
+
+        let result = send_queue.try_push(Message::Text("hello".to_string()), |_| false);
+
+        assert!(matches!(result, Err(TrySendError::RateLimited(_))));
+    }
+
+    #[tokio::test]
+    async fn try_send_without_queue_test() {
+        prepare_freethreaded_python();
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+
+        let mut config = silent_config(server.port);
+        config.headers = vec![(header_key, header_value)];
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        let result = client.try_send(Message::Text("hello".to_string()));
+
+        assert!(matches!(result, Err(TrySendError::NoQueue(_))));
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn split_concurrent_read_write_test() {
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+
+        let (mut reader, mut writer) = WebSocketClient::connect_split(
+            &format!("ws://127.0.0.1:{}", server.port),
+            vec![(header_key, header_value)],
+            None,
+        )
+        .await
+        .unwrap();
+
+        // Keep reading until the writer closes the connection
+        let read_task = task::spawn(async move {
+            let mut received = Vec::new();
+            while let Some(message) = reader.next().await {
+                received.push(message.unwrap().into_text().unwrap());
+            }
+            received
+        });
+
+        let write_task = task::spawn(async move {
+            for i in 0..10 {
+                writer
+                    .send(Message::Text(format!("msg-{i}")))
+                    .await
+                    .unwrap();
+            }
+            // Give the echoes time to arrive before closing
+            sleep(Duration::from_millis(200)).await;
+            writer.close().await.unwrap();
+        });
+
+        write_task.await.unwrap();
+        let received = tokio::time::timeout(Duration::from_secs(2), read_task)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let expected: Vec<String> = (0..10).map(|i| format!("msg-{i}")).collect();
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn split_binary_reader_test() {
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+
+        let (reader, mut writer) = WebSocketClient::connect_split(
+            &format!("ws://127.0.0.1:{}", server.port),
+            vec![(header_key, header_value)],
+            None,
+        )
+        .await
+        .unwrap();
+        let mut reader = reader.into_binary();
+
+        // The echoed text frame is skipped
+        writer
+            .send(Message::Text("text".to_string()))
+            .await
+            .unwrap();
+        writer
+            .send(Message::Binary(b"header:payload".to_vec()))
+            .await
+            .unwrap();
+
+        let payload = tokio::time::timeout(Duration::from_secs(2), reader.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload, &b"header:payload"[..]);
+        assert_eq!(payload.slice(7..), &b"payload"[..]);
+
+        writer.close().await.unwrap();
+        assert!(reader.next().await.is_none());
+    }
+
+    #[rstest]
+    fn binary_payload_test() {
+        let data = b"payload".to_vec();
+        let ptr = data.as_ptr();
+
+        let payload = binary_payload(Message::Binary(data)).unwrap();
+
+        // The payload shares the received buffer
+        assert_eq!(payload.as_ptr(), ptr);
+        assert!(binary_payload(Message::Text("payload".to_string())).is_none());
+    }
+
+    #[tokio::test]
+    async fn binary_consumer_test() {
+        prepare_freethreaded_python();
+
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut config = silent_config(server.port);
+        config.headers = vec![(header_key, header_value)];
+        config.binary_consumer = Some(tx);
+
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        // The echoed binary frame is sent to the consumer rather than the handler
+        client
+            .send_bytes(b"header:payload".to_vec(), None)
+            .await
+            .unwrap();
+
+        let payload = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload, &b"header:payload"[..]);
+        assert_eq!(payload.slice(7..), &b"payload"[..]);
+
+        client.disconnect().await;
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    #[tokio::test]
+    async fn connect_with_server_applies_socket_options_test(#[case] nodelay: bool) {
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+        let socket_options = SocketOptions {
+            nodelay,
+            ..SocketOptions::default()
+        };
+
+        let (writer, reader) = WebSocketClientInner::connect_with_server(
+            &format!("ws://127.0.0.1:{}", server.port),
+            vec![(header_key, header_value)],
+            None,
+            &socket_options,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let stream = reader.reunite(writer).unwrap();
+        let MaybeTlsStream::Plain(stream) = stream.get_ref() else {
+            panic!("Expected a plain stream");
+        };
+        assert_eq!(stream.get_ref().nodelay().unwrap(), nodelay);
+    }
+
+    #[tokio::test]
+    async fn split_reader_close_propagates_to_writer_test() {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            websocket.close(None).await.unwrap();
+            sleep(Duration::from_secs(60)).await;
+        });
+
+        let (mut reader, mut writer) =
+            WebSocketClient::connect_split(&format!("ws://127.0.0.1:{port}"), vec![], None)
+                .await
+                .unwrap();
+
+        assert!(matches!(reader.next().await, Some(Ok(Message::Close(_)))));
+        let result = writer.send(Message::Text("late".to_string())).await;
+
+        assert!(matches!(result, Err(Error::AlreadyClosed)));
+        server_task.abort();
+    }
+
+    #[rstest]
+    fn subscription_manager_test() {
+        let mut subscriptions = SubscriptionManager::new();
+
+        assert!(subscriptions.subscribe("trades".to_string(), "sub-trades".to_string(), None));
+        assert!(subscriptions.subscribe("quotes".to_string(), "sub-quotes".to_string(), None));
+        assert!(!subscriptions.subscribe("trades".to_string(), "sub-trades".to_string(), None));
+        assert_eq!(subscriptions.topics(), vec!["trades", "quotes"]);
+
+        assert!(subscriptions.unsubscribe("trades"));
+        assert!(!subscriptions.unsubscribe("trades"));
+        assert!(!subscriptions.is_subscribed("trades"));
+        assert_eq!(subscriptions.len(), 1);
+    }
+
+    #[rstest]
+    fn subscription_manager_ack_timeout_test() {
+        let timeout = Duration::from_secs(1);
+        let mut subscriptions = SubscriptionManager::with_ack_timeout(timeout);
+        for topic in ["trades", "quotes", "bars"] {
+            subscriptions.subscribe(topic.to_string(), format!("sub-{topic}"), None);
+        }
+
+        assert!(subscriptions.ack("bars"));
+        assert!(!subscriptions.ack("bars"));
+        assert!(!subscriptions.ack("unknown"));
+        assert!(subscriptions.is_acked("bars"));
+        assert!(!subscriptions.all_acked());
+        assert_eq!(subscriptions.unacked(), vec!["trades", "quotes"]);
+
+        // Unacked subscriptions expire in the order they were subscribed, once per timeout
+        let now = Instant::now();
+        assert!(subscriptions.expired_messages(now).is_empty());
+        let expired: Vec<String> = subscriptions
+            .expired_messages(now + timeout)
+            .into_iter()
+            .map(|(message, _)| message)
+            .collect();
+        assert_eq!(expired, vec!["sub-trades", "sub-quotes"]);
+        assert!(subscriptions.expired_messages(now + timeout).is_empty());
+
+        assert!(subscriptions.ack("quotes"));
+        assert!(subscriptions.unsubscribe("trades"));
+        assert!(subscriptions.all_acked());
+    }
+
+    #[rstest]
+    fn subscription_manager_without_ack_timeout_test() {
+        let mut subscriptions = SubscriptionManager::new();
+        subscriptions.subscribe("trades".to_string(), "sub-trades".to_string(), None);
+
+        assert!(subscriptions.all_acked());
+        assert!(!subscriptions.ack("trades"));
+        assert!(subscriptions
+            .expired_messages(Instant::now() + Duration::from_secs(3600))
+            .is_empty());
+    }
+
+    #[rstest]
+    #[case::json(
+        r#"{"op":"login","apiKey":"k3y","ts":1}"#,
+        r#"{"op":"login","apiKey":"***","ts":1}"#
+    )]
+    #[case::json_spaced(r#"{"api_secret" : "s3cr3t"}"#, r#"{"api_secret" : "***"}"#)]
+    #[case::query(
+        "symbol=BTCUSDT&signature=abc123&recvWindow=5000",
+        "symbol=BTCUSDT&signature=***&recvWindow=5000"
+    )]
+    #[case::unquoted(r#"{"accountId":12345}"#, r#"{"accountId":***}"#)]
+    #[case::key_like_token(
+        r#"["auth","3a9f1c2b7d4e8f6a0b5c9d1e2f3a4b5c6d7e8f90"]"#,
+        r#"["auth","***"]"#
+    )]
+    #[case::nested_object(r#"{"keys":{"a":1}}"#, r#"{"keys":{"a":1}}"#)]
+    #[case::nothing_sensitive(
+        r#"{"op":"subscribe","args":["trades"]}"#,
+        r#"{"op":"subscribe","args":["trades"]}"#
+    )]
+    fn redact_secrets_test(#[case] text: &str, #[case] expected: &str) {
+        let message = Message::Text(text.to_string());
+        assert_eq!(redact_secrets(&message), expected);
+    }
+
+    #[rstest]
+    fn redact_secrets_binary_test() {
+        let message = Message::Binary(b"apiKey=secret".to_vec());
+        assert_eq!(redact_secrets(&message), "<binary 13 bytes>");
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn frame_tracing_masks_secrets_test() {
+        const HEADER_KEY: &str = "hello-custom-key";
+        const HEADER_VALUE: &str = "hello-custom-value";
+
+        prepare_freethreaded_python();
+
+        let server = TestServer::setup(HEADER_KEY.to_string(), HEADER_VALUE.to_string()).await;
+        let mut config = silent_config(server.port);
+        config.headers = vec![(HEADER_KEY.to_string(), HEADER_VALUE.to_string())];
+        config.frame_redactor = Some(redact_secrets);
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        client
+            .subscribe(
+                "orders".to_string(),
+                r#"{"op":"login","apiKey":"hunter2"}"#.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The server echoes the message back
+        sleep(Duration::from_millis(500)).await;
+        assert!(logs_contain(r#"Sent frame: {"op":"login","apiKey":"***"}"#));
+        assert!(logs_contain(
+            r#"Received frame: {"op":"login","apiKey":"***"}"#
+        ));
+        logs_assert(|lines: &[&str]| {
+            match lines
+                .iter()
+                .filter(|line| line.contains(" frame: "))
+                .find(|line| line.contains("hunter2"))
+            {
+                Some(line) => Err(format!("Unmasked frame: {line}")),
+                None => Ok(()),
+            }
+        });
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+    }
+
+    fn parse_ack(message: &Message) -> Option<String> {
+        message
+            .to_text()
+            .ok()?
+            .strip_prefix("ack-")
+            .map(ToString::to_string)
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn subscriptions_resent_until_acked_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let received: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let messages = received.clone();
+
+        // Acks the first subscriptions out of order, dropping the ack for `quotes` until it
+        // is resent
+        let server_task = task::spawn(async move {
+            let (conn, _) = server.accept().await.unwrap();
+            let mut websocket = accept_async(conn).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = websocket.next().await {
+                let count = {
+                    let mut messages = messages.lock().unwrap();
+                    messages.push(text.clone());
+                    messages.len()
+                };
+                let acks: &[&str] = match count {
+                    3 => &["ack-bars", "ack-trades"],
+                    4 => &["ack-quotes"],
+                    _ => &[],
+                };
+                for ack in acks {
+                    websocket
+                        .send(Message::Text(ack.to_string()))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let mut config = silent_config(port);
+        config.subscription_ack_timeout_ms = Some(300);
+        config.subscription_ack_parser = Some(parse_ack);
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        for topic in ["trades", "quotes", "bars"] {
+            client
+                .subscribe(topic.to_string(), format!("sub-{topic}"), None)
+                .await
+                .unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(5), client.subscriptions_acked())
+            .await
+            .expect("Subscriptions not acked");
+
+        assert!(logs_contain("Resending 1 unacked subscriptions"));
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["sub-trades", "sub-quotes", "sub-bars", "sub-quotes"]
+        );
+
+        client.disconnect().await;
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn subscriptions_acked_without_ack_tracking_test() {
+        prepare_freethreaded_python();
+        let header_key = "hello-custom-key".to_string();
+        let header_value = "hello-custom-value".to_string();
+        let server = TestServer::setup(header_key.clone(), header_value.clone()).await;
+
+        let mut config = silent_config(server.port);
+        config.headers = vec![(header_key, header_value)];
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+        client
+            .subscribe("trades".to_string(), "sub-trades".to_string(), None)
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), client.subscriptions_acked())
+            .await
+            .expect("Subscriptions not acked");
+        client.disconnect().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn subscriptions_replayed_on_reconnect_test() {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let received: Arc<std::sync::Mutex<Vec<Vec<String>>>> = Arc::default();
+        let connections = received.clone();
+
+        // Records the text messages received on each connection, and closes the connection
+        // when sent a binary `drop` message
+        let server_task = task::spawn(async move {
+            loop {
+                let (conn, _) = server.accept().await.unwrap();
+                let mut websocket = accept_async(conn).await.unwrap();
+                let index = {
+                    let mut connections = connections.lock().unwrap();
+                    connections.push(Vec::new());
+                    connections.len() - 1
+                };
+                let connections = connections.clone();
+                task::spawn(async move {
+                    while let Some(Ok(msg)) = websocket.next().await {
+                        match msg {
+                            Message::Text(text) => connections.lock().unwrap()[index].push(text),
+                            Message::Binary(data) if data == b"drop" => {
+                                let _ = websocket.close(None).await;
+                            }
+                            _ => (),
+                        }
+                    }
+                });
+            }
+        });
+
+        let client = WebSocketClient::connect(silent_config(port), None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        for topic in ["trades", "quotes", "trades"] {
+            client
+                .subscribe(topic.to_string(), format!("sub-{topic}"), None)
+                .await
+                .unwrap();
+        }
+        client
+            .unsubscribe("quotes", "unsub-quotes".to_string(), None)
+            .await
+            .unwrap();
+        client.send_bytes(b"drop".to_vec(), None).await.unwrap();
+
+        // The client reconnects and resubscribes to the remaining topic
+        sleep(Duration::from_secs(1)).await;
+        assert!(logs_contain("Replaying 1 subscriptions"));
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                vec!["sub-trades", "sub-quotes", "unsub-quotes"],
+                vec!["sub-trades"],
+            ]
+        );
+        assert_eq!(client.subscriptions(), vec!["trades"]);
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+        server_task.abort();
+    }
+
+    #[traced_test]
+    #[rstest]
+    #[case(None)]
+    #[case(Some(1 << 20))]
+    #[tokio::test]
+    async fn max_message_size_test(#[case] read_buffer_size: Option<usize>) {
+        prepare_freethreaded_python();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+
+        // Sends a message within the limit, then one exceeding it, on the first connection
+        let server_task = task::spawn(async move {
+            let mut first = true;
+            loop {
+                let (conn, _) = server.accept().await.unwrap();
+                let mut websocket = accept_async(conn).await.unwrap();
+                let sizes = if first { vec![1024, 1025] } else { vec![] };
+                first = false;
+                task::spawn(async move {
+                    for size in sizes {
+                        websocket
+                            .send(Message::Binary(vec![0; size]))
+                            .await
+                            .unwrap();
+                    }
+                    while websocket.next().await.is_some() {}
+                });
+            }
+        });
+
+        let mut config = silent_config(port);
+        config.max_message_size = Some(1024);
+        config.read_buffer_size = read_buffer_size;
+        let client = WebSocketClient::connect(config, None, None, None, vec![], None)
+            .await
+            .unwrap();
+
+        sleep(Duration::from_millis(500)).await;
+        assert!(logs_contain("Received message <binary>"));
+        assert!(logs_contain(
+            "Received error message - terminating: Space limit exceeded: Message too long: 1025 > 1024"
+        ));
+
+        client.disconnect().await;
+        assert!(client.is_disconnected());
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn connect_cancelled_during_stalled_handshake_test() {
+        prepare_freethreaded_python();
+
+        // Accepts the TCP connection but never responds to the websocket handshake
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = TcpListener::local_addr(&server).unwrap().port();
+        let server_task = task::spawn(async move { server.accept().await });
+
+        let token = CancellationToken::new();
+        let mut config = silent_config(port);
+        config.cancellation_token = Some(token.clone());
+        task::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            token.cancel();
+        });
+
+        let start = Instant::now();
+        let result = WebSocketClient::connect(config, None, None, None, vec![], None).await;
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(is_cancelled(&result.err().unwrap()));
+        server_task.abort();
+    }
 }
@@ -184,7 +184,7 @@ where
     pub async fn until_key_ready(&self, key: &K) {
         loop {
             match self.check_key(key) {
-                Ok(x) => x,
+                Ok(()) => break,
                 Err(neg) => {
                     sleep(neg.wait_time_from(self.clock.now())).await;
                 }
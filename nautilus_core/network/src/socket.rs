@@ -16,6 +16,7 @@
 //! A high-performance raw TCP client implementation with TLS capability.
 
 use std::{
+    io::{self, ErrorKind},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -29,10 +30,11 @@ use rustls::{
     crypto::{aws_lc_rs, ring, CryptoProvider},
     ClientConfig, RootCertStore,
 };
+use socket2::{SockRef, TcpKeepalive};
 use tokio::{
     io::{split, AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf},
     net::TcpStream,
-    sync::Mutex,
+    sync::{Mutex, Semaphore},
     task,
     time::sleep,
 };
@@ -41,12 +43,34 @@ use tokio_tungstenite::{
     MaybeTlsStream,
 };
 
-use crate::tls::tcp_tls;
+use crate::{
+    dns::DnsCache,
+    happy_eyeballs::{connect_host, DEFAULT_ATTEMPT_DELAY},
+    tls::{tcp_tls_limited, tcp_tls_with_fallback, Connector},
+};
 
 type TcpWriter = WriteHalf<MaybeTlsStream<TcpStream>>;
 type SharedTcpWriter = Arc<Mutex<WriteHalf<MaybeTlsStream<TcpStream>>>>;
 type TcpReader = ReadHalf<MaybeTlsStream<TcpStream>>;
 
+/// Splits the `host:port` of the `url` (e.g. `example.com:443` or `[::1]:443`).
+fn split_host_port(url: &str) -> io::Result<(&str, u16)> {
+    url.rsplit_once(':')
+        .and_then(|(host, port)| {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            Some((host, port.parse().ok()?))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid `host:port` address: {url}"),
+            )
+        })
+}
+
+/// The default maximum length (bytes) of a length prefixed message (16 MiB).
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
 /// Configuration for TCP socket connection.
 #[derive(Debug, Clone)]
 #[cfg_attr(
@@ -64,6 +88,176 @@ pub struct SocketConfig {
     pub handler: PyObject,
     /// The optional heartbeat with period and beat message.
     pub heartbeat: Option<(u64, Vec<u8>)>,
+    /// The optional size (bytes) of a big-endian length prefix which frames messages
+    /// instead of the suffix.
+    pub length_prefix: Option<usize>,
+    /// The maximum length (bytes) of a received length prefixed message, beyond which the
+    /// connection is closed ([`DEFAULT_MAX_FRAME_LEN`] if `None`).
+    pub max_frame_len: Option<usize>,
+    /// The connector used to wrap the connection with TLS (the default `rustls` config if
+    /// `None`).
+    pub connector: Option<Connector>,
+    /// The options applied to the TCP socket before it is wrapped with TLS.
+    pub socket_options: SocketOptions,
+    /// The cache the server host is resolved through when connecting (resolved for every
+    /// connection if `None`).
+    pub dns_cache: Option<Arc<DnsCache>>,
+    /// The semaphore shared between clients bounding their concurrent TLS handshakes
+    /// (unbounded if `None`).
+    pub handshake_limit: Option<Arc<Semaphore>>,
+}
+
+/// Options applied to a TCP socket once connected, before it is wrapped with TLS.
+///
+/// Options left as `None` keep the operating system defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Whether to disable Nagle's algorithm (`TCP_NODELAY`), so small writes such as orders
+    /// are sent immediately rather than coalesced.
+    pub nodelay: bool,
+    /// The idle time before TCP keepalive probes are sent (keepalive disabled if `None`).
+    pub keepalive: Option<Duration>,
+    /// The size (bytes) of the socket receive buffer (`SO_RCVBUF`).
+    pub recv_buf: Option<usize>,
+    /// The size (bytes) of the socket send buffer (`SO_SNDBUF`).
+    pub send_buf: Option<usize>,
+}
+
+impl Default for SocketOptions {
+    /// Creates the default options for latency sensitive (e.g. order entry) connections,
+    /// with Nagle's algorithm disabled.
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: None,
+            recv_buf: None,
+            send_buf: None,
+        }
+    }
+}
+
+impl SocketOptions {
+    /// Applies the options to the given `stream`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any option cannot be set on the socket.
+    pub fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_nodelay(self.nodelay)?;
+
+        let socket = SockRef::from(stream);
+        if let Some(time) = self.keepalive {
+            socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+        }
+        if let Some(size) = self.recv_buf {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buf {
+            socket.set_send_buffer_size(size)?;
+        }
+        Ok(())
+    }
+}
+
+/// The framing of messages on the byte stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Framing {
+    suffix: Vec<u8>,
+    length_prefix: Option<usize>,
+    max_frame_len: usize,
+}
+
+impl Framing {
+    /// Creates the framing for the given `config`.
+    fn new(config: &SocketConfig) -> Result<Self, std::io::Error> {
+        if let Some(size) = config.length_prefix {
+            if !(1..=8).contains(&size) {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid length prefix size {size}, expected 1 to 8 bytes"),
+                ));
+            }
+        }
+
+        Ok(Self {
+            suffix: config.suffix.clone(),
+            length_prefix: config.length_prefix,
+            max_frame_len: config.max_frame_len.unwrap_or(DEFAULT_MAX_FRAME_LEN),
+        })
+    }
+
+    /// Returns the `data` framed for sending.
+    pub(crate) fn encode(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        match self.length_prefix {
+            Some(size) => {
+                let len = data.len() as u64;
+                if size < 8 && len >> (size * 8) != 0 {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Message of {len} bytes exceeds {size} byte length prefix"),
+                    ));
+                }
+                let mut frame = len.to_be_bytes()[8 - size..].to_vec();
+                frame.extend_from_slice(data);
+                Ok(frame)
+            }
+            None => {
+                let mut frame = data.to_vec();
+                frame.extend_from_slice(&self.suffix);
+                Ok(frame)
+            }
+        }
+    }
+
+    /// Drains the next complete message from the `buf`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a length prefix exceeds the maximum frame length, in which case
+    /// the stream cannot be decoded any further.
+    pub(crate) fn decode(&self, buf: &mut Vec<u8>) -> Result<Option<Vec<u8>>, std::io::Error> {
+        match self.length_prefix {
+            Some(size) => {
+                let Some(prefix) = buf.get(..size) else {
+                    return Ok(None);
+                };
+                let len = prefix
+                    .iter()
+                    .fold(0u64, |len, byte| (len << 8) | u64::from(*byte));
+                let frame_len = usize::try_from(len)
+                    .ok()
+                    .filter(|len| *len <= self.max_frame_len)
+                    .and_then(|len| len.checked_add(size))
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "Message of {len} bytes exceeds maximum frame length of {} bytes",
+                                self.max_frame_len
+                            ),
+                        )
+                    })?;
+                if buf.len() < frame_len {
+                    return Ok(None);
+                }
+                let data = buf[size..frame_len].to_vec();
+                buf.drain(..frame_len);
+                Ok(Some(data))
+            }
+            None => {
+                let Some((i, _)) = buf
+                    .windows(self.suffix.len())
+                    .enumerate()
+                    .find(|(_, pair)| pair.eq(&self.suffix))
+                else {
+                    return Ok(None);
+                };
+                let mut data: Vec<u8> = buf.drain(0..i + self.suffix.len()).collect();
+                data.truncate(data.len() - self.suffix.len());
+                Ok(Some(data))
+            }
+        }
+    }
 }
 
 /// Creates a TcpStream with the server.
@@ -80,7 +274,9 @@ pub struct SocketConfig {
 ///
 /// The client uses a suffix to separate messages on the byte stream. It is
 /// appended to all sent messages and heartbeats. It is also used the split
-/// the received byte stream.
+/// the received byte stream. Alternatively messages can be framed with a
+/// length prefix, which suits binary protocols whose payloads may contain
+/// any byte sequence.
 #[cfg_attr(
     feature = "python",
     pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.network")
@@ -103,22 +299,35 @@ impl SocketClientInner {
             }
         }
 
+        let framing = Framing::new(&config)?;
         let SocketConfig {
             url,
             mode,
             heartbeat,
-            suffix,
             handler,
+            connector,
+            socket_options,
+            dns_cache,
+            handshake_limit,
+            ..
         } = &config;
-        let (reader, writer) = Self::tls_connect_with_server(url, *mode).await?;
+        let (reader, writer) = Self::tls_connect_with_server(
+            url,
+            *mode,
+            connector.clone(),
+            socket_options,
+            dns_cache.as_deref(),
+            handshake_limit.as_deref(),
+        )
+        .await?;
         let shared_writer = Arc::new(Mutex::new(writer));
 
         // Keep receiving messages from socket pass them as arguments to handler
-        let read_task = Self::spawn_read_task(reader, handler.clone(), suffix.clone());
+        let read_task = Self::spawn_read_task(reader, handler.clone(), framing.clone());
 
         // Optionally create heartbeat task
         let heartbeat_task =
-            Self::spawn_heartbeat_task(heartbeat.clone(), shared_writer.clone(), suffix.clone());
+            Self::spawn_heartbeat_task(heartbeat.clone(), shared_writer.clone(), framing)?;
 
         Ok(Self {
             config,
@@ -128,22 +337,52 @@ impl SocketClientInner {
         })
     }
 
+    /// Connects with the server, applying the `socket_options` to the TCP stream before it
+    /// is wrapped with TLS (for the `Tls` mode). The `host:port` of the `url` is resolved
+    /// through the `dns_cache` if given, and a permit is acquired from the `handshake_limit`
+    /// if given before the TLS handshake (see [`tcp_tls_limited`]). The server is reconnected
+    /// once if the handshake fails with an error the `connector` falls back on (see
+    /// [`tcp_tls_with_fallback`]).
     pub async fn tls_connect_with_server(
         url: &str,
         mode: Mode,
+        connector: Option<Connector>,
+        socket_options: &SocketOptions,
+        dns_cache: Option<&DnsCache>,
+        handshake_limit: Option<&Semaphore>,
     ) -> Result<(TcpReader, TcpWriter), Error> {
-        tracing::debug!("Connecting to server");
-        let stream = TcpStream::connect(url).await?;
-        tracing::debug!("Making TLS connection");
         let request = url.into_client_request()?;
-        tcp_tls(&request, mode, stream, None).await.map(split)
+        let connect = || async {
+            tracing::debug!("Connecting to server");
+            let stream = match dns_cache {
+                Some(cache) => {
+                    let (host, port) = split_host_port(url)?;
+                    cache.connect(host, port, DEFAULT_ATTEMPT_DELAY).await?
+                }
+                None => connect_host(url, DEFAULT_ATTEMPT_DELAY).await?,
+            };
+            socket_options.apply(&stream)?;
+            tracing::debug!("Making TLS connection");
+            Ok(stream)
+        };
+        tcp_tls_with_fallback(
+            &request,
+            mode,
+            connect,
+            connector,
+            None,
+            None,
+            handshake_limit,
+        )
+        .await
+        .map(split)
     }
 
     #[must_use]
     pub fn spawn_read_task(
         mut reader: TcpReader,
         handler: PyObject,
-        suffix: Vec<u8>,
+        framing: Framing,
     ) -> task::JoinHandle<()> {
         // Keep receiving messages from socket pass them as arguments to handler
         task::spawn(async move {
@@ -164,16 +403,17 @@ impl SocketClientInner {
                     Ok(bytes) => {
                         tracing::debug!("Received {bytes} bytes of data");
 
-                        // While received data has a line break
+                        // While received data has a complete message
                         // drain it and pass it to the handler
-                        while let Some((i, _)) = &buf
-                            .windows(suffix.len())
-                            .enumerate()
-                            .find(|(_, pair)| pair.eq(&suffix))
-                        {
-                            let mut data: Vec<u8> = buf.drain(0..i + suffix.len()).collect();
-                            data.truncate(data.len() - suffix.len());
-
+                        loop {
+                            let data = match framing.decode(&mut buf) {
+                                Ok(Some(data)) => data,
+                                Ok(None) => break,
+                                Err(e) => {
+                                    tracing::error!("Closing connection: {e}");
+                                    return;
+                                }
+                            };
                             if let Err(e) =
                                 Python::with_gil(|py| handler.call1(py, (data.as_slice(),)))
                             {
@@ -191,23 +431,25 @@ impl SocketClientInner {
     pub fn spawn_heartbeat_task(
         heartbeat: Option<(u64, Vec<u8>)>,
         writer: SharedTcpWriter,
-        suffix: Vec<u8>,
-    ) -> Option<task::JoinHandle<()>> {
-        heartbeat.map(|(duration, mut message)| {
-            task::spawn(async move {
-                let duration = Duration::from_secs(duration);
-                message.extend(suffix);
-                loop {
-                    sleep(duration).await;
-                    tracing::debug!("Sending heartbeat");
-                    let mut guard = writer.lock().await;
-                    match guard.write_all(&message).await {
-                        Ok(()) => tracing::debug!("Sent heartbeat"),
-                        Err(e) => tracing::error!("Failed to send heartbeat: {e}"),
+        framing: Framing,
+    ) -> Result<Option<task::JoinHandle<()>>, std::io::Error> {
+        heartbeat
+            .map(|(duration, message)| {
+                let message = framing.encode(&message)?;
+                Ok(task::spawn(async move {
+                    let duration = Duration::from_secs(duration);
+                    loop {
+                        sleep(duration).await;
+                        tracing::debug!("Sending heartbeat");
+                        let mut guard = writer.lock().await;
+                        match guard.write_all(&message).await {
+                            Ok(()) => tracing::debug!("Sent heartbeat"),
+                            Err(e) => tracing::error!("Failed to send heartbeat: {e}"),
+                        }
                     }
-                }
+                }))
             })
-        })
+            .transpose()
     }
 
     /// Shutdown read task and the connection.
@@ -242,15 +484,28 @@ impl SocketClientInner {
     ///
     /// TODO: fix error type
     pub async fn reconnect(&mut self) -> Result<(), Error> {
+        let framing = Framing::new(&self.config)?;
         let SocketConfig {
             url,
             mode,
             heartbeat,
-            suffix,
             handler,
+            connector,
+            socket_options,
+            dns_cache,
+            handshake_limit,
+            ..
         } = &self.config;
         tracing::debug!("Reconnecting client");
-        let (reader, new_writer) = Self::tls_connect_with_server(url, *mode).await?;
+        let (reader, new_writer) = Self::tls_connect_with_server(
+            url,
+            *mode,
+            connector.clone(),
+            socket_options,
+            dns_cache.as_deref(),
+            handshake_limit.as_deref(),
+        )
+        .await?;
 
         tracing::debug!("Use new writer end");
         let mut guard = self.writer.lock().await;
@@ -258,9 +513,9 @@ impl SocketClientInner {
         drop(guard);
 
         tracing::debug!("Recreate reader and heartbeat task");
-        self.read_task = Self::spawn_read_task(reader, handler.clone(), suffix.clone());
+        self.read_task = Self::spawn_read_task(reader, handler.clone(), framing.clone());
         self.heartbeat_task =
-            Self::spawn_heartbeat_task(heartbeat.clone(), self.writer.clone(), suffix.clone());
+            Self::spawn_heartbeat_task(heartbeat.clone(), self.writer.clone(), framing)?;
         Ok(())
     }
 
@@ -300,7 +555,7 @@ pub struct SocketClient {
     pub(crate) writer: SharedTcpWriter,
     pub(crate) controller_task: task::JoinHandle<()>,
     pub(crate) disconnect_mode: Arc<AtomicBool>,
-    pub(crate) suffix: Vec<u8>,
+    pub(crate) framing: Framing,
 }
 
 impl SocketClient {
@@ -310,7 +565,7 @@ impl SocketClient {
         post_reconnection: Option<PyObject>,
         post_disconnection: Option<PyObject>,
     ) -> Result<Self, Error> {
-        let suffix = config.suffix.clone();
+        let framing = Framing::new(&config)?;
         let inner = SocketClientInner::connect_url(config).await?;
         let writer = inner.writer.clone();
         let disconnect_mode = Arc::new(AtomicBool::new(false));
@@ -333,7 +588,7 @@ impl SocketClient {
             writer,
             controller_task,
             disconnect_mode,
-            suffix,
+            framing,
         })
     }
 
@@ -361,9 +616,9 @@ impl SocketClient {
     }
 
     pub async fn send_bytes(&self, data: &[u8]) -> Result<(), std::io::Error> {
+        let frame = self.framing.encode(data)?;
         let mut writer = self.writer.lock().await;
-        writer.write_all(data).await?;
-        writer.write_all(&self.suffix).await
+        writer.write_all(&frame).await
     }
 
     #[must_use]
@@ -429,3 +684,139 @@ impl SocketClient {
         })
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn framing(suffix: &[u8], length_prefix: Option<usize>) -> Framing {
+        Framing {
+            suffix: suffix.to_vec(),
+            length_prefix,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    #[rstest]
+    #[case(framing(b"\r\n", None), b"\x01\x02".as_slice(), b"\x01\x02\r\n".as_slice())]
+    #[case(framing(b"", Some(2)), b"\x01\x02".as_slice(), b"\x00\x02\x01\x02".as_slice())]
+    #[case(framing(b"", Some(4)), b"".as_slice(), b"\x00\x00\x00\x00".as_slice())]
+    fn test_framing_round_trip(
+        #[case] framing: Framing,
+        #[case] data: &[u8],
+        #[case] expected: &[u8],
+    ) {
+        let frame = framing.encode(data).unwrap();
+        assert_eq!(frame, expected);
+
+        let mut buf = frame.repeat(2);
+        assert_eq!(framing.decode(&mut buf).unwrap().unwrap(), data);
+        assert_eq!(framing.decode(&mut buf).unwrap().unwrap(), data);
+        assert!(framing.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[rstest]
+    fn test_decode_length_prefixed_partial_message() {
+        let framing = framing(b"", Some(2));
+        let mut buf = b"\x00\x03ab".to_vec();
+
+        assert!(framing.decode(&mut buf).unwrap().is_none());
+        buf.push(b'c');
+        assert_eq!(framing.decode(&mut buf).unwrap().unwrap(), b"abc");
+        assert!(buf.is_empty());
+    }
+
+    #[rstest]
+    #[case::overflowing(framing(b"", Some(8)), vec![0xff; 8])]
+    #[case::exceeding_max(framing(b"", Some(4)), vec![0x01, 0x00, 0x00, 0x01])]
+    fn test_decode_length_prefix_exceeding_max_frame_len(
+        #[case] framing: Framing,
+        #[case] mut buf: Vec<u8>,
+    ) {
+        let result = framing.decode(&mut buf);
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[rstest]
+    fn test_decode_length_prefix_with_configured_max_frame_len() {
+        let framing = Framing {
+            max_frame_len: 2,
+            ..framing(b"", Some(1))
+        };
+
+        assert_eq!(
+            framing.decode(&mut b"\x02ab".to_vec()).unwrap().unwrap(),
+            b"ab"
+        );
+        assert_eq!(
+            framing
+                .decode(&mut b"\x03abc".to_vec())
+                .unwrap_err()
+                .to_string(),
+            "Message of 3 bytes exceeds maximum frame length of 2 bytes"
+        );
+    }
+
+    #[rstest]
+    #[case("example.com:443", Some(("example.com", 443)))]
+    #[case("[::1]:8080", Some(("::1", 8080)))]
+    #[case("example.com", None)]
+    #[case("example.com:https", None)]
+    fn test_split_host_port(#[case] url: &str, #[case] expected: Option<(&str, u16)>) {
+        assert_eq!(split_host_port(url).ok(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_socket_options_apply() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let options = SocketOptions {
+            nodelay: true,
+            keepalive: Some(Duration::from_secs(30)),
+            recv_buf: Some(64 * 1024),
+            send_buf: Some(32 * 1024),
+        };
+
+        options.apply(&stream).unwrap();
+
+        let socket = SockRef::from(&stream);
+        assert!(stream.nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+        // The kernel may round up (e.g. Linux doubles) the requested buffer sizes
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+        assert!(socket.send_buffer_size().unwrap() >= 32 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_socket_options_default() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        SocketOptions::default().apply(&stream).unwrap();
+
+        assert!(stream.nodelay().unwrap());
+        assert!(!SockRef::from(&stream).keepalive().unwrap());
+    }
+
+    #[rstest]
+    fn test_encode_message_exceeding_length_prefix() {
+        let framing = framing(b"", Some(1));
+        let result = framing.encode(&[0; 256]);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Message of 256 bytes exceeds 1 byte length prefix"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Test utilities shared by the TLS handshake tests of the crate.
+//!
+//! A [`TestPki`] issues the certificates of a test CA, and a [`TestTlsServer`] serves them
+//! from a local `rustls` echo server, optionally requiring client certificates or advertising
+//! ALPN protocols.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedKey, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair,
+};
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    task,
+};
+use tokio_rustls::TlsAcceptor;
+
+/// A test CA along with a server certificate for `localhost` and a client certificate it
+/// issued.
+pub(crate) struct TestPki {
+    pub(crate) ca: CertifiedKey,
+    pub(crate) server: CertifiedKey,
+    pub(crate) client: CertifiedKey,
+}
+
+impl TestPki {
+    pub(crate) fn new() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params
+            .distinguished_name
+            .push(DnType::CommonName, "Nautilus Test CA");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = ca_params.self_signed(&key_pair).unwrap();
+        let ca = CertifiedKey { cert, key_pair };
+
+        let server = Self::issue_with(&ca, "localhost", ExtendedKeyUsagePurpose::ServerAuth);
+        let client = Self::issue_with(&ca, "client", ExtendedKeyUsagePurpose::ClientAuth);
+
+        Self { ca, server, client }
+    }
+
+    pub(crate) fn issue_with(
+        ca: &CertifiedKey,
+        name: &str,
+        usage: ExtendedKeyUsagePurpose,
+    ) -> CertifiedKey {
+        let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.extended_key_usages = vec![usage];
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = params.signed_by(&key_pair, &ca.cert, &ca.key_pair).unwrap();
+        CertifiedKey { cert, key_pair }
+    }
+
+    /// Issues a server certificate for `name` signed by the test CA.
+    pub(crate) fn issue_server(&self, name: &str) -> CertifiedKey {
+        Self::issue_with(&self.ca, name, ExtendedKeyUsagePurpose::ServerAuth)
+    }
+
+    pub(crate) fn root_store(&self) -> RootCertStore {
+        let mut root_store = RootCertStore::empty();
+        root_store.add(self.ca.cert.der().clone()).unwrap();
+        root_store
+    }
+
+    pub(crate) fn chain(certified: &CertifiedKey) -> Vec<CertificateDer<'static>> {
+        vec![certified.cert.der().clone()]
+    }
+
+    pub(crate) fn key(certified: &CertifiedKey) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()))
+    }
+}
+
+pub(crate) fn install_crypto_provider() {
+    if CryptoProvider::get_default().is_none() {
+        // An error can occur if another test installed the provider concurrently
+        let _ = ring::default_provider().install_default();
+    }
+}
+
+/// A local `rustls` echo server, serving a certificate chain of a [`TestPki`].
+///
+/// The server accepts connections until the test runtime shuts down, echoing the data
+/// received on each connection once its handshake completes.
+pub(crate) struct TestTlsServer {
+    cert_chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    client_ca: Option<RootCertStore>,
+    alpn_protocols: Vec<Vec<u8>>,
+}
+
+impl TestTlsServer {
+    /// Creates a server presenting the `localhost` server certificate of the `pki`.
+    pub(crate) fn new(pki: &TestPki) -> Self {
+        Self {
+            cert_chain: TestPki::chain(&pki.server),
+            key: TestPki::key(&pki.server),
+            client_ca: None,
+            alpn_protocols: Vec::new(),
+        }
+    }
+
+    /// Presents the `certified` server certificate instead.
+    pub(crate) fn with_cert(mut self, certified: &CertifiedKey) -> Self {
+        self.cert_chain = TestPki::chain(certified);
+        self.key = TestPki::key(certified);
+        self
+    }
+
+    /// Requires clients to present a certificate issued by a CA of the `client_ca` store.
+    pub(crate) fn with_client_auth(mut self, client_ca: RootCertStore) -> Self {
+        self.client_ca = Some(client_ca);
+        self
+    }
+
+    /// Advertises the ALPN `protocols`, in order of preference.
+    pub(crate) fn with_alpn(mut self, protocols: Vec<Vec<u8>>) -> Self {
+        self.alpn_protocols = protocols;
+        self
+    }
+
+    /// Returns the `rustls` config of the server.
+    pub(crate) fn config(self) -> ServerConfig {
+        let builder = ServerConfig::builder();
+        let builder = match self.client_ca {
+            Some(client_ca) => builder.with_client_cert_verifier(
+                WebPkiClientVerifier::builder(Arc::new(client_ca))
+                    .build()
+                    .unwrap(),
+            ),
+            None => builder.with_no_client_auth(),
+        };
+        let mut config = builder.with_single_cert(self.cert_chain, self.key).unwrap();
+        config.alpn_protocols = self.alpn_protocols;
+        config
+    }
+
+    /// Starts the server, returning the address it is listening on.
+    pub(crate) async fn start(self) -> SocketAddr {
+        Self::start_with_config(self.config()).await
+    }
+
+    /// Starts an echo server with the given `rustls` `config`, returning the address it is
+    /// listening on.
+    pub(crate) async fn start_with_config(config: ServerConfig) -> SocketAddr {
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                task::spawn(async move {
+                    let Ok(mut stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut buf = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+                            break;
+                        }
+                        let _ = stream.flush().await;
+                    }
+                });
+            }
+        });
+
+        addr
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rustls::{pki_types::ServerName, ClientConfig};
+    use tokio::net::TcpStream;
+    use tokio_rustls::{client::TlsStream, TlsConnector};
+
+    use super::*;
+
+    /// Connects to the server at `addr` as `localhost` with the `config`.
+    async fn connect(
+        addr: SocketAddr,
+        config: ClientConfig,
+    ) -> std::io::Result<TlsStream<TcpStream>> {
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+        // A rejected client certificate is only reported by the server after the handshake
+        stream.write_all(b"hello").await?;
+        stream.flush().await?;
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"hello");
+        Ok(stream)
+    }
+
+    #[tokio::test]
+    async fn test_tls_server_echoes() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        let config = ClientConfig::builder()
+            .with_root_certificates(pki.root_store())
+            .with_no_client_auth();
+        let stream = connect(addr, config).await.unwrap();
+
+        assert_eq!(stream.get_ref().1.alpn_protocol(), None);
+    }
+
+    #[tokio::test]
+    async fn test_tls_server_requires_client_auth() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_client_auth(pki.root_store())
+            .start()
+            .await;
+
+        let without_cert = ClientConfig::builder()
+            .with_root_certificates(pki.root_store())
+            .with_no_client_auth();
+        let with_cert = ClientConfig::builder()
+            .with_root_certificates(pki.root_store())
+            .with_client_auth_cert(TestPki::chain(&pki.client), TestPki::key(&pki.client))
+            .unwrap();
+
+        assert!(connect(addr, without_cert).await.is_err());
+        assert!(connect(addr, with_cert).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tls_server_advertises_alpn() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(pki.root_store())
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        let stream = connect(addr, config).await.unwrap();
+
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+    }
+}
@@ -15,145 +15,3537 @@
 
 //! Module for wrapping raw socket streams with TLS encryption.
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    hash::Hash,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use rustls::{self, pki_types::TrustAnchor, ClientConfig, RootCertStore};
-use rustls_native_certs::load_native_certs;
-use tokio::io::{AsyncRead, AsyncWrite};
+use rustls::{
+    self,
+    client::{ClientSessionMemoryCache, ClientSessionStore, Resumption, WebPkiServerVerifier},
+    pki_types::{
+        pem::{self, PemObject},
+        CertificateDer, CertificateRevocationListDer, PrivateKeyDer, ServerName, TrustAnchor,
+    },
+    CertificateError, CipherSuite, ClientConfig, ProtocolVersion, RootCertStore,
+    SupportedProtocolVersion,
+};
+use rustls_native_certs::{load_native_certs, CertificateResult};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::Semaphore,
+};
 use tokio_rustls::TlsConnector;
 use tokio_tungstenite::{
     tungstenite::{
         client::IntoClientRequest,
+        error::{ProtocolError, TlsError, UrlError},
         handshake::client::{Request, Response},
         stream::Mode,
         Error,
     },
     MaybeTlsStream,
 };
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use tungstenite;
 
+/// The source of the error returned for a connection attempt cancelled through its
+/// cancellation token (see [`is_cancelled`]).
+#[derive(Debug, thiserror::Error)]
+#[error("Connection attempt cancelled")]
+pub struct Cancelled;
+
 /// A connector that can be used when establishing connections, allowing to control whether
 /// `native-tls` or `rustls` is used to create a TLS connection. Or TLS can be disabled with the
 /// `Plain` variant.
 #[non_exhaustive]
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Connector {
     /// No TLS connection.
     Plain,
     /// TLS connection using `rustls`.
     Rustls(std::sync::Arc<rustls::ClientConfig>),
+    /// TLS connection using `native-tls` (the platform TLS stack).
+    #[cfg(feature = "native-tls")]
+    NativeTls(std::sync::Arc<native_tls::TlsConnector>),
+    /// TLS connection using `rustls`, retried once using `native-tls` (which may complete the
+    /// chain from intermediates it already holds) when `rustls` rejects the server certificate
+    /// chain (see [`Connector::fallback_for`] and [`tcp_tls_with_fallback`]).
+    ///
+    /// The retry only applies the `native-tls` verification, so any pins or CRLs of the
+    /// `rustls` config are not enforced on the fallback connection.
+    #[cfg(feature = "native-tls")]
+    AutoFallback(
+        std::sync::Arc<rustls::ClientConfig>,
+        std::sync::Arc<native_tls::TlsConnector>,
+    ),
 }
 
-mod encryption {
+impl Connector {
+    /// Returns the connector to retry with after a handshake using this connector failed with
+    /// the `error`, which is the `native-tls` connector of a [`Connector::AutoFallback`] when
+    /// the `error` is a chain validation failure (see [`is_chain_validation_error`]).
+    ///
+    /// IO errors (including handshake timeouts) and other TLS errors are not retried.
+    #[must_use]
+    pub fn fallback_for(&self, error: &Error) -> Option<Self> {
+        if !is_chain_validation_error(error) {
+            return None;
+        }
+        match self {
+            #[cfg(feature = "native-tls")]
+            Self::AutoFallback(_, native_tls) => Some(Self::NativeTls(native_tls.clone())),
+            #[cfg(feature = "native-tls")]
+            Self::NativeTls(_) => None,
+            Self::Plain | Self::Rustls(_) => None,
+        }
+    }
 
-    pub mod rustls {
-        use std::{convert::TryFrom, sync::Arc};
+    /// Creates a `rustls` connector which authenticates the server against the given
+    /// `root_store`, and presents the given client certificate chain during the handshake
+    /// (mutual TLS).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `client_key` is invalid or not supported by the installed
+    /// cryptographic provider.
+    pub fn rustls_with_client_auth(
+        root_store: RootCertStore,
+        client_cert_chain: Vec<CertificateDer<'static>>,
+        client_key: PrivateKeyDer<'static>,
+    ) -> Result<Self, Error> {
+        let config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_client_auth_cert(client_cert_chain, client_key)
+            .map_err(|e| Error::Tls(TlsError::Rustls(e)))?;
 
-        pub use rustls::ClientConfig;
-        use rustls::{pki_types::ServerName, RootCertStore};
-        use rustls_native_certs::load_native_certs;
-        use tokio::io::{AsyncRead, AsyncWrite};
-        use tokio_rustls::TlsConnector as TokioTlsConnector;
-        use tokio_tungstenite::{
-            tungstenite::{error::TlsError, stream::Mode, Error},
-            MaybeTlsStream,
-        };
+        Ok(Self::Rustls(Arc::new(config)))
+    }
 
-        pub async fn wrap_stream<S>(
-            socket: S,
-            domain: String,
-            mode: Mode,
-            tls_connector: Option<Arc<ClientConfig>>,
-        ) -> Result<MaybeTlsStream<S>, Error>
-        where
-            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
-        {
-            match mode {
-                Mode::Plain => Ok(MaybeTlsStream::Plain(socket)),
-                Mode::Tls => {
-                    let config = match tls_connector {
-                        Some(config) => config,
-                        None => {
-                            tracing::info!("Loading native certificates");
-                            let mut root_store = RootCertStore::empty();
-                            let cert_result = load_native_certs();
-                            for e in cert_result.errors {
-                                tracing::error!("Error loading certificates: {e}");
-                            }
-                            root_store.add_parsable_certificates(cert_result.certs);
-
-                            Arc::new(
-                                ClientConfig::builder()
-                                    .with_root_certificates(root_store)
-                                    .with_no_client_auth(),
-                            )
-                        }
-                    };
-                    let domain = ServerName::try_from(domain.as_str())
-                        .map_err(|_| TlsError::InvalidDnsName)?
-                        .to_owned();
-                    let stream = TokioTlsConnector::from(config);
-                    let connected = stream.connect(domain, socket).await;
+    /// Creates a `rustls` connector which authenticates the server against the given `ca`
+    /// root store, and presents the client certificate chain and private key of the password
+    /// protected PKCS#12 (`.p12`) bundle at `p12_path` during the handshake (mutual TLS).
+    ///
+    /// The bundle is parsed with OpenSSL, so requires the `pkcs12` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bundle cannot be read or parsed, the `password` is wrong, or
+    /// the bundle holds no client certificate or private key.
+    #[cfg(feature = "pkcs12")]
+    pub fn rustls_with_pkcs12(
+        ca: RootCertStore,
+        p12_path: &Path,
+        password: &str,
+    ) -> Result<Self, Error> {
+        let (client_cert_chain, client_key) = load_pkcs12(p12_path, password)?;
+        Self::rustls_with_client_auth(ca, client_cert_chain, client_key)
+    }
 
-                    match connected {
-                        Err(e) => Err(Error::Io(e)),
-                        Ok(s) => Ok(MaybeTlsStream::Rustls(s)),
-                    }
-                }
-            }
+    /// Creates a `rustls` connector which trusts the root certificates contained in the PEM
+    /// bundle at `path`, instead of the native (OS) trust store.
+    ///
+    /// Certificates which fail to parse are skipped and logged rather than failing the whole
+    /// bundle. Returns the connector along with the number of certificates added and skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or if no valid certificates were found.
+    pub fn rustls_from_pem_file(path: &Path) -> Result<(Self, (usize, usize)), Error> {
+        let (root_store, counts) = load_pem_root_store(path)?;
+        let config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        Ok((Self::Rustls(Arc::new(config)), counts))
+    }
+
+    /// Creates a `rustls` connector which trusts the native (OS) root certificates, failing
+    /// if they cannot all be loaded.
+    ///
+    /// Unlike the default (lenient) behavior when no connector is given, which logs loading
+    /// errors and continues with whatever certificates were found, this ensures connections
+    /// never proceed with a partial or empty trust store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any native certificate fails to load or parse, or if no native
+    /// certificates were found.
+    pub fn rustls_strict_native() -> Result<Self, Error> {
+        let root_store = strict_native_root_store(load_native_certs())?;
+        let config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        Ok(Self::Rustls(Arc::new(config)))
+    }
+
+    /// Creates a `rustls` connector which trusts the given `root_store`, and offers the given
+    /// ALPN `protocols` (e.g. `b"h2"`) in preference order during the handshake.
+    ///
+    /// The protocol agreed with the server can be read from the connected stream
+    /// with [`negotiated_alpn_protocol`].
+    #[must_use]
+    pub fn rustls_with_alpn(root_store: RootCertStore, protocols: Vec<Vec<u8>>) -> Self {
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+        config.alpn_protocols = protocols;
+
+        Self::Rustls(Arc::new(config))
+    }
+
+    /// Creates a `rustls` connector which trusts the given `root_store`, and keeps TLS session
+    /// tickets in the given session `store`.
+    ///
+    /// Reconnects through a connector sharing the same `store` can resume a previous session,
+    /// skipping the full handshake.
+    #[must_use]
+    pub fn rustls_with_session_store(
+        root_store: RootCertStore,
+        store: Arc<dyn ClientSessionStore>,
+    ) -> Self {
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+        config.resumption = Resumption::store(store);
+
+        Self::Rustls(Arc::new(config))
+    }
+
+    /// Creates a `rustls` connector which trusts the given `root_store`, and keeps up to
+    /// `capacity` TLS sessions in an in-memory cache for resumption on reconnect.
+    ///
+    /// The cache holds up to eight TLS 1.3 tickets per server, so `capacity` should be
+    /// comfortably above eight for any session to be retained.
+    #[must_use]
+    pub fn rustls_with_session_cache(root_store: RootCertStore, capacity: usize) -> Self {
+        let store = Arc::new(ClientSessionMemoryCache::new(capacity));
+        Self::rustls_with_session_store(root_store, store)
+    }
+
+    /// Creates a `rustls` connector which trusts the given `root_store`, and only negotiates
+    /// the given TLS protocol `versions` (e.g. `&[&rustls::version::TLS12]`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `versions` is empty.
+    pub fn rustls_with_versions(
+        root_store: RootCertStore,
+        versions: &[&'static SupportedProtocolVersion],
+    ) -> Result<Self, Error> {
+        if versions.is_empty() {
+            return Err(Error::Tls(TlsError::Rustls(rustls::Error::General(
+                "At least one TLS protocol version must be supplied".to_string(),
+            ))));
         }
+
+        let config = ClientConfig::builder_with_protocol_versions(versions)
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        Ok(Self::Rustls(Arc::new(config)))
     }
 
-    pub mod plain {
-        use tokio::io::{AsyncRead, AsyncWrite};
-        use tokio_tungstenite::{
-            tungstenite::{
-                error::{Error, UrlError},
-                stream::Mode,
-            },
-            MaybeTlsStream,
-        };
+    /// Creates a `rustls` connector which authenticates the server against the given
+    /// `root_store`, and additionally requires the SHA-256 hash of the server leaf
+    /// certificate's SubjectPublicKeyInfo (SPKI) to be one of `pinned_spki_sha256`.
+    ///
+    /// A server whose certificate chain is valid but whose key is not pinned is rejected
+    /// with an [`rustls::CertificateError::ApplicationVerificationFailure`] error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pinned_spki_sha256` is empty, or the verifier cannot be built
+    /// from `root_store`.
+    pub fn rustls_with_pins(
+        root_store: RootCertStore,
+        pinned_spki_sha256: Vec<[u8; 32]>,
+    ) -> Result<Self, Error> {
+        if pinned_spki_sha256.is_empty() {
+            return Err(Error::Tls(TlsError::Rustls(rustls::Error::General(
+                "At least one pinned SPKI hash must be supplied".to_string(),
+            ))));
+        }
 
-        pub async fn wrap_stream<S>(socket: S, mode: Mode) -> Result<MaybeTlsStream<S>, Error>
-        where
-            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
-        {
-            match mode {
-                Mode::Plain => Ok(MaybeTlsStream::Plain(socket)),
-                Mode::Tls => Err(Error::Url(UrlError::TlsFeatureNotEnabled)),
+        let inner = WebPkiServerVerifier::builder(Arc::new(root_store))
+            .build()
+            .map_err(|e| Error::Tls(TlsError::Rustls(rustls::Error::General(e.to_string()))))?;
+        let verifier = pinning::SpkiPinVerifier::new(inner, pinned_spki_sha256);
+
+        let config = ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(Self::Rustls(Arc::new(config)))
+    }
+
+    /// Creates a `rustls` connector which authenticates the server chain against the given
+    /// `root_store`, but checks the leaf certificate is valid for the `expected` name (a DNS
+    /// name or IP address) rather than the host connected to.
+    ///
+    /// This is for a venue whose certificate is issued for a known fixed name which differs
+    /// from the connection host, and is safer than disabling verification (the chain, expiry
+    /// and signatures are still verified). The host is still presented for SNI. A certificate
+    /// which is not valid for the `expected` name is rejected with a
+    /// [`rustls::CertificateError::NotValidForName`] error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `expected` is not a valid DNS name or IP address, or the verifier
+    /// cannot be built from `root_store`.
+    pub fn rustls_expect_name(root_store: RootCertStore, expected: String) -> Result<Self, Error> {
+        let expected = ServerName::try_from(expected.as_str())
+            .map(|name| name.to_owned())
+            .map_err(|e| {
+                Error::Tls(TlsError::Rustls(rustls::Error::General(format!(
+                    "Invalid expected server name '{expected}': {e}"
+                ))))
+            })?;
+        let inner = WebPkiServerVerifier::builder(Arc::new(root_store))
+            .build()
+            .map_err(|e| Error::Tls(TlsError::Rustls(rustls::Error::General(e.to_string()))))?;
+        let verifier = expected_name::ExpectedNameVerifier::new(inner, expected);
+
+        let config = ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(Self::Rustls(Arc::new(config)))
+    }
+
+    /// Creates a `rustls` connector which authenticates the server against the given
+    /// `root_store`, and rejects any certificate in the chain revoked by one of the given
+    /// certificate revocation lists (`crls`).
+    ///
+    /// Each issuer in the server's certificate chain must be covered by one of the `crls`,
+    /// otherwise the revocation status is unknown and the certificate is rejected. Stapled
+    /// OCSP responses are not checked. A revoked certificate is rejected with a
+    /// [`rustls::CertificateError::Revoked`] error, see [`is_certificate_revoked`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the `crls` cannot be parsed, or the verifier cannot be built.
+    pub fn rustls_with_revocation(
+        root_store: RootCertStore,
+        crls: Vec<CertificateRevocationListDer<'static>>,
+    ) -> Result<Self, Error> {
+        let verifier = WebPkiServerVerifier::builder(Arc::new(root_store))
+            .with_crls(crls)
+            .build()
+            .map_err(|e| Error::Tls(TlsError::Rustls(rustls::Error::General(e.to_string()))))?;
+
+        let config = ClientConfig::builder()
+            .with_webpki_verifier(verifier)
+            .with_no_client_auth();
+
+        Ok(Self::Rustls(Arc::new(config)))
+    }
+}
+
+/// A builder which assembles a `rustls` [`Connector`] from the given CA sources, server key
+/// pins, revocation lists, client authentication, ALPN protocols and TLS versions.
+///
+/// Root certificates from every configured source are combined into a single trust store.
+/// Any option which is not set keeps the `rustls` default.
+#[derive(Debug)]
+pub struct TlsConnectorBuilder {
+    root_store: RootCertStore,
+    native_roots: bool,
+    pem_files: Vec<PathBuf>,
+    pins: Option<Vec<[u8; 32]>>,
+    crls: Vec<CertificateRevocationListDer<'static>>,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+    alpn_protocols: Vec<Vec<u8>>,
+    versions: Option<Vec<&'static SupportedProtocolVersion>>,
+    session_cache_capacity: Option<usize>,
+}
+
+impl Default for TlsConnectorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TlsConnectorBuilder {
+    /// Creates a new [`TlsConnectorBuilder`] instance with no trusted root certificates.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            root_store: RootCertStore::empty(),
+            native_roots: false,
+            pem_files: Vec::new(),
+            pins: None,
+            crls: Vec::new(),
+            client_auth: None,
+            alpn_protocols: Vec::new(),
+            versions: None,
+            session_cache_capacity: None,
+        }
+    }
+
+    /// Trusts the native (OS) root certificates, which must all load successfully
+    /// (see [`Connector::rustls_strict_native`]).
+    #[must_use]
+    pub const fn with_native_roots(mut self) -> Self {
+        self.native_roots = true;
+        self
+    }
+
+    /// Trusts the root certificates in the given `root_store`.
+    #[must_use]
+    pub fn with_root_store(mut self, root_store: RootCertStore) -> Self {
+        self.root_store.roots.extend(root_store.roots);
+        self
+    }
+
+    /// Trusts the root certificates contained in the PEM bundle at `path`
+    /// (see [`Connector::rustls_from_pem_file`]).
+    #[must_use]
+    pub fn with_pem_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.pem_files.push(path.into());
+        self
+    }
+
+    /// Requires the SHA-256 hash of the server leaf certificate's SPKI to be one of
+    /// `pinned_spki_sha256` (see [`Connector::rustls_with_pins`]).
+    #[must_use]
+    pub fn with_pins(mut self, pinned_spki_sha256: Vec<[u8; 32]>) -> Self {
+        self.pins = Some(pinned_spki_sha256);
+        self
+    }
+
+    /// Rejects server certificates revoked by one of the given `crls`
+    /// (see [`Connector::rustls_with_revocation`]).
+    #[must_use]
+    pub fn with_crls(mut self, crls: Vec<CertificateRevocationListDer<'static>>) -> Self {
+        self.crls = crls;
+        self
+    }
+
+    /// Presents the given client certificate chain during the handshake (mutual TLS).
+    #[must_use]
+    pub fn with_client_auth(
+        mut self,
+        client_cert_chain: Vec<CertificateDer<'static>>,
+        client_key: PrivateKeyDer<'static>,
+    ) -> Self {
+        self.client_auth = Some((client_cert_chain, client_key));
+        self
+    }
+
+    /// Offers the given ALPN `protocols` in preference order during the handshake.
+    #[must_use]
+    pub fn with_alpn(mut self, protocols: Vec<Vec<u8>>) -> Self {
+        self.alpn_protocols = protocols;
+        self
+    }
+
+    /// Only negotiates the given TLS protocol `versions`.
+    #[must_use]
+    pub fn with_versions(mut self, versions: &[&'static SupportedProtocolVersion]) -> Self {
+        self.versions = Some(versions.to_vec());
+        self
+    }
+
+    /// Keeps up to `capacity` TLS sessions in an in-memory cache for resumption on reconnect.
+    #[must_use]
+    pub const fn with_session_cache(mut self, capacity: usize) -> Self {
+        self.session_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Builds the `rustls` [`Connector`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Any of the configured CA sources fails to load, or no root certificates were configured.
+    /// - The pins or versions were set but are empty.
+    /// - Any of the CRLs cannot be parsed, or the client key is invalid.
+    pub fn build(self) -> Result<Connector, Error> {
+        let general = |msg: String| Error::Tls(TlsError::Rustls(rustls::Error::General(msg)));
+
+        let mut root_store = self.root_store;
+        if self.native_roots {
+            let native = strict_native_root_store(load_native_certs())?;
+            root_store.roots.extend(native.roots);
+        }
+        for path in &self.pem_files {
+            let (pem, _) = load_pem_root_store(path)?;
+            root_store.roots.extend(pem.roots);
+        }
+        if root_store.is_empty() {
+            return Err(general("No root certificates configured".to_string()));
+        }
+
+        let versions = self
+            .versions
+            .unwrap_or_else(|| rustls::DEFAULT_VERSIONS.to_vec());
+        if versions.is_empty() {
+            return Err(general(
+                "At least one TLS protocol version must be supplied".to_string(),
+            ));
+        }
+
+        let verifier = WebPkiServerVerifier::builder(Arc::new(root_store))
+            .with_crls(self.crls)
+            .build()
+            .map_err(|e| general(e.to_string()))?;
+
+        let builder = ClientConfig::builder_with_protocol_versions(&versions);
+        let builder = match self.pins {
+            Some(pins) if pins.is_empty() => {
+                return Err(general(
+                    "At least one pinned SPKI hash must be supplied".to_string(),
+                ));
             }
+            Some(pins) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(pinning::SpkiPinVerifier::new(
+                    verifier, pins,
+                ))),
+            None => builder.with_webpki_verifier(verifier),
+        };
+
+        let mut config = match self.client_auth {
+            Some((chain, key)) => builder
+                .with_client_auth_cert(chain, key)
+                .map_err(|e| Error::Tls(TlsError::Rustls(e)))?,
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = self.alpn_protocols;
+        if let Some(capacity) = self.session_cache_capacity {
+            config.resumption =
+                Resumption::store(Arc::new(ClientSessionMemoryCache::new(capacity)));
         }
+
+        Ok(Connector::Rustls(Arc::new(config)))
     }
 }
 
-pub async fn tcp_tls<S>(
-    request: &Request,
-    mode: Mode,
-    stream: S,
-    connector: Option<Connector>,
-) -> Result<MaybeTlsStream<S>, Error>
-where
-    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
-    MaybeTlsStream<S>: Unpin,
-{
-    let domain = domain(request)?;
+/// A source of the root certificates trusted by a [`TlsProfile`].
+#[derive(Clone, Debug)]
+pub enum CaSource {
+    /// The native (OS) root certificates (see [`TlsConnectorBuilder::with_native_roots`]).
+    Native,
+    /// The root certificates contained in the PEM bundle at the path.
+    PemFile(PathBuf),
+    /// The root certificates in the store.
+    RootStore(RootCertStore),
+}
+
+/// The TLS configuration of a venue: its CA sources, client authentication, ALPN protocols,
+/// TLS versions and server key pins.
+///
+/// Any option which is not set keeps the `rustls` default (see [`TlsConnectorBuilder`]).
+#[derive(Debug, Default)]
+pub struct TlsProfile {
+    /// The sources of the trusted root certificates, which are combined.
+    pub ca_sources: Vec<CaSource>,
+    /// The client certificate chain and key presented during the handshake (mutual TLS).
+    pub client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+    /// The ALPN protocols offered in preference order.
+    pub alpn_protocols: Vec<Vec<u8>>,
+    /// The TLS protocol versions negotiated (the `rustls` defaults if `None`).
+    pub versions: Option<Vec<&'static SupportedProtocolVersion>>,
+    /// The accepted SHA-256 hashes of the server leaf certificate's SPKI.
+    pub pins: Option<Vec<[u8; 32]>>,
+}
+
+impl TlsProfile {
+    /// Returns a [`TlsConnectorBuilder`] configured with the options of the profile.
+    #[must_use]
+    pub fn builder(&self) -> TlsConnectorBuilder {
+        let mut builder = TlsConnectorBuilder::new();
+        for source in &self.ca_sources {
+            builder = match source {
+                CaSource::Native => builder.with_native_roots(),
+                CaSource::PemFile(path) => builder.with_pem_file(path.clone()),
+                CaSource::RootStore(root_store) => builder.with_root_store(root_store.clone()),
+            };
+        }
+        if let Some((chain, key)) = &self.client_auth {
+            builder = builder.with_client_auth(chain.clone(), key.clone_key());
+        }
+        if let Some(versions) = &self.versions {
+            builder = builder.with_versions(versions);
+        }
+        if let Some(pins) = &self.pins {
+            builder = builder.with_pins(pins.clone());
+        }
+        builder.with_alpn(self.alpn_protocols.clone())
+    }
+
+    /// Builds the `rustls` [`Connector`] of the profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connector cannot be built (see [`TlsConnectorBuilder::build`]).
+    pub fn connector(&self) -> Result<Connector, Error> {
+        self.builder().build()
+    }
+}
+
+/// A registry of [`TlsProfile`]s which yields the [`Connector`] for each venue (or other
+/// key), so adapters can look up the connector by `Venue`.
+///
+/// The connectors are built when the registry is created, so lookups are cheap and an
+/// invalid profile is reported up front.
+#[derive(Clone, Debug)]
+pub struct TlsProfiles<K> {
+    connectors: HashMap<K, Connector>,
+}
+
+impl<K: Eq + Hash + Display> TlsProfiles<K> {
+    /// Creates a new [`TlsProfiles`] instance building the connector of each of the
+    /// `profiles`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the key of the first profile whose connector cannot be built.
+    pub fn new(profiles: HashMap<K, TlsProfile>) -> Result<Self, Error> {
+        let connectors = profiles
+            .into_iter()
+            .map(|(key, profile)| match profile.connector() {
+                Ok(connector) => Ok((key, connector)),
+                Err(e) => Err(Error::Tls(TlsError::Rustls(rustls::Error::General(
+                    format!("Invalid TLS profile for {key}: {e}"),
+                )))),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { connectors })
+    }
+
+    /// Returns the connector for the `key`, if it has a profile.
+    #[must_use]
+    pub fn connector(&self, key: &K) -> Option<Connector> {
+        self.connectors.get(key).cloned()
+    }
+
+    /// Returns the number of profiles.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.connectors.len()
+    }
+
+    /// Returns whether there are no profiles.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.connectors.is_empty()
+    }
+}
+
+/// Returns whether the given connection `error` was caused by `rustls` failing to build the
+/// server certificate chain to a trusted root, i.e. an unknown issuer (such as when the server
+/// omits an intermediate).
+///
+/// Any other certificate error (e.g. an expired or revoked certificate, a bad signature or a
+/// pin mismatch) is not a chain validation failure, since a more lenient verifier must not
+/// accept the certificate either.
+#[must_use]
+pub fn is_chain_validation_error(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Tls(TlsError::Rustls(rustls::Error::InvalidCertificate(
+            CertificateError::UnknownIssuer
+        )))
+    )
+}
+
+/// Returns whether the given connection `error` was caused by the server presenting a
+/// revoked certificate.
+#[must_use]
+pub fn is_certificate_revoked(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Tls(TlsError::Rustls(rustls::Error::InvalidCertificate(
+            CertificateError::Revoked
+        )))
+    )
+}
+
+/// Returns whether the given connection `error` is transient, so the connection attempt
+/// is worth retrying.
+///
+/// IO errors (e.g. a refused or reset connection, or a handshake timeout), a connection
+/// closed by the server (including during the websocket handshake), and server side HTTP
+/// errors are transient. TLS errors (e.g. a rejected certificate or an invalid DNS name),
+/// invalid URLs and requests, client side HTTP errors, and protocol, capacity and UTF-8
+/// errors are permanent, since retrying would fail the same way. An IO error of kind
+/// [`ErrorKind::InvalidInput`] is permanent, as it indicates the connection was
+/// misconfigured (e.g. a plain connector for a TLS endpoint).
+/// A cancelled connection attempt (see [`is_cancelled`]) is not retried either.
+#[must_use]
+pub fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::Io(e) => e.kind() != ErrorKind::InvalidInput && !is_cancelled(error),
+        Error::Http(response) => {
+            let status = response.status();
+            status.is_server_error() || status == tungstenite::http::StatusCode::TOO_MANY_REQUESTS
+        }
+        Error::ConnectionClosed | Error::Protocol(ProtocolError::HandshakeIncomplete) => true,
+        Error::AlreadyClosed
+        | Error::Tls(_)
+        | Error::Capacity(_)
+        | Error::Protocol(_)
+        | Error::WriteBufferFull(_)
+        | Error::Utf8
+        | Error::AttackAttempt
+        | Error::Url(_)
+        | Error::HttpFormat(_) => false,
+    }
+}
 
-    match connector {
-        Some(conn) => match conn {
-            Connector::Rustls(conn) => {
-                self::encryption::rustls::wrap_stream(stream, domain, mode, Some(conn)).await
+/// Returns whether the given connection `error` is due to the attempt being cancelled.
+#[must_use]
+pub fn is_cancelled(error: &Error) -> bool {
+    matches!(error, Error::Io(e) if e.get_ref().is_some_and(|source| source.is::<Cancelled>()))
+}
+
+/// Runs the connection `future` to completion, unless the `cancellation_token` (if any) is
+/// cancelled first.
+///
+/// Once cancelled, the `future` is dropped (closing any connection it opened) and an
+/// [`ErrorKind::Interrupted`] IO error with a [`Cancelled`] source is returned.
+pub async fn cancellable<T>(
+    future: impl Future<Output = Result<T, Error>>,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<T, Error> {
+    match cancellation_token {
+        Some(token) => tokio::select! {
+            biased;
+            () = token.cancelled() => Err(Error::Io(std::io::Error::new(
+                ErrorKind::Interrupted,
+                Cancelled,
+            ))),
+            result = future => result,
+        },
+        None => future.await,
+    }
+}
+
+mod pinning {
+    use std::sync::Arc;
+
+    use ring::digest::{digest, SHA256};
+    use rustls::{
+        client::{
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+            WebPkiServerVerifier,
+        },
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        CertificateError, DigitallySignedStruct, SignatureScheme,
+    };
+    use webpki::EndEntityCert;
+
+    /// A certificate verifier which performs the standard `webpki` chain verification, then
+    /// checks the leaf certificate's SPKI hash is one of the pinned hashes.
+    #[derive(Debug)]
+    pub struct SpkiPinVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        pins: Vec<[u8; 32]>,
+    }
+
+    impl SpkiPinVerifier {
+        pub fn new(inner: Arc<WebPkiServerVerifier>, pins: Vec<[u8; 32]>) -> Self {
+            Self { inner, pins }
+        }
+    }
+
+    /// Returns the SHA-256 hash of the DER encoded SPKI of the given certificate.
+    pub fn spki_sha256(cert: &CertificateDer<'_>) -> Result<[u8; 32], rustls::Error> {
+        let cert = EndEntityCert::try_from(cert)
+            .map_err(|_| rustls::Error::InvalidCertificate(CertificateError::BadEncoding))?;
+        let hash = digest(&SHA256, cert.subject_public_key_info().as_ref());
+
+        let mut spki_sha256 = [0u8; 32];
+        spki_sha256.copy_from_slice(hash.as_ref());
+        Ok(spki_sha256)
+    }
+
+    impl ServerCertVerifier for SpkiPinVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            let verified = self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            )?;
+
+            if self.pins.contains(&spki_sha256(end_entity)?) {
+                Ok(verified)
+            } else {
+                tracing::error!("Server certificate for {server_name:?} does not match any pin");
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::ApplicationVerificationFailure,
+                ))
             }
-            Connector::Plain => self::encryption::plain::wrap_stream(stream, mode).await,
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
+    }
+}
+
+mod expected_name {
+    use std::sync::Arc;
+
+    use rustls::{
+        client::{
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+            WebPkiServerVerifier,
         },
-        None => self::encryption::rustls::wrap_stream(stream, domain, mode, None).await,
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        DigitallySignedStruct, SignatureScheme,
+    };
+
+    /// A certificate verifier which performs the standard `webpki` chain verification, but
+    /// checks the leaf certificate against the expected name instead of the server name.
+    #[derive(Debug)]
+    pub struct ExpectedNameVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        expected: ServerName<'static>,
+    }
+
+    impl ExpectedNameVerifier {
+        pub fn new(inner: Arc<WebPkiServerVerifier>, expected: ServerName<'static>) -> Self {
+            Self { inner, expected }
+        }
+    }
+
+    impl ServerCertVerifier for ExpectedNameVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            tracing::debug!(
+                "Verifying server certificate for {server_name:?} against expected name {:?}",
+                self.expected
+            );
+            self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                &self.expected,
+                ocsp_response,
+                now,
+            )
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
     }
 }
 
-fn domain(request: &tungstenite::handshake::client::Request) -> Result<String, Error> {
-    match request.uri().host() {
-        // rustls expects IPv6 addresses without the surrounding [] brackets
-        Some(d) if d.starts_with('[') && d.ends_with(']') => Ok(d[1..d.len() - 1].to_string()),
-        Some(d) => Ok(d.to_string()),
-        None => panic!("No host name"),
+#[cfg(feature = "tls-keylog")]
+impl Connector {
+    /// Creates a `rustls` connector which trusts the given `root_store`, and passes the TLS
+    /// session secrets to the given `key_log`.
+    ///
+    /// Use [`rustls::KeyLogFile`] to write the secrets to the file named by the `SSLKEYLOGFILE`
+    /// environment variable (in the NSS key log format understood by Wireshark).
+    ///
+    /// # Warning
+    ///
+    /// Anyone with access to the key log file can decrypt the captured traffic, including any
+    /// credentials and orders. This is only intended for debugging, and must never be enabled
+    /// in production.
+    #[must_use]
+    pub fn rustls_with_keylog(root_store: RootCertStore, key_log: Arc<dyn rustls::KeyLog>) -> Self {
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+        config.key_log = key_log;
+
+        Self::Rustls(Arc::new(config))
+    }
+}
+
+#[cfg(feature = "dangerous-tls")]
+impl Connector {
+    /// Creates a `rustls` connector which accepts **any** server certificate.
+    ///
+    /// # Warning
+    ///
+    /// This disables server authentication entirely, so the connection is open to
+    /// man-in-the-middle attacks. It is only intended for development and testing against
+    /// local servers with self-signed certificates, and must never be used in production.
+    #[must_use]
+    pub fn rustls_insecure() -> Self {
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        let provider = config.crypto_provider().clone();
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(danger::NoCertificateVerification(provider)));
+
+        Self::Rustls(Arc::new(config))
+    }
+}
+
+#[cfg(feature = "dangerous-tls")]
+mod danger {
+    use std::sync::Arc;
+
+    use rustls::{
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        DigitallySignedStruct, SignatureScheme,
+    };
+
+    /// A certificate verifier which accepts any server certificate, while still checking
+    /// handshake signatures are made by the presented certificate.
+    #[derive(Debug)]
+    pub struct NoCertificateVerification(pub Arc<CryptoProvider>);
+
+    impl ServerCertVerifier for NoCertificateVerification {
+        fn verify_server_cert(
+            &self,
+            _end_entity: &CertificateDer<'_>,
+            _intermediates: &[CertificateDer<'_>],
+            _server_name: &ServerName<'_>,
+            _ocsp_response: &[u8],
+            _now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls12_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            verify_tls13_signature(
+                message,
+                cert,
+                dss,
+                &self.0.signature_verification_algorithms,
+            )
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+}
+
+/// Builds a [`RootCertStore`] from the native certificate loading `result`, returning an error
+/// rather than skipping any certificate which failed to load or parse.
+fn strict_native_root_store(result: CertificateResult) -> Result<RootCertStore, Error> {
+    if let Some(e) = result.errors.first() {
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::Other,
+            format!(
+                "Error loading native certificates ({} errors): {e}",
+                result.errors.len()
+            ),
+        )));
+    }
+
+    let mut root_store = RootCertStore::empty();
+    let (added, ignored) = root_store.add_parsable_certificates(result.certs);
+    tracing::debug!("Loaded {added} native certificates ({ignored} ignored)");
+
+    if ignored > 0 {
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Failed to parse {ignored} native certificates"),
+        )));
+    }
+    if root_store.is_empty() {
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::NotFound,
+            "No native certificates found",
+        )));
+    }
+
+    Ok(root_store)
+}
+
+/// Builds a [`RootCertStore`] from the native certificate loading `result`, skipping (and
+/// logging) any certificate which failed to load or parse.
+///
+/// Returns an error aggregating every loading error if no certificate could be added.
+fn lenient_native_root_store(result: CertificateResult) -> Result<RootCertStore, Error> {
+    for e in &result.errors {
+        tracing::error!("Error loading certificates: {e}");
+    }
+
+    let mut root_store = RootCertStore::empty();
+    let (added, ignored) = root_store.add_parsable_certificates(result.certs);
+    tracing::debug!("Loaded {added} native certificates ({ignored} ignored)");
+
+    if root_store.is_empty() {
+        let errors: Vec<String> = result.errors.iter().map(ToString::to_string).collect();
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "No native certificates loaded ({ignored} failed to parse, {} errors: [{}])",
+                errors.len(),
+                errors.join("; ")
+            ),
+        )));
+    }
+
+    Ok(root_store)
+}
+
+/// Returns the client certificate chain (leaf first) and private key of the PKCS#12 bundle
+/// at `path`, decrypted with the `password`.
+#[cfg(feature = "pkcs12")]
+fn load_pkcs12(
+    path: &Path,
+    password: &str,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), Error> {
+    let invalid_data = |msg: String| Error::Io(std::io::Error::new(ErrorKind::InvalidData, msg));
+
+    let der = std::fs::read(path).map_err(|e| {
+        Error::Io(std::io::Error::new(
+            e.kind(),
+            format!("Error reading PKCS#12 file '{}': {e}", path.display()),
+        ))
+    })?;
+    let parsed = openssl::pkcs12::Pkcs12::from_der(&der)
+        .and_then(|pkcs12| pkcs12.parse2(password))
+        .map_err(|e| {
+            // The integrity check of the bundle fails first on a wrong password
+            let wrong_password = e
+                .errors()
+                .iter()
+                .any(|e| e.reason() == Some("mac verify failure"));
+            if wrong_password {
+                Error::Io(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid password for PKCS#12 file '{}'", path.display()),
+                ))
+            } else {
+                invalid_data(format!(
+                    "Error parsing PKCS#12 file '{}': {e}",
+                    path.display()
+                ))
+            }
+        })?;
+
+    let (Some(cert), Some(key)) = (parsed.cert, parsed.pkey) else {
+        return Err(invalid_data(format!(
+            "No client certificate and private key in PKCS#12 file '{}'",
+            path.display()
+        )));
+    };
+    let to_der_error = |e: openssl::error::ErrorStack| {
+        invalid_data(format!(
+            "Error encoding PKCS#12 contents of '{}': {e}",
+            path.display()
+        ))
+    };
+    let mut chain = vec![CertificateDer::from(cert.to_der().map_err(to_der_error)?)];
+    for ca_cert in parsed.ca.iter().flatten() {
+        chain.push(CertificateDer::from(
+            ca_cert.to_der().map_err(to_der_error)?,
+        ));
+    }
+    let key = PrivateKeyDer::Pkcs8(key.private_key_to_pkcs8().map_err(to_der_error)?.into());
+
+    Ok((chain, key))
+}
+
+/// Loads the certificates from the PEM bundle at `path` into a new [`RootCertStore`].
+///
+/// Returns the store along with the number of certificates added and skipped.
+fn load_pem_root_store(path: &Path) -> Result<(RootCertStore, (usize, usize)), Error> {
+    let invalid_data = |msg: String| Error::Io(std::io::Error::new(ErrorKind::InvalidData, msg));
+
+    let mut certs = Vec::new();
+    let mut pem_errors = 0;
+    let iter = CertificateDer::pem_file_iter(path).map_err(|e| {
+        invalid_data(format!(
+            "Error reading PEM file '{}': {e:?}",
+            path.display()
+        ))
+    })?;
+
+    for result in iter {
+        match result {
+            Ok(cert) => certs.push(cert),
+            Err(pem::Error::Io(e)) => return Err(Error::Io(e)),
+            Err(e) => {
+                tracing::error!("Error parsing PEM section in '{}': {e:?}", path.display());
+                pem_errors += 1;
+            }
+        }
+    }
+
+    let mut root_store = RootCertStore::empty();
+    let (added, ignored) = root_store.add_parsable_certificates(certs);
+    let skipped = ignored + pem_errors;
+    tracing::debug!(
+        "Loaded {added} certificates from '{}' ({skipped} skipped)",
+        path.display()
+    );
+
+    if added == 0 {
+        return Err(invalid_data(format!(
+            "No valid certificates found in '{}'",
+            path.display()
+        )));
+    }
+
+    Ok((root_store, (added, skipped)))
+}
+
+mod encryption {
+    use std::{io::ErrorKind, time::Duration};
+
+    use tokio_tungstenite::tungstenite::{error::TlsError, Error};
+
+    /// The maximum length of a DNS name (RFC 1035).
+    const MAX_DNS_NAME_LEN: usize = 253;
+
+    /// The maximum length of each label of a DNS name (RFC 1035).
+    const MAX_DNS_LABEL_LEN: usize = 63;
+
+    /// Checks the `domain` is within the DNS name length limits.
+    ///
+    /// The server name is validated again when the handshake starts, but that error does
+    /// not say which part of the name is invalid.
+    fn validate_dns_name(domain: &str) -> Result<(), Error> {
+        let invalid = |reason: String| {
+            Error::Tls(TlsError::Rustls(::rustls::Error::General(format!(
+                "Invalid DNS name '{domain}': {reason}"
+            ))))
+        };
+
+        // The trailing dot of a fully qualified name is not counted
+        let name = domain.strip_suffix('.').unwrap_or(domain);
+        if name.len() > MAX_DNS_NAME_LEN {
+            return Err(invalid(format!(
+                "{} characters long, at most {MAX_DNS_NAME_LEN} allowed",
+                name.len()
+            )));
+        }
+        if let Some(label) = name
+            .split('.')
+            .find(|label| label.len() > MAX_DNS_LABEL_LEN)
+        {
+            return Err(invalid(format!(
+                "label '{label}' is {} characters long, at most {MAX_DNS_LABEL_LEN} allowed",
+                label.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the error for a TLS handshake which did not complete within `timeout`.
+    fn handshake_timed_out(timeout: Duration) -> Error {
+        Error::Io(std::io::Error::new(
+            ErrorKind::TimedOut,
+            format!("TLS handshake timed out after {timeout:?}"),
+        ))
+    }
+
+    pub mod rustls {
+        use std::{
+            convert::TryFrom,
+            io::{self, ErrorKind},
+            pin::Pin,
+            sync::{Arc, OnceLock},
+            task::{Context, Poll},
+            time::Duration,
+        };
+
+        pub use rustls::ClientConfig;
+        use rustls::{pki_types::ServerName, InvalidMessage, RootCertStore};
+        use tokio::io::{AsyncRead, AsyncWrite};
+        use tokio_rustls::{client::TlsStream, TlsConnector as TokioTlsConnector};
+        use tokio_tungstenite::{
+            tungstenite::{error::TlsError, stream::Mode, Error},
+            MaybeTlsStream,
+        };
+
+        static DEFAULT_CONFIG: OnceLock<DefaultConfig> = OnceLock::new();
+
+        /// Performs the TLS handshake over the `socket`.
+        ///
+        /// A server replying in plaintext (e.g. with an HTTP redirect, or a `ws://` endpoint
+        /// behind a `wss://` URL) fails with an error suggesting a scheme or port mismatch,
+        /// rather than the opaque error for the invalid TLS record. Such a reply is told by
+        /// its first byte not being a TLS record content type (an `HTTP/1.` status line
+        /// starts with `H`).
+        async fn handshake<S>(
+            connector: &TokioTlsConnector,
+            domain: ServerName<'static>,
+            socket: S,
+        ) -> Result<TlsStream<S>, Error>
+        where
+            S: AsyncRead + AsyncWrite + Unpin,
+        {
+            match connector.connect(domain.clone(), socket).await {
+                Ok(stream) => Ok(stream),
+                // Surface TLS failures (e.g. certificate rejection) as TLS errors
+                Err(e) => match e.get_ref().and_then(|e| e.downcast_ref::<rustls::Error>()) {
+                    Some(rustls::Error::InvalidMessage(InvalidMessage::InvalidContentType)) => Err(
+                        Error::Tls(TlsError::Rustls(rustls::Error::General(format!(
+                            "Received a plaintext reply from '{}' where a TLS handshake was \
+                            expected: check the URL scheme (`wss://` vs `ws://`) and port",
+                            domain.to_str()
+                        )))),
+                    ),
+                    Some(tls_error) => Err(Error::Tls(TlsError::Rustls(tls_error.clone()))),
+                    None => Err(Error::Io(e)),
+                },
+            }
+        }
+
+        /// The default config, along with why it trusts no root certificates (if so).
+        pub(crate) struct DefaultConfig {
+            config: Arc<ClientConfig>,
+            untrusted_reason: Option<String>,
+        }
+
+        impl DefaultConfig {
+            /// Builds the default config trusting the root certificates returned by the
+            /// `load_roots` loader (none if it fails).
+            pub(crate) fn load(load_roots: impl FnOnce() -> Result<RootCertStore, Error>) -> Self {
+                let (root_store, untrusted_reason) = match load_roots() {
+                    Ok(root_store) if root_store.is_empty() => (
+                        root_store,
+                        Some("root certificate store is empty".to_string()),
+                    ),
+                    Ok(root_store) => (root_store, None),
+                    Err(e) => {
+                        tracing::error!("{e}");
+                        (RootCertStore::empty(), Some(e.to_string()))
+                    }
+                };
+
+                #[allow(unused_mut)]
+                let mut config = ClientConfig::builder()
+                    .with_root_certificates(root_store)
+                    .with_no_client_auth();
+
+                #[cfg(feature = "tls-keylog")]
+                if std::env::var_os("SSLKEYLOGFILE").is_some() {
+                    tracing::warn!("Writing TLS session secrets to SSLKEYLOGFILE");
+                    config.key_log = Arc::new(rustls::KeyLogFile::new());
+                }
+
+                Self {
+                    config: Arc::new(config),
+                    untrusted_reason,
+                }
+            }
+
+            /// Returns the config, or an [`ErrorKind::InvalidInput`] IO error if it trusts no
+            /// root certificates, since every handshake would fail with an opaque error.
+            pub(crate) fn trusted(&self) -> Result<Arc<ClientConfig>, Error> {
+                match &self.untrusted_reason {
+                    Some(reason) => Err(Error::Io(std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("No trusted root certificates available ({reason})"),
+                    ))),
+                    None => Ok(self.config.clone()),
+                }
+            }
+        }
+
+        fn process_default() -> &'static DefaultConfig {
+            DEFAULT_CONFIG.get_or_init(|| DefaultConfig::load(crate::tls::native_root_store))
+        }
+
+        /// Returns the process-wide default config trusting the native root certificates,
+        /// which are loaded on first use only.
+        pub fn default_config() -> Arc<ClientConfig> {
+            process_default().config.clone()
+        }
+
+        pub async fn wrap_stream<S>(
+            socket: S,
+            domain: String,
+            mode: Mode,
+            tls_connector: Option<Arc<ClientConfig>>,
+            handshake_timeout: Option<Duration>,
+        ) -> Result<MaybeTlsStream<S>, Error>
+        where
+            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+        {
+            wrap_stream_with_default(
+                socket,
+                domain,
+                mode,
+                tls_connector,
+                handshake_timeout,
+                process_default(),
+            )
+            .await
+        }
+
+        /// Wraps the `socket` as [`wrap_stream`], with the `default_config` used if no
+        /// `tls_connector` is given.
+        pub(crate) async fn wrap_stream_with_default<S>(
+            socket: S,
+            domain: String,
+            mode: Mode,
+            tls_connector: Option<Arc<ClientConfig>>,
+            handshake_timeout: Option<Duration>,
+            default_config: &DefaultConfig,
+        ) -> Result<MaybeTlsStream<S>, Error>
+        where
+            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+        {
+            match mode {
+                Mode::Plain => Ok(MaybeTlsStream::Plain(socket)),
+                Mode::Tls => {
+                    let config = match tls_connector {
+                        Some(config) => config,
+                        None => default_config.trusted()?,
+                    };
+                    super::validate_dns_name(&domain)?;
+                    let domain = ServerName::try_from(domain.as_str())
+                        .map_err(|_| TlsError::InvalidDnsName)?
+                        .to_owned();
+                    let connector = TokioTlsConnector::from(config);
+                    let connecting = handshake(&connector, domain, socket);
+                    let connected = match handshake_timeout {
+                        Some(timeout) => tokio::time::timeout(timeout, connecting)
+                            .await
+                            .map_err(|_| super::handshake_timed_out(timeout))?,
+                        None => connecting.await,
+                    };
+
+                    connected.map(MaybeTlsStream::Rustls)
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "native-tls")]
+    pub mod native_tls {
+        use std::{sync::Arc, time::Duration};
+
+        use native_tls::TlsConnector;
+        use tokio::io::{AsyncRead, AsyncWrite};
+        use tokio_native_tls::TlsConnector as TokioTlsConnector;
+        use tokio_tungstenite::{
+            tungstenite::{error::TlsError, stream::Mode, Error},
+            MaybeTlsStream,
+        };
+
+        pub async fn wrap_stream<S>(
+            socket: S,
+            domain: String,
+            mode: Mode,
+            tls_connector: Option<Arc<TlsConnector>>,
+            handshake_timeout: Option<Duration>,
+        ) -> Result<MaybeTlsStream<S>, Error>
+        where
+            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+        {
+            match mode {
+                Mode::Plain => Ok(MaybeTlsStream::Plain(socket)),
+                Mode::Tls => {
+                    super::validate_dns_name(&domain)?;
+                    let connector = match tls_connector {
+                        Some(connector) => connector,
+                        None => Arc::new(TlsConnector::new().map_err(TlsError::Native)?),
+                    };
+                    let stream = TokioTlsConnector::from((*connector).clone());
+                    let connecting = stream.connect(&domain, socket);
+                    let connected = match handshake_timeout {
+                        Some(timeout) => tokio::time::timeout(timeout, connecting)
+                            .await
+                            .map_err(|_| super::handshake_timed_out(timeout))?,
+                        None => connecting.await,
+                    };
+
+                    match connected {
+                        Err(e) => Err(handshake_error(e)),
+                        Ok(s) => Ok(MaybeTlsStream::NativeTls(s)),
+                    }
+                }
+            }
+        }
+
+        /// Maps a failed handshake to an IO error if it was caused by the underlying socket
+        /// (e.g. a reset connection), so it is retried like any other IO error, and to a TLS
+        /// error otherwise.
+        fn handshake_error(e: native_tls::Error) -> Error {
+            let kind =
+                std::iter::successors(std::error::Error::source(&e), |source| source.source())
+                    .find_map(|source| source.downcast_ref::<std::io::Error>())
+                    .map(std::io::Error::kind);
+
+            match kind {
+                Some(kind) => Error::Io(std::io::Error::new(kind, e)),
+                None => Error::Tls(TlsError::Native(e)),
+            }
+        }
+    }
+
+    pub mod plain {
+        use std::io::ErrorKind;
+
+        use tokio::io::{AsyncRead, AsyncWrite};
+        use tokio_tungstenite::{
+            tungstenite::{error::Error, stream::Mode},
+            MaybeTlsStream,
+        };
+
+        pub async fn wrap_stream<S>(socket: S, mode: Mode) -> Result<MaybeTlsStream<S>, Error>
+        where
+            S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+        {
+            match mode {
+                Mode::Plain => Ok(MaybeTlsStream::Plain(socket)),
+                // TLS is available, but the caller explicitly chose not to use it
+                Mode::Tls => Err(Error::Io(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "Plain connector used for a TLS (wss) endpoint",
+                ))),
+            }
+        }
+    }
+}
+
+/// Wraps the given `stream` with TLS (depending on the `mode`) using the given `connector`.
+///
+/// The server name used for SNI and certificate verification is taken from the host of the
+/// `request`, unless an `sni_override` is given. This allows connecting the underlying stream
+/// to an IP address or internal host, while presenting the venue's public hostname.
+///
+/// If a `handshake_timeout` is given, the TLS handshake fails with an [`ErrorKind::TimedOut`]
+/// IO error when it does not complete in time (e.g. a server which accepts the TCP connection
+/// but stalls). Without a timeout the handshake may wait indefinitely.
+///
+/// Passing [`Connector::Plain`] for a TLS `mode` (i.e. a `wss://` endpoint) fails with an
+/// [`ErrorKind::InvalidInput`] IO error, rather than silently downgrading the connection.
+/// Without a connector, a TLS `mode` fails the same way if the default `rustls` config
+/// trusts no root certificates (e.g. no native certificates could be loaded).
+///
+/// With `rustls`, a server replying to the handshake in plaintext (e.g. with an HTTP
+/// redirect) fails with a TLS error suggesting a URL scheme or port mismatch.
+///
+/// A [`Connector::AutoFallback`] only attempts the `rustls` handshake, since a failed
+/// handshake consumes the `stream` (see [`tcp_tls_with_fallback`] for the retry).
+///
+/// The handshake runs within a `tls_handshake` tracing span, and on success its duration,
+/// along with the negotiated protocol version and cipher suite, are logged at debug level.
+pub async fn tcp_tls<S>(
+    request: &Request,
+    mode: Mode,
+    stream: S,
+    connector: Option<Connector>,
+    sni_override: Option<String>,
+    handshake_timeout: Option<Duration>,
+) -> Result<MaybeTlsStream<S>, Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let domain = match sni_override {
+        Some(name) => name,
+        None => domain(request)?,
+    };
+
+    let span = tracing::debug_span!("tls_handshake", domain = %domain, ?mode);
+    let start = Instant::now();
+    let result = async move {
+        match connector {
+            Some(conn) => match conn {
+                Connector::Rustls(conn) => {
+                    self::encryption::rustls::wrap_stream(
+                        stream,
+                        domain,
+                        mode,
+                        Some(conn),
+                        handshake_timeout,
+                    )
+                    .await
+                }
+                #[cfg(feature = "native-tls")]
+                Connector::NativeTls(conn) => {
+                    self::encryption::native_tls::wrap_stream(
+                        stream,
+                        domain,
+                        mode,
+                        Some(conn),
+                        handshake_timeout,
+                    )
+                    .await
+                }
+                #[cfg(feature = "native-tls")]
+                Connector::AutoFallback(conn, _) => {
+                    self::encryption::rustls::wrap_stream(
+                        stream,
+                        domain,
+                        mode,
+                        Some(conn),
+                        handshake_timeout,
+                    )
+                    .await
+                }
+                Connector::Plain => self::encryption::plain::wrap_stream(stream, mode).await,
+            },
+            None => {
+                self::encryption::rustls::wrap_stream(stream, domain, mode, None, handshake_timeout)
+                    .await
+            }
+        }
+    }
+    .instrument(span.clone())
+    .await;
+
+    if let Ok(stream) = &result {
+        span.in_scope(|| log_handshake(stream, start.elapsed()));
+    }
+
+    result
+}
+
+/// When [`tcp_tls_with_sni_fallback`] retries the handshake with the next server name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SniRetryPolicy {
+    /// Retry when the handshake fails with a TLS error, such as an alert from a server which
+    /// does not recognize the name, or a certificate which is not valid for the name.
+    #[default]
+    OnTlsError,
+    /// Also retry when the handshake fails with a transient IO error, for servers which
+    /// reset the connection for an unrecognized name.
+    OnAnyError,
+}
+
+/// Wraps a stream with TLS like [`tcp_tls`], presenting each of the `server_names` in turn
+/// for SNI until a handshake succeeds (e.g. for venues fronted by a CDN which requires a
+/// specific SNI, which differs from the host of the `request`).
+///
+/// A failed handshake consumes its stream, so a new stream is opened with `connect` for
+/// each attempt. Attempts stop at the first failure the `policy` does not retry.
+///
+/// # Errors
+///
+/// Returns an [`ErrorKind::InvalidInput`] IO error if `server_names` is empty, an error if a
+/// stream cannot be opened, otherwise the error of the last handshake attempted.
+pub async fn tcp_tls_with_sni_fallback<S, F, Fut>(
+    request: &Request,
+    mode: Mode,
+    mut connect: F,
+    connector: Option<Connector>,
+    server_names: &[String],
+    policy: SniRetryPolicy,
+    handshake_timeout: Option<Duration>,
+) -> Result<MaybeTlsStream<S>, Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::io::Result<S>>,
+{
+    let Some((last, fallbacks)) = server_names.split_last() else {
+        return Err(Error::Io(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            "No server names to try for SNI",
+        )));
+    };
+
+    for name in fallbacks {
+        let stream = connect().await?;
+        match tcp_tls(
+            request,
+            mode,
+            stream,
+            connector.clone(),
+            Some(name.clone()),
+            handshake_timeout,
+        )
+        .await
+        {
+            Ok(stream) => return Ok(stream),
+            Err(e) if retries_sni(&e, policy) => {
+                tracing::warn!("TLS handshake with SNI '{name}' failed, trying next name: {e}");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    let stream = connect().await?;
+    tcp_tls(
+        request,
+        mode,
+        stream,
+        connector,
+        Some(last.clone()),
+        handshake_timeout,
+    )
+    .await
+}
+
+/// Returns whether the handshake failing with the given `error` is retried with the next
+/// server name under the `policy`.
+fn retries_sni(error: &Error, policy: SniRetryPolicy) -> bool {
+    match error {
+        Error::Tls(_) => true,
+        Error::Io(_) => policy == SniRetryPolicy::OnAnyError && is_retryable(error),
+        _ => false,
+    }
+}
+
+/// Wraps the given `stream` with TLS like [`tcp_tls`], also returning the parameters
+/// negotiated during the handshake, so they can be recorded at connect time.
+///
+/// The returned info is only populated for `rustls` connections, and is empty for
+/// connections without TLS or using `native-tls`.
+pub async fn tcp_tls_with_info<S>(
+    request: &Request,
+    mode: Mode,
+    stream: S,
+    connector: Option<Connector>,
+    sni_override: Option<String>,
+    handshake_timeout: Option<Duration>,
+) -> Result<(MaybeTlsStream<S>, TlsConnectionInfo), Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let stream = tcp_tls(
+        request,
+        mode,
+        stream,
+        connector,
+        sni_override,
+        handshake_timeout,
+    )
+    .await?;
+    let info = TlsConnectionInfo::from_stream(&stream);
+    Ok((stream, info))
+}
+
+/// The parameters negotiated during the TLS handshake of a connection.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsConnectionInfo {
+    /// The negotiated protocol version.
+    pub protocol_version: Option<ProtocolVersion>,
+    /// The negotiated cipher suite.
+    pub cipher_suite: Option<CipherSuite>,
+    /// The ALPN protocol the server agreed on.
+    pub alpn_protocol: Option<Vec<u8>>,
+    /// The SHA-256 fingerprint of the DER encoded leaf certificate presented by the peer.
+    pub peer_cert_sha256: Option<[u8; 32]>,
+}
+
+impl TlsConnectionInfo {
+    /// Returns the info of the given `stream`, which must have completed its handshake.
+    #[must_use]
+    pub fn from_stream<S>(stream: &MaybeTlsStream<S>) -> Self {
+        match stream {
+            MaybeTlsStream::Rustls(s) => {
+                let conn = s.get_ref().1;
+                Self {
+                    protocol_version: conn.protocol_version(),
+                    cipher_suite: conn.negotiated_cipher_suite().map(|s| s.suite()),
+                    alpn_protocol: conn.alpn_protocol().map(<[u8]>::to_vec),
+                    peer_cert_sha256: conn
+                        .peer_certificates()
+                        .and_then(|certs| certs.first())
+                        .map(|cert| {
+                            let hash = ring::digest::digest(&ring::digest::SHA256, cert);
+                            let mut sha256 = [0u8; 32];
+                            sha256.copy_from_slice(hash.as_ref());
+                            sha256
+                        }),
+                }
+            }
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Wraps the given `stream` with TLS like [`tcp_tls`], returning as soon as the
+/// `cancellation_token` is cancelled rather than waiting for a stalled handshake to time out.
+///
+/// # Errors
+///
+/// Returns an [`ErrorKind::Interrupted`] IO error (see [`is_cancelled`]) if cancelled before
+/// the handshake completes, otherwise as [`tcp_tls`].
+pub async fn tcp_tls_cancellable<S>(
+    request: &Request,
+    mode: Mode,
+    stream: S,
+    connector: Option<Connector>,
+    sni_override: Option<String>,
+    handshake_timeout: Option<Duration>,
+    cancellation_token: &CancellationToken,
+) -> Result<MaybeTlsStream<S>, Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+{
+    cancellable(
+        tcp_tls(
+            request,
+            mode,
+            stream,
+            connector,
+            sni_override,
+            handshake_timeout,
+        ),
+        Some(cancellation_token),
+    )
+    .await
+}
+
+/// Wraps the given `stream` with TLS like [`tcp_tls`], waiting for a permit from the
+/// `handshake_limit` semaphore first if given (unbounded if `None`).
+///
+/// Sharing the semaphore between connections bounds the number of handshakes in flight at
+/// any time, e.g. when opening many venue connections at startup. The permit is held until
+/// the handshake completes or fails.
+///
+/// # Errors
+///
+/// Returns an IO error if the `handshake_limit` semaphore has been closed, otherwise as
+/// [`tcp_tls`].
+pub async fn tcp_tls_limited<S>(
+    request: &Request,
+    mode: Mode,
+    stream: S,
+    connector: Option<Connector>,
+    sni_override: Option<String>,
+    handshake_timeout: Option<Duration>,
+    handshake_limit: Option<&Semaphore>,
+) -> Result<MaybeTlsStream<S>, Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let _permit = match handshake_limit {
+        Some(semaphore) => Some(semaphore.acquire().await.map_err(|e| {
+            Error::Io(std::io::Error::new(
+                ErrorKind::Other,
+                format!("TLS handshake limit unavailable: {e}"),
+            ))
+        })?),
+        None => None,
+    };
+    tcp_tls(
+        request,
+        mode,
+        stream,
+        connector,
+        sni_override,
+        handshake_timeout,
+    )
+    .await
+}
+
+/// Wraps a stream opened with `connect` with TLS like [`tcp_tls_limited`], retrying once on
+/// a new stream when the handshake fails with an error the `connector` falls back on (see
+/// [`Connector::fallback_for`]), i.e. retrying a [`Connector::AutoFallback`] with `native-tls`
+/// when `rustls` rejects the server certificate chain.
+///
+/// # Errors
+///
+/// Returns an error if a stream cannot be opened, otherwise the error of the last handshake
+/// attempted.
+pub async fn tcp_tls_with_fallback<S, F, Fut>(
+    request: &Request,
+    mode: Mode,
+    mut connect: F,
+    connector: Option<Connector>,
+    sni_override: Option<String>,
+    handshake_timeout: Option<Duration>,
+    handshake_limit: Option<&Semaphore>,
+) -> Result<MaybeTlsStream<S>, Error>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Unpin,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::io::Result<S>>,
+{
+    let stream = connect().await?;
+    let error = match tcp_tls_limited(
+        request,
+        mode,
+        stream,
+        connector.clone(),
+        sni_override.clone(),
+        handshake_timeout,
+        handshake_limit,
+    )
+    .await
+    {
+        Ok(stream) => return Ok(stream),
+        Err(e) => e,
+    };
+
+    let Some(fallback) = connector.and_then(|connector| connector.fallback_for(&error)) else {
+        return Err(error);
+    };
+    tracing::warn!("TLS handshake failed, retrying with fallback connector: {error}");
+    let stream = connect().await?;
+    tcp_tls_limited(
+        request,
+        mode,
+        stream,
+        Some(fallback),
+        sni_override,
+        handshake_timeout,
+        handshake_limit,
+    )
+    .await
+}
+
+/// Logs the connection info of the given `stream` once its TLS handshake has completed.
+fn log_handshake<S>(stream: &MaybeTlsStream<S>, elapsed: Duration) {
+    match stream {
+        MaybeTlsStream::Rustls(s) => {
+            let conn = s.get_ref().1;
+            tracing::debug!(
+                ?elapsed,
+                version = ?conn.protocol_version(),
+                cipher_suite = ?conn.negotiated_cipher_suite().map(|s| s.suite()),
+                "TLS handshake completed",
+            );
+        }
+        #[cfg(feature = "native-tls")]
+        MaybeTlsStream::NativeTls(_) => {
+            tracing::debug!(?elapsed, "TLS handshake completed");
+        }
+        _ => {}
+    }
+}
+
+/// Returns the default `rustls` config used by [`tcp_tls`] when no connector is given.
+///
+/// The config trusts the native root certificates, which are loaded once on first use and
+/// then shared by every connection in the process.
+#[must_use]
+pub fn default_rustls_config() -> Arc<ClientConfig> {
+    self::encryption::rustls::default_config()
+}
+
+/// Loads the native (OS) root certificates into a new [`RootCertStore`].
+///
+/// The store can be loaded once at startup and shared by many connectors, rather than loading
+/// the OS trust store for each of them. Certificates which fail to load or parse are logged
+/// and skipped, as for the [`default_rustls_config`] (see [`Connector::rustls_strict_native`]
+/// to fail instead).
+///
+/// # Errors
+///
+/// Returns an error listing every loading error if no native certificate could be loaded.
+pub fn native_root_store() -> Result<RootCertStore, Error> {
+    tracing::info!("Loading native certificates");
+    lenient_native_root_store(load_native_certs())
+}
+
+/// Returns the ALPN protocol negotiated during the TLS handshake of the given `stream`.
+///
+/// Returns `None` for non-`rustls` streams, or if the server did not agree on a protocol.
+pub fn negotiated_alpn_protocol<S>(stream: &MaybeTlsStream<S>) -> Option<Vec<u8>> {
+    match stream {
+        MaybeTlsStream::Rustls(s) => s.get_ref().1.alpn_protocol().map(<[u8]>::to_vec),
+        _ => None,
+    }
+}
+
+/// Returns the certificate chain presented by the peer during the TLS handshake of the
+/// given `stream`, with the leaf certificate first.
+///
+/// This must be called after the handshake has completed (i.e. on a stream returned by
+/// [`tcp_tls`]). Returns `None` for non-`rustls` streams, or if no certificates were presented.
+pub fn peer_certificates<S>(stream: &MaybeTlsStream<S>) -> Option<Vec<CertificateDer<'static>>> {
+    match stream {
+        MaybeTlsStream::Rustls(s) => s
+            .get_ref()
+            .1
+            .peer_certificates()
+            .map(|certs| certs.iter().map(|c| c.clone().into_owned()).collect()),
+        _ => None,
+    }
+}
+
+fn domain(request: &tungstenite::handshake::client::Request) -> Result<String, Error> {
+    match request.uri().host() {
+        // rustls expects IPv6 addresses without the surrounding [] brackets
+        Some(d) if d.starts_with('[') && d.ends_with(']') => {
+            let addr = &d[1..d.len() - 1];
+            // Strip any zone identifier (e.g. `%eth0`, or URI encoded `%25eth0`)
+            let addr = addr.split_once('%').map_or(addr, |(addr, _zone_id)| addr);
+            Ok(addr.to_string())
+        }
+        Some(d) => Ok(d.to_string()),
+        None => Err(Error::Url(UrlError::NoHostName)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use rcgen::{
+        date_time_ymd, BasicConstraints, CertificateParams, CertificateRevocationListParams,
+        CertifiedKey, DnType, ExtendedKeyUsagePurpose, IsCa, KeyIdMethod, KeyPair,
+        RevocationReason, RevokedCertParams, SerialNumber,
+    };
+    use rustls::{
+        crypto::{ring, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+        server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier},
+        sign, HandshakeKind, RootCertStore, ServerConfig,
+    };
+    use tempfile::NamedTempFile;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        task,
+    };
+    use tokio_rustls::TlsAcceptor;
+    use tokio_tungstenite::tungstenite::{
+        client::IntoClientRequest, error::CapacityError, stream::Mode,
+    };
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::test_util::{install_crypto_provider, TestPki, TestTlsServer};
+
+    #[cfg(feature = "pkcs12")]
+    const TEST_DATA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/test_data");
+
+    /// Creates a `rustls` connector trusting only the test CA.
+    fn rustls_connector(pki: &TestPki) -> Connector {
+        let config = ClientConfig::builder()
+            .with_root_certificates(pki.root_store())
+            .with_no_client_auth();
+        Connector::Rustls(Arc::new(config))
+    }
+
+    async fn connect(
+        addr: SocketAddr,
+        connector: Option<Connector>,
+    ) -> Result<MaybeTlsStream<TcpStream>, Error> {
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        tcp_tls(&request, Mode::Tls, stream, connector, None, None).await
+    }
+
+    async fn echo<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut MaybeTlsStream<S>,
+        data: &[u8],
+    ) -> std::io::Result<Vec<u8>> {
+        stream.write_all(data).await?;
+        stream.flush().await?;
+        let mut buf = vec![0u8; data.len()];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_client_auth_connects() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_client_auth(pki.root_store())
+            .start()
+            .await;
+
+        let connector = Connector::rustls_with_client_auth(
+            pki.root_store(),
+            TestPki::chain(&pki.client),
+            TestPki::key(&pki.client),
+        )
+        .unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert!(matches!(stream, MaybeTlsStream::Rustls(_)));
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[cfg(feature = "pkcs12")]
+    #[tokio::test]
+    async fn test_rustls_with_pkcs12_connects() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        // The bundle holds a client certificate issued by the fixture CA
+        let (client_ca, _) =
+            load_pem_root_store(Path::new(&format!("{TEST_DATA_PATH}/client_ca.pem"))).unwrap();
+        let verifier = WebPkiClientVerifier::builder(Arc::new(client_ca))
+            .build()
+            .unwrap();
+        let config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(TestPki::chain(&pki.server), TestPki::key(&pki.server))
+            .unwrap();
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let connector = Connector::rustls_with_pkcs12(
+            pki.root_store(),
+            Path::new(&format!("{TEST_DATA_PATH}/client.p12")),
+            "nautilus",
+        )
+        .unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert!(matches!(stream, MaybeTlsStream::Rustls(_)));
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[cfg(feature = "pkcs12")]
+    #[rstest::rstest]
+    fn test_rustls_with_pkcs12_wrong_password() {
+        install_crypto_provider();
+        let path = format!("{TEST_DATA_PATH}/client.p12");
+
+        let result =
+            Connector::rustls_with_pkcs12(TestPki::new().root_store(), Path::new(&path), "wrong");
+
+        match result {
+            Err(Error::Io(e)) => {
+                assert_eq!(e.kind(), ErrorKind::InvalidInput);
+                assert_eq!(
+                    e.to_string(),
+                    format!("Invalid password for PKCS#12 file '{path}'")
+                );
+            }
+            other => panic!("Expected an invalid password error, was {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rustls_without_client_auth_is_rejected() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_client_auth(pki.root_store())
+            .start()
+            .await;
+
+        let connector = rustls_connector(&pki);
+
+        // With TLS 1.3 the server only rejects the missing certificate after the client
+        // considers the handshake complete, so the failure can surface on first use
+        let result = match connect(addr, Some(connector)).await {
+            Ok(mut stream) => echo(&mut stream, b"hello").await.map(|_| ()),
+            Err(e) => Err(std::io::Error::other(e)),
+        };
+
+        assert!(result.is_err());
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_with_client_auth_invalid_key() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let invalid_key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(vec![0u8; 32]));
+
+        let result = Connector::rustls_with_client_auth(
+            pki.root_store(),
+            TestPki::chain(&pki.client),
+            invalid_key,
+        );
+
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_rustls_from_pem_file_connects() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        // A well-formed PEM section which does not hold a valid certificate is skipped
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "{}-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n",
+            pki.ca.cert.pem()
+        )
+        .unwrap();
+
+        let (connector, (added, skipped)) = Connector::rustls_from_pem_file(file.path()).unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(added, 1);
+        assert_eq!(skipped, 1);
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_from_pem_file_without_certificates() {
+        install_crypto_provider();
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "not a certificate").unwrap();
+
+        let result = Connector::rustls_from_pem_file(file.path());
+
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_from_pem_file_missing_file() {
+        install_crypto_provider();
+        let result = Connector::rustls_from_pem_file(Path::new("/nonexistent/bundle.pem"));
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[tokio::test]
+    async fn test_native_tls_connects() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        let ca_cert = native_tls::Certificate::from_der(pki.ca.cert.der()).unwrap();
+        let tls_connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(ca_cert)
+            .build()
+            .unwrap();
+        let connector = Connector::NativeTls(Arc::new(tls_connector));
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert!(matches!(stream, MaybeTlsStream::NativeTls(_)));
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[tokio::test]
+    async fn test_native_tls_reset_during_handshake_is_io_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        task::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1];
+            let _ = stream.read(&mut buf).await;
+            stream.set_linger(Some(std::time::Duration::ZERO)).unwrap();
+        });
+
+        let tls_connector = native_tls::TlsConnector::new().unwrap();
+        let connector = Connector::NativeTls(Arc::new(tls_connector));
+        let error = connect(addr, Some(connector)).await.unwrap_err();
+
+        assert!(matches!(&error, Error::Io(_)), "{error:?}");
+        assert!(is_retryable(&error));
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[tokio::test]
+    async fn test_auto_fallback_retries_with_native_tls_on_incomplete_chain() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Nautilus Test Intermediate CA");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = params
+            .signed_by(&key_pair, &pki.ca.cert, &pki.ca.key_pair)
+            .unwrap();
+        let intermediate = CertifiedKey { cert, key_pair };
+        let server = TestPki::issue_with(
+            &intermediate,
+            "localhost",
+            ExtendedKeyUsagePurpose::ServerAuth,
+        );
+
+        // The server omits the intermediate, which only the native-tls connector holds
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(TestPki::chain(&server), TestPki::key(&server))
+            .unwrap();
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let rustls_config = Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(pki.root_store())
+                .with_no_client_auth(),
+        );
+        let native_connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(native_tls::Certificate::from_der(pki.ca.cert.der()).unwrap())
+            .add_root_certificate(
+                native_tls::Certificate::from_der(intermediate.cert.der()).unwrap(),
+            )
+            .build()
+            .unwrap();
+        let connector = Connector::AutoFallback(rustls_config, Arc::new(native_connector));
+
+        let error = connect(addr, Some(connector.clone())).await.unwrap_err();
+        assert!(is_chain_validation_error(&error));
+
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let mut stream = tcp_tls_with_fallback(
+            &request,
+            Mode::Tls,
+            || TcpStream::connect(addr),
+            Some(connector),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(stream, MaybeTlsStream::NativeTls(_)));
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[tokio::test]
+    async fn test_auto_fallback_does_not_retry_io_errors() {
+        install_crypto_provider();
+        let native_connector = native_tls::TlsConnector::new().unwrap();
+        let config = Arc::new(
+            ClientConfig::builder()
+                .with_root_certificates(RootCertStore::empty())
+                .with_no_client_auth(),
+        );
+        let connector = Connector::AutoFallback(config, Arc::new(native_connector));
+        let error = Error::Io(std::io::Error::from(ErrorKind::TimedOut));
+
+        assert!(connector.fallback_for(&error).is_none());
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[tokio::test]
+    async fn test_auto_fallback_does_not_retry_pin_mismatch() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let other_pin = pinning::spki_sha256(pki.client.cert.der()).unwrap();
+
+        // The native-tls connector trusts the server, so would accept the fallback connection
+        let Connector::Rustls(pinned_config) =
+            Connector::rustls_with_pins(pki.root_store(), vec![other_pin]).unwrap()
+        else {
+            panic!("Expected a `rustls` connector");
+        };
+        let native_connector = native_tls::TlsConnector::builder()
+            .add_root_certificate(native_tls::Certificate::from_der(pki.ca.cert.der()).unwrap())
+            .build()
+            .unwrap();
+        let connector = Connector::AutoFallback(pinned_config, Arc::new(native_connector));
+
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let result = tcp_tls_with_fallback(
+            &request,
+            Mode::Tls,
+            || TcpStream::connect(addr),
+            Some(connector),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Tls(TlsError::Rustls(
+                rustls::Error::InvalidCertificate(
+                    rustls::CertificateError::ApplicationVerificationFailure
+                )
+            )))
+        ));
+    }
+
+    #[cfg(feature = "dangerous-tls")]
+    #[tokio::test]
+    async fn test_rustls_insecure_accepts_untrusted_certificate() {
+        install_crypto_provider();
+        let key_pair = KeyPair::generate().unwrap();
+        let cert = CertificateParams::new(vec!["localhost".to_string()])
+            .unwrap()
+            .self_signed(&key_pair)
+            .unwrap();
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.der().clone()],
+                TestPki::key(&CertifiedKey { cert, key_pair }),
+            )
+            .unwrap();
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let mut stream = connect(addr, Some(Connector::rustls_insecure()))
+            .await
+            .unwrap();
+
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_alpn_negotiates_protocol() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+
+        let protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        let connector = Connector::rustls_with_alpn(pki.root_store(), protocols);
+        let stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(negotiated_alpn_protocol(&stream), Some(b"h2".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_tls_connector_builder_with_pem_file_and_alpn() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", pki.ca.cert.pem()).unwrap();
+
+        let connector = TlsConnectorBuilder::new()
+            .with_pem_file(file.path())
+            .with_alpn(vec![b"h2".to_vec()])
+            .with_versions(&[&rustls::version::TLS13])
+            .build()
+            .unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(negotiated_alpn_protocol(&stream), Some(b"h2".to_vec()));
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_tls_profiles_connector_per_venue() {
+        install_crypto_provider();
+        let cme_pki = TestPki::new();
+        let cme_addr = TestTlsServer::new(&cme_pki)
+            .with_client_auth(cme_pki.root_store())
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+        let binance_pki = TestPki::new();
+        let binance_addr = TestTlsServer::new(&binance_pki).start().await;
+
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", binance_pki.ca.cert.pem()).unwrap();
+        let profiles = TlsProfiles::new(HashMap::from([
+            (
+                "XCME",
+                TlsProfile {
+                    ca_sources: vec![CaSource::RootStore(cme_pki.root_store())],
+                    client_auth: Some((
+                        TestPki::chain(&cme_pki.client),
+                        TestPki::key(&cme_pki.client),
+                    )),
+                    alpn_protocols: vec![b"h2".to_vec()],
+                    ..Default::default()
+                },
+            ),
+            (
+                "BINANCE",
+                TlsProfile {
+                    ca_sources: vec![CaSource::PemFile(file.path().to_path_buf())],
+                    versions: Some(vec![&rustls::version::TLS13]),
+                    pins: Some(vec![
+                        pinning::spki_sha256(binance_pki.server.cert.der()).unwrap()
+                    ]),
+                    ..Default::default()
+                },
+            ),
+        ]))
+        .unwrap();
+
+        let mut cme = connect(cme_addr, profiles.connector(&"XCME"))
+            .await
+            .unwrap();
+        let mut binance = connect(binance_addr, profiles.connector(&"BINANCE"))
+            .await
+            .unwrap();
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(negotiated_alpn_protocol(&cme), Some(b"h2".to_vec()));
+        assert_eq!(echo(&mut cme, b"hello").await.unwrap(), b"hello");
+        assert_eq!(negotiated_alpn_protocol(&binance), None);
+        assert_eq!(echo(&mut binance, b"hello").await.unwrap(), b"hello");
+        assert!(connect(binance_addr, profiles.connector(&"XCME"))
+            .await
+            .is_err());
+        assert!(profiles.connector(&"XNAS").is_none());
+    }
+
+    #[rstest::rstest]
+    fn test_tls_profiles_with_invalid_profile() {
+        install_crypto_provider();
+        let result = TlsProfiles::new(HashMap::from([("XCME", TlsProfile::default())]));
+
+        match result {
+            Err(Error::Tls(TlsError::Rustls(rustls::Error::General(msg)))) => {
+                assert!(msg.starts_with("Invalid TLS profile for XCME: "));
+            }
+            _ => panic!("Expected invalid profile error"),
+        }
+    }
+
+    /// Spawns a plaintext server which replies to any data with the `response`.
+    async fn start_plaintext_server(response: &'static [u8]) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        task::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream.write_all(response).await.unwrap();
+            let _ = stream.shutdown().await;
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_plaintext_http_response() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = start_plaintext_server(
+            b"HTTP/1.1 301 Moved Permanently\r\nLocation: https://localhost/\r\n\r\n",
+        )
+        .await;
+
+        let result = connect(addr, Some(rustls_connector(&pki))).await;
+
+        match result {
+            Err(Error::Tls(TlsError::Rustls(rustls::Error::General(msg)))) => assert_eq!(
+                msg,
+                "Received a plaintext reply from 'localhost' where a TLS handshake was \
+                expected: check the URL scheme (`wss://` vs `ws://`) and port"
+            ),
+            other => panic!("Expected plaintext response error, was {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_invalid_tls_record() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        // A handshake record with an unknown protocol version
+        let addr = start_plaintext_server(&[0x16, 0x00, 0x00, 0x00, 0x01, 0x00]).await;
+
+        let result = connect(addr, Some(rustls_connector(&pki))).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Tls(TlsError::Rustls(rustls::Error::InvalidMessage(
+                _
+            ))))
+        ));
+    }
+
+    #[rstest::rstest]
+    fn test_tls_connector_builder_without_roots() {
+        install_crypto_provider();
+        let result = TlsConnectorBuilder::new()
+            .with_alpn(vec![b"h2".to_vec()])
+            .build();
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_rustls_without_alpn_negotiates_no_protocol() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+
+        let connector = Connector::rustls_with_alpn(pki.root_store(), vec![]);
+        let stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(negotiated_alpn_protocol(&stream), None);
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_alpn_no_common_protocol_is_rejected() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki)
+            .with_alpn(vec![b"h2".to_vec()])
+            .start()
+            .await;
+
+        let connector = Connector::rustls_with_alpn(pki.root_store(), vec![b"http/1.1".to_vec()]);
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest::rstest]
+    #[case("wss://[::1]:443/path", "::1")]
+    #[case("wss://[fe80::1%eth0]:443/path", "fe80::1")]
+    #[case("wss://[fe80::1%25eth0]:443/path", "fe80::1")]
+    #[case("wss://venue.example.com:443/path", "venue.example.com")]
+    fn test_domain(#[case] uri: &str, #[case] expected: &str) {
+        let request = Request::builder().uri(uri).body(()).unwrap();
+        assert_eq!(domain(&request).unwrap(), expected);
+    }
+
+    #[rstest::rstest]
+    fn test_domain_without_host_returns_error() {
+        let request = Request::builder().uri("/path").body(()).unwrap();
+        let result = domain(&request);
+        assert!(matches!(result, Err(Error::Url(UrlError::NoHostName))));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_without_host_returns_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let request = Request::builder().uri("/path").body(()).unwrap();
+
+        let result = tcp_tls(&request, Mode::Tls, stream, None, None, None).await;
+
+        assert!(matches!(result, Err(Error::Url(UrlError::NoHostName))));
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_versions_negotiates_version() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        let connector =
+            Connector::rustls_with_versions(pki.root_store(), &[&rustls::version::TLS12]).unwrap();
+        let stream = connect(addr, Some(connector)).await.unwrap();
+
+        let MaybeTlsStream::Rustls(stream) = stream else {
+            panic!("Expected a `rustls` stream");
+        };
+        assert_eq!(
+            stream.get_ref().1.protocol_version(),
+            Some(rustls::ProtocolVersion::TLSv1_2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_versions_unsupported_by_server() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let config = ServerConfig::builder_with_protocol_versions(&[&rustls::version::TLS12])
+            .with_no_client_auth()
+            .with_single_cert(TestPki::chain(&pki.server), TestPki::key(&pki.server))
+            .unwrap();
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let connector =
+            Connector::rustls_with_versions(pki.root_store(), &[&rustls::version::TLS13]).unwrap();
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(result.is_err());
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_with_versions_empty() {
+        install_crypto_provider();
+        let result = Connector::rustls_with_versions(RootCertStore::empty(), &[]);
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
+    }
+
+    /// Resolves a single certificate while recording the SNI presented by each client.
+    #[derive(Debug)]
+    struct SniRecorder {
+        certified_key: Arc<sign::CertifiedKey>,
+        server_names: Mutex<Vec<String>>,
+        /// The only server name the handshake is accepted for, if any.
+        accepted_name: Option<&'static str>,
+    }
+
+    impl SniRecorder {
+        fn new(pki: &TestPki, name: &str, accepted_name: Option<&'static str>) -> Arc<Self> {
+            let server = pki.issue_server(name);
+            let signing_key = CryptoProvider::get_default()
+                .unwrap()
+                .key_provider
+                .load_private_key(TestPki::key(&server))
+                .unwrap();
+            Arc::new(Self {
+                certified_key: Arc::new(sign::CertifiedKey::new(
+                    TestPki::chain(&server),
+                    signing_key,
+                )),
+                server_names: Mutex::new(Vec::new()),
+                accepted_name,
+            })
+        }
+    }
+
+    impl ResolvesServerCert for SniRecorder {
+        fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<sign::CertifiedKey>> {
+            let name = client_hello.server_name()?.to_string();
+            self.server_names.lock().unwrap().push(name.clone());
+            match self.accepted_name {
+                Some(accepted) if accepted != name => None,
+                _ => Some(self.certified_key.clone()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_with_sni_override() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let recorder = SniRecorder::new(&pki, "venue.example.com", None);
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(recorder.clone());
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        // The TCP connection targets the IP address, while SNI presents the public name
+        let request = format!("wss://{addr}").into_client_request().unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connector = rustls_connector(&pki);
+        let mut stream = tcp_tls(
+            &request,
+            Mode::Tls,
+            stream,
+            Some(connector),
+            Some("venue.example.com".to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+        assert_eq!(
+            *recorder.server_names.lock().unwrap(),
+            vec!["venue.example.com".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_session_cache_resumes_session() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let connector = Connector::rustls_with_session_cache(pki.root_store(), 64);
+
+        let mut handshake_kinds = Vec::new();
+        for _ in 0..2 {
+            let mut stream = connect(addr, Some(connector.clone())).await.unwrap();
+            // Round trip so the TLS 1.3 session ticket sent after the handshake is received
+            echo(&mut stream, b"hello").await.unwrap();
+            let MaybeTlsStream::Rustls(stream) = stream else {
+                panic!("Expected a `rustls` stream");
+            };
+            handshake_kinds.push(stream.get_ref().1.handshake_kind());
+        }
+
+        assert_eq!(
+            handshake_kinds,
+            vec![Some(HandshakeKind::Full), Some(HandshakeKind::Resumed)]
+        );
+    }
+
+    /// A session store counting the TLS 1.3 tickets taken from the wrapped memory cache.
+    #[derive(Debug)]
+    struct CountingSessionStore {
+        inner: ClientSessionMemoryCache,
+        tickets_taken: AtomicUsize,
+    }
+
+    impl ClientSessionStore for CountingSessionStore {
+        fn set_kx_hint(&self, server_name: ServerName<'static>, group: rustls::NamedGroup) {
+            self.inner.set_kx_hint(server_name, group);
+        }
+
+        fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<rustls::NamedGroup> {
+            self.inner.kx_hint(server_name)
+        }
+
+        fn set_tls12_session(
+            &self,
+            server_name: ServerName<'static>,
+            value: rustls::client::Tls12ClientSessionValue,
+        ) {
+            self.inner.set_tls12_session(server_name, value);
+        }
+
+        fn tls12_session(
+            &self,
+            server_name: &ServerName<'_>,
+        ) -> Option<rustls::client::Tls12ClientSessionValue> {
+            self.inner.tls12_session(server_name)
+        }
+
+        fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+            self.inner.remove_tls12_session(server_name);
+        }
+
+        fn insert_tls13_ticket(
+            &self,
+            server_name: ServerName<'static>,
+            value: rustls::client::Tls13ClientSessionValue,
+        ) {
+            self.inner.insert_tls13_ticket(server_name, value);
+        }
+
+        fn take_tls13_ticket(
+            &self,
+            server_name: &ServerName<'static>,
+        ) -> Option<rustls::client::Tls13ClientSessionValue> {
+            self.tickets_taken.fetch_add(1, Ordering::SeqCst);
+            self.inner.take_tls13_ticket(server_name)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rustls_handshake_takes_one_ticket_per_connection() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let store = Arc::new(CountingSessionStore {
+            inner: ClientSessionMemoryCache::new(64),
+            tickets_taken: AtomicUsize::new(0),
+        });
+        let connector = Connector::rustls_with_session_store(pki.root_store(), store.clone());
+
+        for _ in 0..2 {
+            let mut stream = connect(addr, Some(connector.clone())).await.unwrap();
+            echo(&mut stream, b"hello").await.unwrap();
+        }
+
+        assert_eq!(store.tickets_taken.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_pins_accepts_pinned_key() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let pin = pinning::spki_sha256(pki.server.cert.der()).unwrap();
+
+        let connector = Connector::rustls_with_pins(pki.root_store(), vec![pin]).unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_pins_rejects_unpinned_key() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let other_pin = pinning::spki_sha256(pki.client.cert.der()).unwrap();
+
+        let connector = Connector::rustls_with_pins(pki.root_store(), vec![other_pin]).unwrap();
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Tls(TlsError::Rustls(
+                rustls::Error::InvalidCertificate(
+                    rustls::CertificateError::ApplicationVerificationFailure
+                )
+            )))
+        ));
+    }
+
+    /// Starts an echo server presenting a certificate issued for `name` (while the client
+    /// connects to `localhost`).
+    async fn start_named_echo_server(pki: &TestPki, name: &str) -> SocketAddr {
+        TestTlsServer::new(pki)
+            .with_cert(&pki.issue_server(name))
+            .start()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_rustls_expect_name_accepts_matching_name() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = start_named_echo_server(&pki, "gateway.venue.internal").await;
+
+        let connector =
+            Connector::rustls_expect_name(pki.root_store(), "gateway.venue.internal".to_string())
+                .unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[rstest::rstest]
+    #[case::host_verifier(None)]
+    #[case::other_expected_name(Some("other.venue.internal"))]
+    #[tokio::test]
+    async fn test_rustls_expect_name_rejects_mismatched_name(#[case] expected: Option<&str>) {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = start_named_echo_server(&pki, "gateway.venue.internal").await;
+
+        let connector = match expected {
+            Some(name) => {
+                Connector::rustls_expect_name(pki.root_store(), name.to_string()).unwrap()
+            }
+            None => rustls_connector(&pki),
+        };
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Tls(TlsError::Rustls(
+                rustls::Error::InvalidCertificate(rustls::CertificateError::NotValidForName)
+            )))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rustls_expect_name_rejects_untrusted_chain() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = start_named_echo_server(&pki, "gateway.venue.internal").await;
+
+        // Trusts another CA, so the chain is rejected even though the name matches
+        let connector = Connector::rustls_expect_name(
+            TestPki::new().root_store(),
+            "gateway.venue.internal".to_string(),
+        )
+        .unwrap();
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Tls(TlsError::Rustls(
+                rustls::Error::InvalidCertificate(_)
+            )))
+        ));
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_expect_name_invalid_name() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+
+        let result = Connector::rustls_expect_name(pki.root_store(), "not a name!".to_string());
+
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_with_pins_empty() {
+        install_crypto_provider();
+        let result = Connector::rustls_with_pins(RootCertStore::empty(), vec![]);
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_handshake_timeout() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        // Accepts the TCP connection but never responds to the TLS handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = task::spawn(async move { listener.accept().await.unwrap() });
+
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let result = tcp_tls(
+            &request,
+            Mode::Tls,
+            stream,
+            Some(rustls_connector(&pki)),
+            None,
+            Some(Duration::from_millis(100)),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::TimedOut));
+        drop(server);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_tcp_tls_logs_handshake() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        let connector =
+            Connector::rustls_with_versions(pki.root_store(), &[&rustls::version::TLS13]).unwrap();
+        connect(addr, Some(connector)).await.unwrap();
+
+        assert!(logs_contain("tls_handshake{domain=localhost mode=Tls}"));
+        assert!(logs_contain("TLS handshake completed"));
+        assert!(logs_contain("version=Some(TLSv1_3)"));
+        assert!(logs_contain("cipher_suite=Some(TLS13_"));
+        assert!(logs_contain("elapsed="));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_with_info() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let connector =
+            Connector::rustls_with_versions(pki.root_store(), &[&rustls::version::TLS13]).unwrap();
+
+        let (_stream, info) =
+            tcp_tls_with_info(&request, Mode::Tls, stream, Some(connector), None, None)
+                .await
+                .unwrap();
+
+        let fingerprint = ::ring::digest::digest(&::ring::digest::SHA256, pki.server.cert.der());
+        assert_eq!(info.protocol_version, Some(ProtocolVersion::TLSv1_3));
+        assert!(format!("{:?}", info.cipher_suite.unwrap()).starts_with("TLS13_"));
+        assert_eq!(info.alpn_protocol, None);
+        assert_eq!(
+            info.peer_cert_sha256.as_ref().map(<[u8; 32]>::as_slice),
+            Some(fingerprint.as_ref())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_with_info_without_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let request = "ws://localhost/path".into_client_request().unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let (_stream, info) = tcp_tls_with_info(&request, Mode::Plain, stream, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(info, TlsConnectionInfo::default());
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_with_sni_fallback() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        // The server rejects any name other than the one its certificate is valid for
+        let recorder = SniRecorder::new(&pki, "venue.example.com", Some("venue.example.com"));
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(recorder.clone());
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let request = format!("wss://{addr}").into_client_request().unwrap();
+        let server_names = vec![
+            "cdn.example.com".to_string(),
+            "venue.example.com".to_string(),
+        ];
+        let mut stream = tcp_tls_with_sni_fallback(
+            &request,
+            Mode::Tls,
+            || TcpStream::connect(addr),
+            Some(rustls_connector(&pki)),
+            &server_names,
+            SniRetryPolicy::default(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(echo(&mut stream, b"ping").await.unwrap(), b"ping");
+        assert_eq!(*recorder.server_names.lock().unwrap(), server_names);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_with_sni_fallback_when_all_rejected() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let recorder = SniRecorder::new(&pki, "venue.example.com", Some("venue.example.com"));
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(recorder.clone());
+        let addr = TestTlsServer::start_with_config(config).await;
+
+        let request = format!("wss://{addr}").into_client_request().unwrap();
+        let server_names = vec![
+            "cdn.example.com".to_string(),
+            "edge.example.com".to_string(),
+        ];
+        let result = tcp_tls_with_sni_fallback(
+            &request,
+            Mode::Tls,
+            || TcpStream::connect(addr),
+            Some(rustls_connector(&pki)),
+            &server_names,
+            SniRetryPolicy::default(),
+            None,
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Tls(_))));
+        assert_eq!(*recorder.server_names.lock().unwrap(), server_names);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_cancellable_during_stalled_handshake() {
+        install_crypto_provider();
+        // Accepts the TCP connection but never responds to the TLS handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = task::spawn(async move { listener.accept().await });
+        let request = format!("wss://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        task::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            canceller.cancel();
+        });
+
+        let start = Instant::now();
+        let result =
+            tcp_tls_cancellable(&request, Mode::Tls, stream, None, None, None, &token).await;
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let e = result.unwrap_err();
+        assert!(is_cancelled(&e));
+        assert!(!is_retryable(&e));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_limited_bounds_concurrent_handshakes() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let acceptor = TlsAcceptor::from(Arc::new(TestTlsServer::new(&pki).config()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let (in_flight_server, max_in_flight_server) = (in_flight.clone(), max_in_flight.clone());
+        let server = task::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                let in_flight = in_flight_server.clone();
+                let max_in_flight = max_in_flight_server.clone();
+                task::spawn(async move {
+                    // A handshake is in flight once its client hello arrives
+                    stream.peek(&mut [0u8; 1]).await.unwrap();
+                    let count = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(count, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let _ = acceptor.accept(stream).await;
+                });
+            }
+        });
+
+        let semaphore = Arc::new(Semaphore::new(2));
+        let connects: Vec<_> = (0..8)
+            .map(|_| {
+                let semaphore = semaphore.clone();
+                let connector = rustls_connector(&pki);
+                task::spawn(async move {
+                    let request = format!("wss://localhost:{}", addr.port())
+                        .into_client_request()
+                        .unwrap();
+                    let stream = TcpStream::connect(addr).await.unwrap();
+                    tcp_tls_limited(
+                        &request,
+                        Mode::Tls,
+                        stream,
+                        Some(connector),
+                        None,
+                        None,
+                        Some(&semaphore),
+                    )
+                    .await
+                })
+            })
+            .collect();
+        for connect in connects {
+            assert!(matches!(
+                connect.await.unwrap(),
+                Ok(MaybeTlsStream::Rustls(_))
+            ));
+        }
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_limited_when_semaphore_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let request = format!("ws://localhost:{}", addr.port())
+            .into_client_request()
+            .unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let semaphore = Semaphore::new(1);
+        semaphore.close();
+
+        let result = tcp_tls_limited(
+            &request,
+            Mode::Plain,
+            stream,
+            Some(Connector::Plain),
+            None,
+            None,
+            Some(&semaphore),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::Other));
+    }
+
+    #[tokio::test]
+    async fn test_cancellable_when_not_cancelled() {
+        let token = CancellationToken::new();
+
+        let result = cancellable(async { Ok(1) }, Some(&token)).await;
+
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_tls_plain_connector_with_tls_mode() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let result = connect(addr, Some(Connector::Plain)).await;
+
+        match result {
+            Err(Error::Io(e)) => {
+                assert_eq!(e.kind(), ErrorKind::InvalidInput);
+                assert_eq!(
+                    e.to_string(),
+                    "Plain connector used for a TLS (wss) endpoint"
+                );
+            }
+            other => panic!("Expected IO error, was {other:?}"),
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(
+        format!("{}.example.com", "a".repeat(64)),
+        format!("label '{}' is 64 characters long, at most 63 allowed", "a".repeat(64))
+    )]
+    #[case(
+        vec!["a".repeat(50); 6].join("."),
+        "305 characters long, at most 253 allowed".to_string()
+    )]
+    #[tokio::test]
+    async fn test_tcp_tls_with_invalid_dns_name(#[case] host: String, #[case] reason: String) {
+        install_crypto_provider();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let request = format!("wss://{host}:443").into_client_request().unwrap();
+
+        let result = tcp_tls(&request, Mode::Tls, stream, None, None, None).await;
+
+        match result {
+            Err(Error::Tls(TlsError::Rustls(rustls::Error::General(msg)))) => {
+                assert_eq!(msg, format!("Invalid DNS name '{host}': {reason}"));
+            }
+            other => panic!("Expected invalid DNS name error, was {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer_certificates_returns_server_chain() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+
+        let stream = connect(addr, Some(rustls_connector(&pki))).await.unwrap();
+        let certs = peer_certificates(&stream).unwrap();
+
+        assert_eq!(certs.first(), Some(pki.server.cert.der()));
+    }
+
+    #[tokio::test]
+    async fn test_peer_certificates_plain_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        assert!(peer_certificates(&MaybeTlsStream::Plain(stream)).is_none());
+    }
+
+    #[cfg(feature = "tls-keylog")]
+    #[derive(Debug, Default)]
+    struct RecordingKeyLog {
+        labels: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[cfg(feature = "tls-keylog")]
+    impl rustls::KeyLog for RecordingKeyLog {
+        fn log(&self, label: &str, _client_random: &[u8], _secret: &[u8]) {
+            self.labels.lock().unwrap().push(label.to_string());
+        }
+    }
+
+    #[cfg(feature = "tls-keylog")]
+    #[tokio::test]
+    async fn test_rustls_with_keylog_logs_secrets() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let key_log = Arc::new(RecordingKeyLog::default());
+
+        let connector = Connector::rustls_with_keylog(pki.root_store(), key_log.clone());
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+        echo(&mut stream, b"hello").await.unwrap();
+
+        let labels = key_log.labels.lock().unwrap();
+        assert!(labels
+            .iter()
+            .any(|label| label == "CLIENT_TRAFFIC_SECRET_0"));
+    }
+
+    #[tokio::test]
+    async fn test_default_rustls_config_is_shared_across_tasks() {
+        install_crypto_provider();
+        let handles: Vec<_> = (0..4)
+            .map(|_| task::spawn(async { default_rustls_config() }))
+            .collect();
+
+        let expected = default_rustls_config();
+        for handle in handles {
+            assert!(Arc::ptr_eq(&handle.await.unwrap(), &expected));
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(Error::Io(std::io::Error::from(ErrorKind::ConnectionRefused)), true)]
+    #[case(Error::Io(std::io::Error::from(ErrorKind::TimedOut)), true)]
+    #[case(Error::Io(std::io::Error::from(ErrorKind::InvalidInput)), false)]
+    #[case(Error::Io(std::io::Error::from(ErrorKind::Interrupted)), true)]
+    #[case(
+        Error::Io(std::io::Error::new(ErrorKind::Interrupted, Cancelled)),
+        false
+    )]
+    #[case(Error::ConnectionClosed, true)]
+    #[case(Error::Tls(TlsError::InvalidDnsName), false)]
+    #[case(
+        Error::Tls(TlsError::Rustls(rustls::Error::InvalidCertificate(
+            CertificateError::UnknownIssuer
+        ))),
+        false
+    )]
+    #[case(Error::Url(UrlError::NoHostName), false)]
+    #[case(Error::Protocol(ProtocolError::HandshakeIncomplete), true)]
+    #[case(Error::Protocol(ProtocolError::SecWebSocketAcceptKeyMismatch), false)]
+    #[case(Error::Capacity(CapacityError::TooManyHeaders), false)]
+    #[case(Error::Utf8, false)]
+    fn test_is_retryable(#[case] error: Error, #[case] expected: bool) {
+        assert_eq!(is_retryable(&error), expected);
+    }
+
+    #[rstest::rstest]
+    #[case(503, true)]
+    #[case(429, true)]
+    #[case(401, false)]
+    #[case(404, false)]
+    fn test_is_retryable_http_response(#[case] status: u16, #[case] expected: bool) {
+        let response = tungstenite::http::Response::builder()
+            .status(status)
+            .body(None)
+            .unwrap();
+        assert_eq!(is_retryable(&Error::Http(response)), expected);
+    }
+
+    #[rstest::rstest]
+    fn test_strict_native_root_store() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let mut result = CertificateResult::default();
+        result.certs.push(pki.ca.cert.der().clone());
+
+        let root_store = strict_native_root_store(result).unwrap();
+
+        assert_eq!(root_store.len(), 1);
+    }
+
+    #[rstest::rstest]
+    fn test_strict_native_root_store_with_load_error() {
+        let pki = TestPki::new();
+        let mut result = CertificateResult::default();
+        result.certs.push(pki.ca.cert.der().clone());
+        result.errors.push(rustls_native_certs::Error {
+            context: "failed to read native certs",
+            kind: rustls_native_certs::ErrorKind::Os("Keychain unavailable".into()),
+        });
+
+        let result = strict_native_root_store(result);
+
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::Other));
+    }
+
+    #[rstest::rstest]
+    fn test_strict_native_root_store_with_invalid_certificate() {
+        let pki = TestPki::new();
+        let mut result = CertificateResult::default();
+        result.certs.push(pki.ca.cert.der().clone());
+        result.certs.push(CertificateDer::from(vec![0u8; 16]));
+
+        let result = strict_native_root_store(result);
+
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::InvalidData));
+    }
+
+    #[rstest::rstest]
+    fn test_native_root_store() {
+        install_crypto_provider();
+        let root_store = native_root_store().unwrap();
+        assert!(!root_store.is_empty());
+    }
+
+    #[rstest::rstest]
+    fn test_lenient_native_root_store_skips_failures() {
+        let pki = TestPki::new();
+        let mut result = CertificateResult::default();
+        result.certs.push(pki.ca.cert.der().clone());
+        result.certs.push(CertificateDer::from(vec![0u8; 16]));
+        result.errors.push(rustls_native_certs::Error {
+            context: "failed to read native certs",
+            kind: rustls_native_certs::ErrorKind::Os("Keychain unavailable".into()),
+        });
+
+        let root_store = lenient_native_root_store(result).unwrap();
+
+        assert_eq!(root_store.len(), 1);
+    }
+
+    #[rstest::rstest]
+    fn test_lenient_native_root_store_aggregates_errors() {
+        let mut result = CertificateResult::default();
+        result.certs.push(CertificateDer::from(vec![0u8; 16]));
+        for context in ["failed to read keychain", "failed to read cert dir"] {
+            result.errors.push(rustls_native_certs::Error {
+                context,
+                kind: rustls_native_certs::ErrorKind::Os("unavailable".into()),
+            });
+        }
+
+        let Err(Error::Io(e)) = lenient_native_root_store(result) else {
+            panic!("Expected an IO error");
+        };
+
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        let msg = e.to_string();
+        assert!(msg.contains("1 failed to parse, 2 errors"));
+        assert!(msg.contains("failed to read keychain"));
+        assert!(msg.contains("failed to read cert dir"));
+    }
+
+    #[rstest::rstest]
+    #[case(Ok(RootCertStore::empty()), "root certificate store is empty")]
+    #[case(
+        lenient_native_root_store(CertificateResult::default()),
+        "No native certificates loaded"
+    )]
+    #[tokio::test]
+    async fn test_wrap_stream_without_trusted_roots(
+        #[case] roots: Result<RootCertStore, Error>,
+        #[case] reason: &str,
+    ) {
+        install_crypto_provider();
+        let default_config = encryption::rustls::DefaultConfig::load(|| roots);
+        let (stream, _server) = tokio::io::duplex(1024);
+
+        let result = encryption::rustls::wrap_stream_with_default(
+            stream,
+            "example.com".to_string(),
+            Mode::Tls,
+            None,
+            None,
+            &default_config,
+        )
+        .await;
+
+        let Err(error) = result else {
+            panic!("Expected missing root certificates error");
+        };
+        assert!(!is_retryable(&error));
+        let Error::Io(e) = error else {
+            panic!("Expected an IO error");
+        };
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(e
+            .to_string()
+            .starts_with("No trusted root certificates available"));
+        assert!(e.to_string().contains(reason));
+    }
+
+    #[tokio::test]
+    async fn test_wrap_stream_without_trusted_roots_when_plain() {
+        let default_config = encryption::rustls::DefaultConfig::load(|| Ok(RootCertStore::empty()));
+        let (stream, _server) = tokio::io::duplex(1024);
+
+        let result = encryption::rustls::wrap_stream_with_default(
+            stream,
+            "example.com".to_string(),
+            Mode::Plain,
+            None,
+            None,
+            &default_config,
+        )
+        .await;
+
+        assert!(matches!(result, Ok(MaybeTlsStream::Plain(_))));
+    }
+
+    #[rstest::rstest]
+    fn test_strict_native_root_store_empty() {
+        let result = strict_native_root_store(CertificateResult::default());
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::NotFound));
+    }
+
+    fn revoke(pki: &TestPki, certified: &CertifiedKey) -> CertificateRevocationListDer<'static> {
+        let cert = webpki::EndEntityCert::try_from(certified.cert.der()).unwrap();
+        let params = CertificateRevocationListParams {
+            this_update: date_time_ymd(2024, 1, 1),
+            next_update: date_time_ymd(2099, 1, 1),
+            crl_number: SerialNumber::from(1),
+            issuing_distribution_point: None,
+            revoked_certs: vec![RevokedCertParams {
+                serial_number: SerialNumber::from_slice(cert.serial()),
+                revocation_time: date_time_ymd(2024, 1, 1),
+                reason_code: Some(RevocationReason::KeyCompromise),
+                invalidity_date: None,
+            }],
+            key_identifier_method: KeyIdMethod::Sha256,
+        };
+        let crl = params.signed_by(&pki.ca.cert, &pki.ca.key_pair).unwrap();
+        crl.der().clone()
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_revocation_rejects_revoked_certificate() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let crl = revoke(&pki, &pki.server);
+
+        let connector = Connector::rustls_with_revocation(pki.root_store(), vec![crl]).unwrap();
+        let result = connect(addr, Some(connector)).await;
+
+        assert!(is_certificate_revoked(&result.unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn test_rustls_with_revocation_accepts_unrevoked_certificate() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let addr = TestTlsServer::new(&pki).start().await;
+        let crl = revoke(&pki, &pki.client);
+
+        let connector = Connector::rustls_with_revocation(pki.root_store(), vec![crl]).unwrap();
+        let mut stream = connect(addr, Some(connector)).await.unwrap();
+
+        assert_eq!(echo(&mut stream, b"hello").await.unwrap(), b"hello");
+    }
+
+    #[rstest::rstest]
+    fn test_rustls_with_revocation_invalid_crl() {
+        install_crypto_provider();
+        let pki = TestPki::new();
+        let crl = CertificateRevocationListDer::from(vec![0u8; 16]);
+
+        let result = Connector::rustls_with_revocation(pki.root_store(), vec![crl]);
+
+        assert!(matches!(result, Err(Error::Tls(TlsError::Rustls(_)))));
     }
 }
@@ -0,0 +1,181 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Unix domain socket transport.
+//!
+//! Co-located setups often talk to a local gateway over a Unix socket rather than TCP. The
+//! socket is connected by path, then wrapped by [`tcp_tls`] like a TCP stream, so connections
+//! typically use [`Connector::Plain`]. The URL is still required for the websocket handshake
+//! request (and for SNI if wrapped with TLS), but its host is not resolved.
+
+use std::path::Path;
+
+use tokio::net::UnixStream;
+use tokio_tungstenite::{
+    client_async_with_config,
+    tungstenite::{
+        client::{uri_mode, IntoClientRequest},
+        protocol::WebSocketConfig as ProtocolConfig,
+        stream::Mode,
+        Error,
+    },
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{
+    tls::{tcp_tls, Connector},
+    websocket::client_request,
+};
+
+/// Connects to the Unix socket at the `path`, wrapping the stream with TLS using the given
+/// `connector` for the `Tls` mode.
+///
+/// # Errors
+///
+/// Returns an error if the `url` is invalid, or connecting or the TLS handshake fails.
+pub async fn connect_unix(
+    path: impl AsRef<Path>,
+    url: &str,
+    mode: Mode,
+    connector: Option<Connector>,
+) -> Result<MaybeTlsStream<UnixStream>, Error> {
+    let path = path.as_ref();
+    tracing::debug!("Connecting to Unix socket {}", path.display());
+    let request = url.into_client_request()?;
+    let stream = UnixStream::connect(path).await?;
+    tcp_tls(&request, mode, stream, connector, None, None).await
+}
+
+/// Connects to the websocket server listening on the Unix socket at the `path`, with the
+/// handshake request for the `url` and `headers`.
+///
+/// The stream is wrapped with TLS using the given `connector` for `wss` URLs, and the
+/// `protocol_config` sets the message size limits and write buffer size of the connection
+/// (the `tungstenite` defaults if `None`).
+///
+/// # Errors
+///
+/// Returns an error if the `url` or `headers` are invalid, or connecting or either
+/// handshake fails.
+pub async fn connect_unix_websocket(
+    path: impl AsRef<Path>,
+    url: &str,
+    headers: Vec<(String, String)>,
+    connector: Option<Connector>,
+    protocol_config: Option<ProtocolConfig>,
+) -> Result<WebSocketStream<MaybeTlsStream<UnixStream>>, Error> {
+    let path = path.as_ref();
+    tracing::debug!("Connecting to websocket on Unix socket {}", path.display());
+    let request = client_request(url, headers)?;
+    let mode = uri_mode(request.uri())?;
+    let stream = UnixStream::connect(path).await?;
+    let stream = tcp_tls(&request, mode, stream, connector, None, None).await?;
+    client_async_with_config(request, stream, protocol_config)
+        .await
+        .map(|resp| resp.0)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixListener,
+    };
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connect_unix_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 6];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let mut stream = connect_unix(&path, "gateway:0", Mode::Plain, Some(Connector::Plain))
+            .await
+            .unwrap();
+        stream.write_all(b"hello\n").await.unwrap();
+        let mut buf = [0u8; 6];
+        stream.read_exact(&mut buf).await.unwrap();
+
+        assert!(matches!(stream, MaybeTlsStream::Plain(_)));
+        assert_eq!(&buf, b"hello\n");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_unix_websocket_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut websocket = accept_async(stream).await.unwrap();
+            while let Some(Ok(message)) = websocket.next().await {
+                if message.is_close() {
+                    break;
+                }
+                websocket.send(message).await.unwrap();
+            }
+        });
+
+        let mut websocket = connect_unix_websocket(
+            &path,
+            "ws://localhost/feed",
+            vec![],
+            Some(Connector::Plain),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let frames = vec![
+            Message::Text("hello".to_string()),
+            Message::Binary(vec![0, 1, 2, 3]),
+        ];
+        for frame in &frames {
+            websocket.send(frame.clone()).await.unwrap();
+            assert_eq!(websocket.next().await.unwrap().unwrap(), *frame);
+        }
+
+        websocket.close(None).await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_unix_when_no_listener() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.sock");
+
+        let result = connect_unix(&path, "gateway:0", Mode::Plain, None).await;
+
+        match result {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), ErrorKind::NotFound),
+            _ => panic!("Expected connection error"),
+        }
+    }
+}
@@ -15,32 +15,361 @@
 
 //! A high-performance WebSocket client implementation.
 use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, ErrorKind},
+    pin::Pin,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
+    task::{ready, Context, Poll},
     time::Duration,
 };
 
+use bytes::Bytes;
 use futures_util::{
     stream::{SplitSink, SplitStream},
-    SinkExt, StreamExt,
+    task::AtomicWaker,
+    FutureExt, Sink, SinkExt, Stream, StreamExt,
 };
 use hyper::header::HeaderName;
-use nautilus_core::python::{to_pyruntime_err, to_pyvalue_err};
+use indexmap::IndexMap;
+use nautilus_core::{
+    nanos::UnixNanos,
+    python::{to_pyruntime_err, to_pyvalue_err},
+    time::get_atomic_clock_realtime,
+};
 use pyo3::{prelude::*, types::PyBytes};
 use rustls::crypto::{aws_lc_rs, CryptoProvider};
-use tokio::{net::TcpStream, sync::Mutex, task, time::sleep};
+use serde::de::DeserializeOwned;
+use tokio::{
+    io::BufReader,
+    net::TcpStream,
+    sync::{mpsc, oneshot, Mutex, Notify, Semaphore},
+    task,
+    time::{sleep, Instant},
+};
 use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{client::IntoClientRequest, http::HeaderValue, Error, Message},
+    client_async_with_config,
+    tungstenite::{
+        client::{uri_mode, IntoClientRequest},
+        error::UrlError,
+        handshake::client::Request,
+        http::HeaderValue,
+        protocol::{frame::coding::CloseCode, CloseFrame, WebSocketConfig as ProtocolConfig},
+        stream::Mode,
+        Error, Message,
+    },
     MaybeTlsStream, WebSocketStream,
 };
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    backoff::ExponentialBackoff,
+    dns::DnsCache,
+    happy_eyeballs::{connect_host, DEFAULT_ATTEMPT_DELAY},
+    ratelimiter::{clock::MonotonicClock, quota::Quota, RateLimiter},
+    socket::SocketOptions,
+    tls::{cancellable, is_retryable, tcp_tls, tcp_tls_limited, tcp_tls_with_fallback, Connector},
+};
+
+type ConnectionStream = WebSocketStream<MaybeTlsStream<BufReader<TcpStream>>>;
+type MessageWriter = SplitSink<ConnectionStream, Message>;
+type SharedMessageWriter = Arc<Mutex<MeteredWriter>>;
+type MessageReader = SplitStream<ConnectionStream>;
+
+/// Returns the handshake request for the `url` with the `headers`.
+pub(crate) fn client_request(url: &str, headers: Vec<(String, String)>) -> Result<Request, Error> {
+    let mut request = url.into_client_request()?;
+    let req_headers = request.headers_mut();
+
+    for (key, val) in headers {
+        let header_name: HeaderName = key
+            .parse()
+            .map_err(|_| invalid_header(format!("Invalid websocket header name `{key}`")))?;
+        // `HeaderValue` accepts opaque non-ASCII bytes, which venues would not decode
+        let header_value = Some(val.as_str())
+            .filter(|val| val.is_ascii())
+            .and_then(|val| HeaderValue::from_str(val).ok())
+            .ok_or_else(|| {
+                invalid_header(format!(
+                    "Invalid value for websocket header `{key}`: values must be visible ASCII"
+                ))
+            })?;
+        req_headers.insert(header_name, header_value);
+    }
+
+    Ok(request)
+}
+
+/// Returns an [`ErrorKind::InvalidInput`] IO error for a header which cannot be sent
+/// with the handshake request.
+fn invalid_header(message: String) -> Error {
+    Error::Io(io::Error::new(ErrorKind::InvalidInput, message))
+}
+
+/// The rate limiting key for messages sent without keys, which is subject to the default quota.
+const DEFAULT_RATE_LIMIT_KEY: &str = "default";
+
+/// How long a graceful close waits for the server to reply with its `Close` frame.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The maximum length (bytes) of the payload snippet included in a [`RecvJsonError`].
+const JSON_SNIPPET_LEN: usize = 256;
+
+type SharedCloseFrame = Arc<std::sync::Mutex<Option<CloseFrame<'static>>>>;
+type SharedSubscriptions = Arc<std::sync::Mutex<SubscriptionManager>>;
+type SharedTextWaiters = Arc<std::sync::Mutex<Vec<oneshot::Sender<String>>>>;
+type SharedMetrics = Arc<MetricsCounters>;
+
+/// A snapshot of the traffic of a [`WebSocketClient`] since it was created (see
+/// [`WebSocketClient::metrics`]).
+///
+/// Frames count every frame, including control frames (e.g. heartbeats), and bytes count
+/// their payloads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClientMetrics {
+    pub frames_sent: u64,
+    pub frames_recv: u64,
+    pub bytes_sent: u64,
+    pub bytes_recv: u64,
+    /// The number of successful reconnects.
+    pub reconnects: u64,
+    /// When the last pong was received (`None` if no pong has been received).
+    pub last_pong_at: Option<UnixNanos>,
+}
+
+/// The counters accumulating the [`ClientMetrics`] of a client across reconnects.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    frames_sent: AtomicU64,
+    frames_recv: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_recv: AtomicU64,
+    reconnects: AtomicU64,
+    /// The timestamp of the last pong, `0` if none has been received.
+    last_pong_at: AtomicU64,
+}
+
+impl MetricsCounters {
+    fn record_sent(&self, len: usize) {
+        self.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    fn record_received(&self, message: &Message) {
+        self.frames_recv.fetch_add(1, Ordering::Relaxed);
+        self.bytes_recv
+            .fetch_add(message.len() as u64, Ordering::Relaxed);
+        if message.is_pong() {
+            let now = get_atomic_clock_realtime().get_time_ns();
+            self.last_pong_at.store(now.as_u64(), Ordering::Relaxed);
+        }
+    }
+
+    fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ClientMetrics {
+        let last_pong_at = self.last_pong_at.load(Ordering::Relaxed);
+        ClientMetrics {
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+            frames_recv: self.frames_recv.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_recv: self.bytes_recv.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            last_pong_at: (last_pong_at != 0).then_some(UnixNanos::from(last_pong_at)),
+        }
+    }
+}
+
+/// The write half of a connection of a [`WebSocketClient`], recording the frames sent in
+/// the client metrics.
+pub(crate) struct MeteredWriter {
+    writer: MessageWriter,
+    metrics: SharedMetrics,
+}
+
+impl Sink<Message> for MeteredWriter {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.writer.poll_ready_unpin(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Error> {
+        let len = item.len();
+        self.writer.start_send_unpin(item)?;
+        self.metrics.record_sent(len);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.writer.poll_flush_unpin(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.writer.poll_close_unpin(cx)
+    }
+}
+
+/// Renders a frame for tracing, masking any sensitive fields (see [`redact_secrets`]).
+pub type FrameRedactor = fn(&Message) -> Cow<'_, str>;
 
-type MessageWriter = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
-type SharedMessageWriter =
-    Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>>;
-type MessageReader = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+/// Field names (compared case-insensitively, ignoring `_` and `-`) whose values are masked
+/// by [`redact_secrets`].
+const SENSITIVE_FIELDS: [&str; 7] = [
+    "key",
+    "secret",
+    "sign",
+    "password",
+    "passphrase",
+    "token",
+    "account",
+];
+
+/// The minimum length of a token of letters and digits which is masked as a likely key.
+const MIN_KEY_LEN: usize = 32;
+
+/// The mask replacing redacted values.
+const REDACTED: &str = "***";
+
+/// The default [`FrameRedactor`], rendering text frames with secrets masked.
+///
+/// The values of fields whose names resemble credentials (e.g. `apiKey`, `secret`,
+/// `signature` or `accountId`, in JSON or `name=value` form) are masked, as is any token of
+/// 32 or more characters mixing letters and digits. Other frames are rendered without their
+/// payloads.
+#[must_use]
+pub fn redact_secrets(message: &Message) -> Cow<'_, str> {
+    match message {
+        Message::Text(text) => mask_secrets(text),
+        Message::Binary(data) => Cow::Owned(format!("<binary {} bytes>", data.len())),
+        Message::Ping(data) => Cow::Owned(format!("<ping {} bytes>", data.len())),
+        Message::Pong(data) => Cow::Owned(format!("<pong {} bytes>", data.len())),
+        Message::Close(frame) => Cow::Owned(format!("<close {frame:?}>")),
+        Message::Frame(_) => Cow::Borrowed("<frame>"),
+    }
+}
+
+/// Returns the value at the JSON pointer `path` of the `text` payload, rendering strings
+/// without quotes.
+fn json_tag(text: &str, path: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(text).ok()?;
+    match value.pointer(path)? {
+        serde_json::Value::String(tag) => Some(tag.clone()),
+        tag => Some(tag.to_string()),
+    }
+}
+
+/// Traces the `message` rendered by the `frame_redactor`, if given.
+fn trace_frame(direction: &str, message: &Message, frame_redactor: Option<FrameRedactor>) {
+    if let Some(redact) = frame_redactor {
+        tracing::debug!("{direction} frame: {}", redact(message));
+    }
+}
+
+const fn is_token_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-'
+}
+
+fn is_sensitive_field(name: &str) -> bool {
+    let name: String = name
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    SENSITIVE_FIELDS.iter().any(|field| name.contains(field))
+}
+
+fn is_key_like(token: &str) -> bool {
+    token.len() >= MIN_KEY_LEN
+        && token.bytes().any(|b| b.is_ascii_alphabetic())
+        && token.bytes().any(|b| b.is_ascii_digit())
+}
+
+/// Returns the byte range of the value assigned to the field name ending at `end` (by `:`
+/// or `=`, optionally quoted), if any.
+fn field_value(bytes: &[u8], end: usize) -> Option<(usize, usize)> {
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = end;
+    if matches!(bytes.get(i), Some(b'"' | b'\'')) {
+        i += 1;
+    }
+    i = skip_whitespace(i);
+    if !matches!(bytes.get(i), Some(b':' | b'=')) {
+        return None;
+    }
+    i = skip_whitespace(i + 1);
+
+    let (start, end) = match bytes.get(i)? {
+        quote @ (b'"' | b'\'') => {
+            let start = i + 1;
+            let len = bytes[start..].iter().position(|b| b == quote)?;
+            (start, start + len)
+        }
+        b'{' | b'[' => return None,
+        _ => {
+            let len = bytes[i..]
+                .iter()
+                .position(|b| matches!(b, b',' | b'&' | b'}' | b']') || b.is_ascii_whitespace())
+                .unwrap_or(bytes.len() - i);
+            (i, i + len)
+        }
+    };
+    (start < end).then_some((start, end))
+}
+
+/// Returns the `text` with the values of sensitive fields and key-like tokens masked.
+fn mask_secrets(text: &str) -> Cow<'_, str> {
+    let bytes = text.as_bytes();
+    let mut masked = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !is_token_byte(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_token_byte(bytes[i]) {
+            i += 1;
+        }
+        let token = &text[start..i];
+
+        let range = if is_key_like(token) {
+            Some((start, i))
+        } else if is_sensitive_field(token) {
+            field_value(bytes, i)
+        } else {
+            None
+        };
+        if let Some((start, end)) = range {
+            masked.push_str(&text[copied..start]);
+            masked.push_str(REDACTED);
+            copied = end;
+            i = end;
+        }
+    }
+
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    masked.push_str(&text[copied..]);
+    Cow::Owned(masked)
+}
+
+/// Returns the topic acked by a received frame, if it is a subscription ack.
+pub type AckParser = fn(&Message) -> Option<String>;
 
 #[derive(Debug, Clone)]
 #[cfg_attr(
@@ -54,6 +383,463 @@ pub struct WebSocketConfig {
     pub heartbeat: Option<u64>,
     pub heartbeat_msg: Option<String>,
     pub ping_handler: Option<PyObject>,
+    /// The deadline (seconds) within which a frame (e.g. a pong) must be received while
+    /// the heartbeat is running, otherwise the connection is considered dead.
+    pub heartbeat_timeout: Option<u64>,
+    /// The payload of heartbeat pings (empty if `None`).
+    pub ping_payload: Option<Vec<u8>>,
+    /// The initial delay (milliseconds) before retrying a failed reconnect.
+    pub reconnect_delay_initial_ms: Option<u64>,
+    /// The maximum delay (milliseconds) between reconnect attempts.
+    pub reconnect_delay_max_ms: Option<u64>,
+    /// The maximum random jitter (milliseconds) added to each reconnect delay.
+    pub reconnect_jitter_ms: Option<u64>,
+    /// The maximum number of reconnect attempts before giving up (unlimited if `None`).
+    pub reconnect_max_attempts: Option<u32>,
+    /// The connector used to wrap the connection with TLS (the default `rustls` config if
+    /// `None`).
+    pub connector: Option<Connector>,
+    /// The options applied to the TCP socket before it is wrapped with TLS.
+    pub socket_options: SocketOptions,
+    /// The capacity of the outbound message queue (messages are written directly if `None`).
+    pub send_queue_capacity: Option<usize>,
+    /// The policy applied when sending to a full outbound message queue.
+    pub backpressure_policy: BackpressurePolicy,
+    /// The maximum number of data frames held while the connection is parked (10,000 if
+    /// `None`), see [`WebSocketClient::warm_connect`].
+    pub parked_capacity: Option<usize>,
+    /// The policy applied when a data frame is received with the parked frames at capacity.
+    pub parked_policy: BackpressurePolicy,
+    /// The maximum size (bytes) of a received message, larger messages fail the connection
+    /// (64 MiB if `None`).
+    pub max_message_size: Option<usize>,
+    /// The maximum size (bytes) of a received frame (16 MiB if `None`).
+    pub max_frame_size: Option<usize>,
+    /// The size (bytes) of the buffer the socket is read into (unbuffered if `None`, in
+    /// which case the socket is read in 4 KiB chunks).
+    pub read_buffer_size: Option<usize>,
+    /// The size (bytes) written messages are buffered up to before being written to the
+    /// connection, unless flushed first (128 KiB if `None`).
+    pub write_buffer_size: Option<usize>,
+    /// The token which cancels in-flight connection attempts (including reconnects) once
+    /// cancelled, after which the client stops reconnecting.
+    pub cancellation_token: Option<CancellationToken>,
+    /// The redactor rendering each frame sent or received by the client at debug level
+    /// (frames are not traced if `None`). Heartbeats are not traced.
+    pub frame_redactor: Option<FrameRedactor>,
+    /// The cache the server host is resolved through when connecting (resolved for every
+    /// connection if `None`).
+    pub dns_cache: Option<Arc<DnsCache>>,
+    /// The semaphore shared between clients bounding their concurrent TLS handshakes
+    /// (unbounded if `None`).
+    pub handshake_limit: Option<Arc<Semaphore>>,
+    /// The timeout (milliseconds) within which the server must ack a subscription, otherwise
+    /// the subscribe message is resent (acks are not tracked if `None`).
+    pub subscription_ack_timeout_ms: Option<u64>,
+    /// The parser recognizing subscription acks among the received frames, which are
+    /// otherwise recorded through [`WebSocketClient::ack_subscription`].
+    pub subscription_ack_parser: Option<AckParser>,
+    /// The channel the payloads of received binary frames are sent to as [`Bytes`], without
+    /// copying, instead of being passed to the `handler` (binary frames are passed to the
+    /// `handler` if `None`). The connection is not read while the channel is full.
+    pub binary_consumer: Option<mpsc::Sender<Bytes>>,
+}
+
+/// Represents errors that can occur when receiving a JSON text frame with
+/// [`WebSocketClient::recv_json`].
+#[derive(Debug, thiserror::Error)]
+pub enum RecvJsonError {
+    #[error("Connection closed before a text frame was received")]
+    Closed,
+    #[error("Error deserializing text frame{}: {source} in `{snippet}`", describe_tag(.tag))]
+    Deserialize {
+        source: serde_json::Error,
+        /// The value at the tag path of the payload, if given and present.
+        tag: Option<String>,
+        /// The start of the payload (at most [`JSON_SNIPPET_LEN`] bytes).
+        snippet: String,
+    },
+}
+
+fn describe_tag(tag: &Option<String>) -> String {
+    tag.as_ref()
+        .map_or_else(String::new, |tag| format!(" tagged `{tag}`"))
+}
+
+/// The policy applied when sending to a full outbound message queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "python",
+    pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.network")
+)]
+pub enum BackpressurePolicy {
+    /// Wait until the queue has space.
+    #[default]
+    Block,
+    /// Drop the oldest queued message to make space.
+    DropOldest,
+    /// Fail the send with an error.
+    Error,
+}
+
+/// A bounded queue of outbound messages, drained into the connection by a writer task.
+#[derive(Debug)]
+pub(crate) struct SendQueue {
+    messages: std::sync::Mutex<VecDeque<Message>>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    not_empty: Notify,
+    not_full: Notify,
+}
+
+impl SendQueue {
+    pub(crate) fn new(capacity: usize, policy: BackpressurePolicy) -> Self {
+        Self {
+            messages: std::sync::Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            policy,
+            not_empty: Notify::new(),
+            not_full: Notify::new(),
+        }
+    }
+
+    /// Queues the `message`, applying the backpressure policy if the queue is full.
+    async fn push(&self, message: Message) -> Result<(), Error> {
+        let mut message = Some(message);
+        loop {
+            {
+                let mut messages = self.messages.lock().expect("Send queue lock poisoned");
+                if messages.len() >= self.capacity {
+                    match self.policy {
+                        BackpressurePolicy::Block => (),
+                        BackpressurePolicy::DropOldest => {
+                            tracing::warn!("Send queue full, dropping oldest message");
+                            messages.pop_front();
+                        }
+                        BackpressurePolicy::Error => {
+                            return Err(Error::Io(io::Error::new(
+                                ErrorKind::WouldBlock,
+                                format!("Send queue full ({} messages)", self.capacity),
+                            )));
+                        }
+                    }
+                }
+
+                if messages.len() < self.capacity {
+                    messages.push_back(message.take().expect("Message already queued"));
+                    self.not_empty.notify_one();
+                    return Ok(());
+                }
+            }
+            self.not_full.notified().await;
+        }
+    }
+
+    /// Queues the `message` without waiting if `admit` allows it, returning it if the queue
+    /// is full (unless the policy is to drop the oldest message) or it is not admitted.
+    ///
+    /// The queue is checked for space first, so `admit` is only called for a message which
+    /// can be queued (and is not called at all for a full queue).
+    pub(crate) fn try_push(
+        &self,
+        message: Message,
+        admit: impl FnOnce(&Message) -> bool,
+    ) -> Result<(), TrySendError> {
+        let mut messages = self.messages.lock().expect("Send queue lock poisoned");
+        let full = messages.len() >= self.capacity;
+        if full && self.policy != BackpressurePolicy::DropOldest {
+            return Err(TrySendError::Full(message));
+        }
+        if !admit(&message) {
+            return Err(TrySendError::RateLimited(message));
+        }
+        if full {
+            tracing::warn!("Send queue full, dropping oldest message");
+            messages.pop_front();
+        }
+        messages.push_back(message);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Waits for and removes the next queued message.
+    async fn pop(&self) -> Message {
+        loop {
+            if let Some(message) = self
+                .messages
+                .lock()
+                .expect("Send queue lock poisoned")
+                .pop_front()
+            {
+                self.not_full.notify_one();
+                return message;
+            }
+            self.not_empty.notified().await;
+        }
+    }
+
+    /// Returns the number of queued messages.
+    fn len(&self) -> usize {
+        self.messages
+            .lock()
+            .expect("Send queue lock poisoned")
+            .len()
+    }
+}
+
+/// The error returned by [`WebSocketClient::try_send`], holding the message which was not
+/// sent.
+#[derive(Debug, thiserror::Error)]
+pub enum TrySendError {
+    /// The send queue is full.
+    #[error("Send queue full")]
+    Full(Message),
+    /// The default rate limiting quota does not allow sending yet.
+    #[error("Rate limited")]
+    RateLimited(Message),
+    /// The client has no send queue to enqueue the message to.
+    #[error("No send queue configured")]
+    NoQueue(Message),
+    /// The client is disconnected.
+    #[error("Client disconnected")]
+    Disconnected(Message),
+}
+
+impl TrySendError {
+    /// Returns the message which was not sent.
+    #[must_use]
+    pub fn into_message(self) -> Message {
+        match self {
+            Self::Full(message)
+            | Self::RateLimited(message)
+            | Self::NoQueue(message)
+            | Self::Disconnected(message) => message,
+        }
+    }
+}
+
+/// A subscribe message sent to the server, with the keys it is rate limited by.
+#[derive(Clone, Debug)]
+struct Subscription {
+    message: String,
+    keys: Option<Vec<String>>,
+    acked: bool,
+    sent_at: Instant,
+}
+
+/// Tracks the subscribe messages sent to the server by topic.
+///
+/// Servers forget subscriptions when the connection drops, so the client
+/// resends the tracked messages (in the order they were subscribed) after
+/// every successful reconnect.
+///
+/// If an ack timeout is given, subscriptions also await an ack from the server
+/// (see [`SubscriptionManager::ack`]). Subscriptions not acked within the timeout
+/// of being sent are resent, in the order they were subscribed.
+#[derive(Debug, Default)]
+pub struct SubscriptionManager {
+    subscriptions: IndexMap<String, Subscription>,
+    ack_timeout: Option<Duration>,
+    acked: Arc<Notify>,
+}
+
+impl SubscriptionManager {
+    /// Creates a new [`SubscriptionManager`] instance.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new [`SubscriptionManager`] instance tracking acks, which resends
+    /// subscriptions not acked within the `ack_timeout`.
+    #[must_use]
+    pub fn with_ack_timeout(ack_timeout: Duration) -> Self {
+        Self {
+            ack_timeout: Some(ack_timeout),
+            ..Self::default()
+        }
+    }
+
+    /// Records the subscribe `message` for the `topic`, as sent now.
+    ///
+    /// Returns `false` without recording the `message` if the `topic` is already subscribed.
+    pub fn subscribe(&mut self, topic: String, message: String, keys: Option<Vec<String>>) -> bool {
+        if self.subscriptions.contains_key(&topic) {
+            return false;
+        }
+        let subscription = Subscription {
+            message,
+            keys,
+            acked: self.ack_timeout.is_none(),
+            sent_at: Instant::now(),
+        };
+        self.subscriptions.insert(topic, subscription);
+        true
+    }
+
+    /// Stops tracking the `topic`, returning `false` if it was not subscribed.
+    pub fn unsubscribe(&mut self, topic: &str) -> bool {
+        let removed = self.subscriptions.shift_remove(topic).is_some();
+        self.notify_if_acked();
+        removed
+    }
+
+    /// Records the server ack for the subscription to the `topic`.
+    ///
+    /// Returns `false` if the `topic` is not subscribed or already acked.
+    pub fn ack(&mut self, topic: &str) -> bool {
+        match self.subscriptions.get_mut(topic) {
+            Some(subscription) if !subscription.acked => {
+                subscription.acked = true;
+                self.notify_if_acked();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[must_use]
+    pub fn is_acked(&self, topic: &str) -> bool {
+        self.subscriptions
+            .get(topic)
+            .is_some_and(|subscription| subscription.acked)
+    }
+
+    /// Returns whether every subscription has been acked.
+    #[must_use]
+    pub fn all_acked(&self) -> bool {
+        self.subscriptions
+            .values()
+            .all(|subscription| subscription.acked)
+    }
+
+    /// Returns the topics awaiting an ack in the order they were subscribed.
+    #[must_use]
+    pub fn unacked(&self) -> Vec<String> {
+        self.subscriptions
+            .iter()
+            .filter(|(_, subscription)| !subscription.acked)
+            .map(|(topic, _)| topic.clone())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn is_subscribed(&self, topic: &str) -> bool {
+        self.subscriptions.contains_key(topic)
+    }
+
+    /// Returns the subscribed topics in the order they were subscribed.
+    #[must_use]
+    pub fn topics(&self) -> Vec<String> {
+        self.subscriptions.keys().cloned().collect()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Returns the subscribe messages to replay, with their rate limiting keys, which
+    /// await a new ack once sent.
+    fn messages(&mut self) -> Vec<(String, Option<Vec<String>>)> {
+        let now = Instant::now();
+        let tracks_acks = self.ack_timeout.is_some();
+        self.subscriptions
+            .values_mut()
+            .map(|subscription| {
+                subscription.acked = !tracks_acks;
+                subscription.sent_at = now;
+                (subscription.message.clone(), subscription.keys.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the subscribe messages not acked within the ack timeout of being sent (as of
+    /// `now`), in the order they were subscribed, recording them as sent again.
+    pub(crate) fn expired_messages(&mut self, now: Instant) -> Vec<(String, Option<Vec<String>>)> {
+        let Some(ack_timeout) = self.ack_timeout else {
+            return Vec::new();
+        };
+        self.subscriptions
+            .values_mut()
+            .filter(|subscription| {
+                !subscription.acked && now.duration_since(subscription.sent_at) >= ack_timeout
+            })
+            .map(|subscription| {
+                subscription.sent_at = now;
+                (subscription.message.clone(), subscription.keys.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the notifier woken whenever every subscription has been acked.
+    fn acked_notify(&self) -> Arc<Notify> {
+        self.acked.clone()
+    }
+
+    fn notify_if_acked(&self) {
+        if self.all_acked() {
+            self.acked.notify_waiters();
+        }
+    }
+}
+
+impl WebSocketConfig {
+    const RECONNECT_DELAY_INITIAL_MS: u64 = 2_000;
+    const RECONNECT_DELAY_MAX_MS: u64 = 30_000;
+    const RECONNECT_JITTER_MS: u64 = 100;
+    const PARKED_CAPACITY: usize = 10_000;
+
+    /// Returns the `tungstenite` config applied to the connection during the handshake.
+    ///
+    /// The defaults suit most connections. For high-throughput market data feeds (e.g.
+    /// order book snapshots), a `read_buffer_size` of 256 KiB to 1 MiB reads bursts of
+    /// frames with fewer syscalls. For order entry, where messages are small and latency
+    /// matters, a small `max_message_size` (e.g. 1 MiB) limits the memory a misbehaving
+    /// server can make the client allocate, and no read buffer is needed.
+    #[must_use]
+    pub fn protocol_config(&self) -> ProtocolConfig {
+        let mut config = ProtocolConfig::default();
+        if let Some(size) = self.max_message_size {
+            config.max_message_size = Some(size);
+        }
+        if let Some(size) = self.max_frame_size {
+            config.max_frame_size = Some(size);
+        }
+        if let Some(size) = self.write_buffer_size {
+            config.write_buffer_size = size;
+        }
+        config
+    }
+
+    /// Returns the maximum number of data frames held while the connection is parked.
+    #[must_use]
+    pub fn parked_capacity(&self) -> usize {
+        self.parked_capacity.unwrap_or(Self::PARKED_CAPACITY)
+    }
+
+    /// Returns the backoff applied between failed reconnect attempts.
+    #[must_use]
+    pub fn reconnect_backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff::new(
+            Duration::from_millis(
+                self.reconnect_delay_initial_ms
+                    .unwrap_or(Self::RECONNECT_DELAY_INITIAL_MS),
+            ),
+            Duration::from_millis(
+                self.reconnect_delay_max_ms
+                    .unwrap_or(Self::RECONNECT_DELAY_MAX_MS),
+            ),
+            self.reconnect_jitter_ms
+                .unwrap_or(Self::RECONNECT_JITTER_MS),
+            self.reconnect_max_attempts,
+        )
+    }
 }
 
 /// `WebSocketClient` connects to a websocket server to read and send messages.
@@ -70,26 +856,44 @@ pub struct WebSocketConfig {
 ///
 /// The client also maintains a heartbeat if given a duration in seconds.
 /// It's preferable to set the duration slightly lower - heartbeat more
-/// frequently - than the required amount.
-struct WebSocketClientInner {
+/// frequently - than the required amount. If a heartbeat timeout is also
+/// given, a connection which receives no frames (including pongs) within
+/// the timeout is treated as disconnected, so the timeout should be longer
+/// than the heartbeat interval.
+///
+/// When the connection drops, the client reconnects using the stored `connector`.
+/// Failed reconnect attempts are retried with an exponential backoff until
+/// the configured maximum number of attempts is reached. Reconnecting stops
+/// immediately on a permanent error (see [`is_retryable`]), such as a rejected
+/// server certificate. Once reconnected, the client resends the subscribe
+/// messages sent through [`WebSocketClient::subscribe`] before calling the
+/// `post_reconnection` handler. If a subscription ack timeout is configured,
+/// subscriptions not acked by the server within the timeout are resent in the
+/// order they were subscribed.
+pub(crate) struct WebSocketClientInner {
     config: WebSocketConfig,
     read_task: task::JoinHandle<()>,
     heartbeat_task: Option<task::JoinHandle<()>>,
     writer: SharedMessageWriter,
+    subscriptions: SharedSubscriptions,
+    drain: CancellationToken,
+    activation: CancellationToken,
+    text_waiters: SharedTextWaiters,
+    metrics: SharedMetrics,
 }
 
 impl WebSocketClientInner {
     /// Create an inner websocket client.
-    pub async fn connect_url(config: WebSocketConfig) -> Result<Self, Error> {
-        if CryptoProvider::get_default().is_none() {
-            tracing::debug!("Installing `aws_lc_rs` cryptographic provider");
-            // An error can occur on install if there is a race condition with another component
-            match aws_lc_rs::default_provider().install_default() {
-                Ok(_) => tracing::debug!("Cryptographic provider installed successfully"),
-                Err(e) => tracing::debug!("Error installing cryptographic provider: {e:?}"),
-            }
-        }
-
+    ///
+    /// Received data frames are held until the `activation` token is cancelled (see
+    /// [`Self::spawn_read_task`]), and text frames are also sent to the `text_waiters`. The
+    /// traffic of the connection is recorded in the `metrics`.
+    pub async fn connect_url(
+        config: WebSocketConfig,
+        activation: CancellationToken,
+        text_waiters: SharedTextWaiters,
+        metrics: SharedMetrics,
+    ) -> Result<Self, Error> {
         let WebSocketConfig {
             url,
             handler,
@@ -97,49 +901,160 @@ impl WebSocketClientInner {
             headers,
             heartbeat_msg,
             ping_handler,
+            heartbeat_timeout,
+            ping_payload,
+            connector,
+            ..
         } = &config;
-        let (writer, reader) = Self::connect_with_server(url, headers.clone()).await?;
-        let writer = Arc::new(Mutex::new(writer));
+        let (writer, reader) = cancellable(
+            Self::connect_with_server(
+                url,
+                headers.clone(),
+                connector.clone(),
+                &config.socket_options,
+                Some(config.protocol_config()),
+                config.read_buffer_size,
+                config.dns_cache.as_deref(),
+                config.handshake_limit.as_deref(),
+            ),
+            config.cancellation_token.as_ref(),
+        )
+        .await?;
+        let writer = Arc::new(Mutex::new(MeteredWriter {
+            writer,
+            metrics: metrics.clone(),
+        }));
+        let subscriptions = Arc::new(std::sync::Mutex::new(
+            match config.subscription_ack_timeout_ms {
+                Some(timeout_ms) => {
+                    SubscriptionManager::with_ack_timeout(Duration::from_millis(timeout_ms))
+                }
+                None => SubscriptionManager::new(),
+            },
+        ));
 
         // Keep receiving messages from socket and pass them as arguments to handler
-        let read_task = Self::spawn_read_task(reader, handler.clone(), ping_handler.clone());
-        let heartbeat_task =
-            Self::spawn_heartbeat_task(*heartbeat, heartbeat_msg.clone(), writer.clone());
+        let drain = CancellationToken::new();
+        let read_task = Self::spawn_read_task(
+            reader,
+            handler.clone(),
+            config.binary_consumer.clone(),
+            ping_handler.clone(),
+            heartbeat.and(*heartbeat_timeout),
+            config.frame_redactor,
+            config
+                .subscription_ack_parser
+                .map(|parser| (parser, subscriptions.clone())),
+            drain.clone(),
+            activation.clone(),
+            config.parked_capacity(),
+            config.parked_policy,
+            text_waiters.clone(),
+            metrics.clone(),
+        );
+        let heartbeat_task = Self::spawn_heartbeat_task(
+            *heartbeat,
+            heartbeat_msg.clone(),
+            ping_payload.clone(),
+            writer.clone(),
+        );
 
         Ok(Self {
             config,
             read_task,
             heartbeat_task,
             writer,
+            subscriptions,
+            drain,
+            activation,
+            text_waiters,
+            metrics,
         })
     }
 
     /// Connects with the server creating a tokio-tungstenite websocket stream.
+    ///
+    /// The TCP stream is wrapped with TLS by [`tcp_tls`] using the given `connector`
+    /// for `wss` URLs, once a permit is acquired from the `handshake_limit` if given (see
+    /// [`tcp_tls_limited`]), reconnecting once if the handshake fails with an error the
+    /// `connector` falls back on (see [`tcp_tls_with_fallback`]).
+    ///
+    /// The `protocol_config` sets the message size limits and write buffer size of the
+    /// connection (the `tungstenite` defaults if `None`), the `socket_options` are applied
+    /// to the TCP stream, and the socket is read through a buffer of `read_buffer_size` bytes
+    /// if given. The host is resolved through the `dns_cache` if given.
+    ///
+    /// The `aws_lc_rs` cryptographic provider is installed if no provider is installed yet.
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     pub async fn connect_with_server(
         url: &str,
         headers: Vec<(String, String)>,
+        connector: Option<Connector>,
+        socket_options: &SocketOptions,
+        protocol_config: Option<ProtocolConfig>,
+        read_buffer_size: Option<usize>,
+        dns_cache: Option<&DnsCache>,
+        handshake_limit: Option<&Semaphore>,
     ) -> Result<(MessageWriter, MessageReader), Error> {
-        let mut request = url.into_client_request()?;
-        let req_headers = request.headers_mut();
-
-        // Hacky solution to overcome the new `http` trait bounds
-        for (key, val) in headers {
-            let header_value = HeaderValue::from_str(&val).unwrap();
-            use http::header::HeaderName;
-            let header_name: HeaderName = key.parse().unwrap();
-            let header_name_string = header_name.to_string();
-            let header_name_str: &'static str = Box::leak(header_name_string.into_boxed_str());
-            req_headers.insert(header_name_str, header_value);
+        if CryptoProvider::get_default().is_none() {
+            tracing::debug!("Installing `aws_lc_rs` cryptographic provider");
+            // An error can occur on install if there is a race condition with another component
+            match aws_lc_rs::default_provider().install_default() {
+                Ok(_) => tracing::debug!("Cryptographic provider installed successfully"),
+                Err(e) => tracing::debug!("Error installing cryptographic provider: {e:?}"),
+            }
         }
 
-        connect_async(request).await.map(|resp| resp.0.split())
+        let request = client_request(url, headers)?;
+        let mode = uri_mode(request.uri())?;
+        let host = request
+            .uri()
+            .host()
+            .ok_or(Error::Url(UrlError::NoHostName))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = request.uri().port_u16().unwrap_or(match mode {
+            Mode::Plain => 80,
+            Mode::Tls => 443,
+        });
+
+        let connect = || async {
+            let stream = match dns_cache {
+                Some(cache) => cache.connect(&host, port, DEFAULT_ATTEMPT_DELAY).await?,
+                None => connect_host((host.as_str(), port), DEFAULT_ATTEMPT_DELAY).await?,
+            };
+            socket_options.apply(&stream)?;
+            // A zero capacity buffer passes reads straight through to the socket
+            Ok(BufReader::with_capacity(
+                read_buffer_size.unwrap_or(0),
+                stream,
+            ))
+        };
+        let stream = tcp_tls_with_fallback(
+            &request,
+            mode,
+            connect,
+            connector,
+            None,
+            None,
+            handshake_limit,
+        )
+        .await?;
+        client_async_with_config(request, stream, protocol_config)
+            .await
+            .map(|resp| resp.0.split())
     }
 
     /// Optionally spawn a hearbeat task to periodically ping the server.
+    ///
+    /// The heartbeat sends the `message` as text if given, otherwise a ping
+    /// with the `ping_payload`.
     pub fn spawn_heartbeat_task(
         heartbeat: Option<u64>,
         message: Option<String>,
+        ping_payload: Option<Vec<u8>>,
         writer: SharedMessageWriter,
     ) -> Option<task::JoinHandle<()>> {
         tracing::debug!("Started task 'heartbeat'");
@@ -151,7 +1066,11 @@ impl WebSocketClientInner {
                     let mut guard = writer.lock().await;
                     let guard_send_response = match message.clone() {
                         Some(msg) => guard.send(Message::Text(msg)).await,
-                        None => guard.send(Message::Ping(vec![])).await,
+                        None => {
+                            guard
+                                .send(Message::Ping(ping_payload.clone().unwrap_or_default()))
+                                .await
+                        }
                     };
                     match guard_send_response {
                         Ok(()) => tracing::trace!("Sent ping"),
@@ -163,18 +1082,137 @@ impl WebSocketClientInner {
     }
 
     /// Keep receiving messages from socket and pass them as arguments to handler.
+    ///
+    /// If a `heartbeat_timeout` (seconds) is given the task terminates when no
+    /// message is received within the timeout. Received frames are traced through the
+    /// `frame_redactor` if given. If `subscription_acks` are given, frames recognized as
+    /// subscription acks by the parser are recorded with the subscriptions (and still passed
+    /// to the handler).
+    ///
+    /// Once the `drain` token is cancelled the task stops waiting for new frames, and
+    /// terminates after passing the frames already received to the handler.
+    ///
+    /// Until the `activation` token is cancelled the connection is parked: control frames
+    /// are still handled (so pings are answered), but data frames are held and passed to
+    /// the handler in order once activated. At most `parked_capacity` data frames are held,
+    /// beyond which the `parked_policy` applies: `Block` stops reading the connection until
+    /// activated (so pings are no longer answered), `DropOldest` drops the oldest held frame,
+    /// and `Error` terminates the task.
+    ///
+    /// If a `binary_consumer` is given the payloads of binary frames are sent to it rather
+    /// than passed to the handler, and the task terminates once the consumer is dropped.
+    ///
+    /// Each text frame passed to the handler is also sent to the `text_waiters` awaiting the
+    /// next text frame (see [`WebSocketClient::recv_json`]), which are dropped once the task
+    /// terminates. Every frame received is recorded in the `metrics`.
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_read_task(
         mut reader: MessageReader,
         handler: PyObject,
+        binary_consumer: Option<mpsc::Sender<Bytes>>,
         ping_handler: Option<PyObject>,
+        heartbeat_timeout: Option<u64>,
+        frame_redactor: Option<FrameRedactor>,
+        subscription_acks: Option<(AckParser, SharedSubscriptions)>,
+        drain: CancellationToken,
+        activation: CancellationToken,
+        parked_capacity: usize,
+        parked_policy: BackpressurePolicy,
+        text_waiters: SharedTextWaiters,
+        metrics: SharedMetrics,
     ) -> task::JoinHandle<()> {
         tracing::debug!("Started task 'read'");
+        let heartbeat_timeout = heartbeat_timeout.map(Duration::from_secs);
         task::spawn(async move {
+            let mut parked = VecDeque::new();
             loop {
-                match reader.next().await {
+                let unparking = activation.is_cancelled() && !parked.is_empty();
+                let blocked = parked_policy == BackpressurePolicy::Block
+                    && !activation.is_cancelled()
+                    && parked.len() >= parked_capacity;
+                let next = if unparking {
+                    parked.pop_front().map(Ok)
+                } else if drain.is_cancelled() {
+                    if blocked {
+                        tracing::debug!("Drained received messages - terminating");
+                        break;
+                    }
+                    // Only deliver the frames already received, without waiting for more (the
+                    // task budget must not make received frames appear pending)
+                    match task::unconstrained(reader.next()).now_or_never() {
+                        Some(next) => next,
+                        None => {
+                            tracing::debug!("Drained received messages - terminating");
+                            break;
+                        }
+                    }
+                } else {
+                    let read = Self::read_next(&mut reader, heartbeat_timeout);
+                    tokio::select! {
+                        next = read, if !blocked => match next {
+                            Ok(next) => next,
+                            Err(timeout) => {
+                                tracing::error!(
+                                    "No message received within {timeout:?} - terminating"
+                                );
+                                break;
+                            }
+                        },
+                        () = drain.cancelled() => continue,
+                        () = activation.cancelled(), if !parked.is_empty() => continue,
+                    }
+                };
+                if let (false, Some(Ok(message))) = (unparking, &next) {
+                    // Parked frames were recorded when received
+                    metrics.record_received(message);
+                }
+                if !activation.is_cancelled() {
+                    if let Some(Ok(message @ (Message::Binary(_) | Message::Text(_)))) = next {
+                        if parked.len() >= parked_capacity {
+                            match parked_policy {
+                                // Not read while blocked
+                                BackpressurePolicy::Block => (),
+                                BackpressurePolicy::DropOldest => {
+                                    tracing::warn!("Parked frames full, dropping oldest frame");
+                                    parked.pop_front();
+                                }
+                                BackpressurePolicy::Error => {
+                                    tracing::error!(
+                                        "Parked frames full ({parked_capacity} frames) \
+                                        - terminating"
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                        parked.push_back(message);
+                        continue;
+                    }
+                }
+                if let Some(Ok(message)) = &next {
+                    trace_frame("Received", message, frame_redactor);
+                    if let Some((parse_ack, subscriptions)) = &subscription_acks {
+                        if let Some(topic) = parse_ack(message) {
+                            if subscriptions
+                                .lock()
+                                .expect("Subscriptions lock poisoned")
+                                .ack(&topic)
+                            {
+                                tracing::debug!("Subscription to {topic} acked");
+                            }
+                        }
+                    }
+                }
+
+                match next {
                     Some(Ok(Message::Binary(data))) => {
                         tracing::trace!("Received message <binary>");
-                        if let Err(e) =
+                        if let Some(consumer) = &binary_consumer {
+                            if consumer.send(Bytes::from(data)).await.is_err() {
+                                tracing::error!("Binary consumer dropped - terminating");
+                                break;
+                            }
+                        } else if let Err(e) =
                             Python::with_gil(|py| handler.call1(py, (PyBytes::new(py, &data),)))
                         {
                             tracing::error!("Error calling handler: {e}");
@@ -184,6 +1222,13 @@ impl WebSocketClientInner {
                     }
                     Some(Ok(Message::Text(data))) => {
                         tracing::trace!("Received message: {data}");
+                        let waiters = std::mem::take(
+                            &mut *text_waiters.lock().expect("Text waiters lock poisoned"),
+                        );
+                        for waiter in waiters {
+                            // The waiter may have stopped waiting
+                            let _ = waiter.send(data.clone());
+                        }
                         if let Err(e) = Python::with_gil(|py| {
                             handler.call1(py, (PyBytes::new(py, data.as_bytes()),))
                         }) {
@@ -225,18 +1270,55 @@ impl WebSocketClientInner {
                     }
                 }
             }
+            text_waiters
+                .lock()
+                .expect("Text waiters lock poisoned")
+                .clear();
         })
     }
 
+    /// Reads the next frame, failing with the `timeout` if given and no frame is received
+    /// within it.
+    async fn read_next(
+        reader: &mut MessageReader,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Result<Message, Error>>, Duration> {
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, reader.next())
+                .await
+                .map_err(|_| timeout),
+            None => Ok(reader.next().await),
+        }
+    }
+
+    /// Stops the read task reading new frames, and waits up to [`CLOSE_TIMEOUT`] for it to
+    /// pass the frames already received to the handler.
+    async fn drain(&mut self) {
+        self.drain.cancel();
+        match tokio::time::timeout(CLOSE_TIMEOUT, &mut self.read_task).await {
+            Ok(_) => tracing::debug!("Drain completed"),
+            Err(_) => tracing::warn!("Received messages not drained within {CLOSE_TIMEOUT:?}"),
+        }
+    }
+
     /// Shutdown read and hearbeat task and the connection.
     ///
     /// The client must be explicitly shutdown before dropping otherwise
     /// the connection might still be alive for some time before terminating.
     /// Closing the connection is an async call which cannot be done by the
     /// drop method so it must be done explicitly.
-    pub async fn shutdown(&mut self) {
+    ///
+    /// If a `close_frame` is given it is sent to the server first, and the read
+    /// task is given up to [`CLOSE_TIMEOUT`] to receive the server's `Close` reply.
+    pub async fn shutdown(&mut self, close_frame: Option<CloseFrame<'static>>) {
         tracing::debug!("Closing connection");
 
+        if let Some(frame) = close_frame {
+            if !self.read_task.is_finished() {
+                self.close_handshake(frame).await;
+            }
+        }
+
         if !self.read_task.is_finished() {
             self.read_task.abort();
             tracing::debug!("Aborted message read task");
@@ -252,8 +1334,30 @@ impl WebSocketClientInner {
 
         tracing::debug!("Closing writer");
         let mut write_half = self.writer.lock().await;
-        write_half.close().await.unwrap();
-        tracing::debug!("Closed connection");
+        match write_half.close().await {
+            Ok(()) => tracing::debug!("Closed connection"),
+            Err(e) => tracing::debug!("Error closing writer: {e}"),
+        }
+    }
+
+    /// Sends the `frame` and waits for the read task to receive the server's `Close` reply.
+    async fn close_handshake(&mut self, frame: CloseFrame<'static>) {
+        tracing::debug!("Sending close frame: {frame}");
+        if let Err(e) = self
+            .writer
+            .lock()
+            .await
+            .send(Message::Close(Some(frame)))
+            .await
+        {
+            tracing::error!("Error sending close frame: {e}");
+            return;
+        }
+
+        match tokio::time::timeout(CLOSE_TIMEOUT, &mut self.read_task).await {
+            Ok(_) => tracing::debug!("Close handshake completed"),
+            Err(_) => tracing::warn!("No close reply received within {CLOSE_TIMEOUT:?}"),
+        }
     }
 
     /// Reconnect with server.
@@ -261,24 +1365,49 @@ impl WebSocketClientInner {
     /// Make a new connection with server. Use the new read and write halves
     /// to update self writer and read and heartbeat tasks.
     pub async fn reconnect(&mut self) -> Result<(), Error> {
-        self.shutdown().await;
+        self.shutdown(None).await;
 
-        let (new_writer, reader) =
-            Self::connect_with_server(&self.config.url, self.config.headers.clone()).await?;
-        let mut guard = self.writer.lock().await;
-        *guard = new_writer;
-        drop(guard);
+        let (new_writer, reader) = cancellable(
+            Self::connect_with_server(
+                &self.config.url,
+                self.config.headers.clone(),
+                self.config.connector.clone(),
+                &self.config.socket_options,
+                Some(self.config.protocol_config()),
+                self.config.read_buffer_size,
+                self.config.dns_cache.as_deref(),
+                self.config.handshake_limit.as_deref(),
+            ),
+            self.config.cancellation_token.as_ref(),
+        )
+        .await?;
+        self.writer.lock().await.writer = new_writer;
 
+        self.drain = CancellationToken::new();
         self.read_task = Self::spawn_read_task(
             reader,
             self.config.handler.clone(),
+            self.config.binary_consumer.clone(),
             self.config.ping_handler.clone(),
+            self.config.heartbeat.and(self.config.heartbeat_timeout),
+            self.config.frame_redactor,
+            self.config
+                .subscription_ack_parser
+                .map(|parser| (parser, self.subscriptions.clone())),
+            self.drain.clone(),
+            self.activation.clone(),
+            self.config.parked_capacity(),
+            self.config.parked_policy,
+            self.text_waiters.clone(),
+            self.metrics.clone(),
         );
         self.heartbeat_task = Self::spawn_heartbeat_task(
             self.config.heartbeat,
             self.config.heartbeat_msg.clone(),
+            self.config.ping_payload.clone(),
             self.writer.clone(),
         );
+        self.metrics.record_reconnect();
 
         Ok(())
     }
@@ -297,6 +1426,17 @@ impl WebSocketClientInner {
     }
 }
 
+impl Drop for WebSocketClient {
+    fn drop(&mut self) {
+        // Cancel send task
+        if let Some(ref handle) = self.send_task.take() {
+            if !handle.is_finished() {
+                handle.abort();
+            }
+        }
+    }
+}
+
 impl Drop for WebSocketClientInner {
     fn drop(&mut self) {
         if !self.read_task.is_finished() {
@@ -320,6 +1460,16 @@ pub struct WebSocketClient {
     pub(crate) writer: SharedMessageWriter,
     pub(crate) controller_task: task::JoinHandle<()>,
     pub(crate) disconnect_mode: Arc<AtomicBool>,
+    pub(crate) drain_on_close: Arc<AtomicBool>,
+    pub(crate) close_frame: SharedCloseFrame,
+    pub(crate) rate_limiter: Arc<RateLimiter<String, MonotonicClock>>,
+    pub(crate) subscriptions: SharedSubscriptions,
+    pub(crate) send_queue: Option<Arc<SendQueue>>,
+    pub(crate) send_task: Option<task::JoinHandle<()>>,
+    pub(crate) frame_redactor: Option<FrameRedactor>,
+    pub(crate) activation: CancellationToken,
+    pub(crate) text_waiters: SharedTextWaiters,
+    pub(crate) metrics: SharedMetrics,
 }
 
 impl WebSocketClient {
@@ -327,20 +1477,106 @@ impl WebSocketClient {
     ///
     /// Creates an inner client and controller task to reconnect or disconnect
     /// the client. Also assumes ownership of writer from inner client.
+    ///
+    /// Sent messages are paced by the `keyed_quotas` for the keys given when
+    /// sending, and by the `default_quota` for any other key (including
+    /// messages sent without keys).
+    ///
+    /// If a send queue capacity is configured, sent messages are queued and
+    /// written by a separate task, and the backpressure policy applies once
+    /// the queue is full.
     pub async fn connect(
         config: WebSocketConfig,
         post_connection: Option<PyObject>,
         post_reconnection: Option<PyObject>,
         post_disconnection: Option<PyObject>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+    ) -> Result<Self, Error> {
+        let activation = CancellationToken::new();
+        activation.cancel();
+        Self::connect_with_activation(
+            config,
+            post_connection,
+            post_reconnection,
+            post_disconnection,
+            keyed_quotas,
+            default_quota,
+            activation,
+        )
+        .await
+    }
+
+    /// Creates a websocket client with a warm connection, which is parked until
+    /// [`Self::activate`] is called.
+    ///
+    /// The TCP, TLS and websocket handshakes complete (and heartbeats start) as for
+    /// [`Self::connect`], so the first messages of the session are not delayed by a cold
+    /// connection. While parked, data frames received are held rather than passed to the
+    /// handler, and are delivered in order on activation. At most the configured
+    /// `parked_capacity` frames are held, beyond which the `parked_policy` applies (see
+    /// [`WebSocketClientInner::spawn_read_task`]). Messages can still be sent, for
+    /// example to authenticate ahead of the session. A reconnection while parked stays parked.
+    pub async fn warm_connect(
+        config: WebSocketConfig,
+        post_connection: Option<PyObject>,
+        post_reconnection: Option<PyObject>,
+        post_disconnection: Option<PyObject>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+    ) -> Result<Self, Error> {
+        Self::connect_with_activation(
+            config,
+            post_connection,
+            post_reconnection,
+            post_disconnection,
+            keyed_quotas,
+            default_quota,
+            CancellationToken::new(),
+        )
+        .await
+    }
+
+    async fn connect_with_activation(
+        config: WebSocketConfig,
+        post_connection: Option<PyObject>,
+        post_reconnection: Option<PyObject>,
+        post_disconnection: Option<PyObject>,
+        keyed_quotas: Vec<(String, Quota)>,
+        default_quota: Option<Quota>,
+        activation: CancellationToken,
     ) -> Result<Self, Error> {
         tracing::debug!("Connecting");
-        let inner = WebSocketClientInner::connect_url(config).await?;
+        let send_queue = config
+            .send_queue_capacity
+            .map(|capacity| Arc::new(SendQueue::new(capacity, config.backpressure_policy)));
+        let frame_redactor = config.frame_redactor;
+        let text_waiters = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let metrics = SharedMetrics::default();
+        let inner = WebSocketClientInner::connect_url(
+            config,
+            activation.clone(),
+            text_waiters.clone(),
+            metrics.clone(),
+        )
+        .await?;
         let writer = inner.writer.clone();
+        let send_task = send_queue
+            .clone()
+            .map(|send_queue| Self::spawn_send_task(send_queue, writer.clone()));
         let disconnect_mode = Arc::new(AtomicBool::new(false));
+        let drain_on_close = Arc::new(AtomicBool::new(false));
+        let close_frame = Arc::new(std::sync::Mutex::new(None));
+        let rate_limiter = Arc::new(RateLimiter::new_with_quota(default_quota, keyed_quotas));
+        let subscriptions = inner.subscriptions.clone();
 
         let controller_task = Self::spawn_controller_task(
             inner,
             disconnect_mode.clone(),
+            drain_on_close.clone(),
+            close_frame.clone(),
+            rate_limiter.clone(),
+            subscriptions.clone(),
             post_reconnection,
             post_disconnection,
         );
@@ -356,9 +1592,102 @@ impl WebSocketClient {
             writer,
             controller_task,
             disconnect_mode,
+            drain_on_close,
+            close_frame,
+            rate_limiter,
+            subscriptions,
+            send_queue,
+            send_task,
+            frame_redactor,
+            activation,
+            text_waiters,
+            metrics,
         })
     }
 
+    /// Returns a snapshot of the traffic of the client since it was created, accumulated
+    /// across reconnects.
+    #[must_use]
+    pub fn metrics(&self) -> ClientMetrics {
+        self.metrics.snapshot()
+    }
+
+    /// Receives the next text frame and deserializes it from JSON as a `T`.
+    ///
+    /// Only frames received after the call are considered, and the frame is still passed
+    /// to the handler. If a `tag_path` (a JSON pointer such as `/e`) is given, for a `T`
+    /// which is a tagged enum, the error for a payload which fails to deserialize includes
+    /// the tag value at that path, since `serde` does not report which variant failed.
+    ///
+    /// The payload snippet included in the error is rendered through the frame redactor
+    /// if configured, so any secrets are masked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection is closed before a text frame is received, or the
+    /// frame is not a valid JSON `T`.
+    pub async fn recv_json<T: DeserializeOwned>(
+        &self,
+        tag_path: Option<&str>,
+    ) -> Result<T, RecvJsonError> {
+        let (sender, receiver) = oneshot::channel();
+        self.text_waiters
+            .lock()
+            .expect("Text waiters lock poisoned")
+            .push(sender);
+        let text = receiver.await.map_err(|_| RecvJsonError::Closed)?;
+
+        serde_json::from_str(&text).map_err(|source| RecvJsonError::Deserialize {
+            source,
+            tag: tag_path.and_then(|path| json_tag(&text, path)),
+            snippet: self.json_snippet(text),
+        })
+    }
+
+    /// Returns the start of the `text` payload for an error, masked by the frame redactor
+    /// if configured.
+    fn json_snippet(&self, text: String) -> String {
+        let message = Message::Text(text);
+        let rendered = match self.frame_redactor {
+            Some(redact) => redact(&message).into_owned(),
+            None => message.into_text().unwrap_or_default(),
+        };
+        if rendered.len() <= JSON_SNIPPET_LEN {
+            return rendered;
+        }
+        let end = (0..=JSON_SNIPPET_LEN)
+            .rev()
+            .find(|i| rendered.is_char_boundary(*i))
+            .unwrap_or(0);
+        format!("{}...", &rendered[..end])
+    }
+
+    /// Activates a connection parked by [`Self::warm_connect`], passing the data frames
+    /// held while parked (then all subsequent frames) to the handler.
+    ///
+    /// Activating an active connection has no effect.
+    pub fn activate(&self) {
+        if !self.activation.is_cancelled() {
+            tracing::debug!("Activating connection");
+            self.activation.cancel();
+        }
+    }
+
+    /// Returns whether received data frames are passed to the handler, i.e. the connection
+    /// is not parked (see [`Self::warm_connect`]).
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.activation.is_cancelled()
+    }
+
+    /// Returns the number of messages waiting in the send queue.
+    #[must_use]
+    pub fn send_queue_depth(&self) -> usize {
+        self.send_queue
+            .as_ref()
+            .map_or(0, |send_queue| send_queue.len())
+    }
+
     #[must_use]
     pub fn is_disconnected(&self) -> bool {
         self.controller_task.is_finished()
@@ -388,10 +1717,280 @@ impl WebSocketClient {
         }
     }
 
-    pub async fn send_bytes(&self, data: Vec<u8>) -> Result<(), Error> {
+    /// Close the connection with the `code` and `reason`.
+    ///
+    /// Unlike [`Self::disconnect`], the client completes the websocket close
+    /// handshake: it sends a `Close` frame and waits (with a timeout) for the
+    /// server's `Close` reply before shutting down the connection.
+    pub async fn close(&self, code: u16, reason: String) {
+        Self::request_close(&self.close_frame, code, reason);
+        self.disconnect().await;
+    }
+
+    /// Close the connection once the messages already received are delivered.
+    ///
+    /// Unlike [`Self::disconnect`], the client first stops reading new frames and passes
+    /// the frames already received (but not yet delivered) to the handler, waiting up to
+    /// [`CLOSE_TIMEOUT`] for them to be delivered. This gives at-least-once delivery of the
+    /// messages already on the wire.
+    pub async fn drain_and_close(&self) {
+        self.drain_on_close.store(true, Ordering::SeqCst);
+        self.disconnect().await;
+    }
+
+    /// Store the `Close` frame sent by the controller task on disconnect.
+    pub(crate) fn request_close(close_frame: &SharedCloseFrame, code: u16, reason: String) {
+        let frame = CloseFrame {
+            code: CloseCode::from(code),
+            reason: reason.into(),
+        };
+        *close_frame.lock().expect("Close frame lock poisoned") = Some(frame);
+    }
+
+    pub async fn send_bytes(&self, data: Vec<u8>, keys: Option<Vec<String>>) -> Result<(), Error> {
+        Self::await_rate_limit(&self.rate_limiter, keys).await;
         tracing::trace!("Sending bytes: {data:?}");
-        let mut guard = self.writer.lock().await;
-        guard.send(Message::Binary(data)).await
+        Self::send_message(
+            &self.writer,
+            self.send_queue.as_deref(),
+            Message::Binary(data),
+            self.frame_redactor,
+        )
+        .await
+    }
+
+    /// Queues the `message` for sending without waiting, for paths which cannot await a
+    /// full send queue (e.g. order entry).
+    ///
+    /// The message is paced by the default rate limiting quota (which a message rejected
+    /// by a full queue does not spend), and requires a send queue to be configured. A full
+    /// queue applies the `DropOldest` backpressure policy, while the other policies return
+    /// [`TrySendError::Full`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error holding the `message` if it cannot be queued immediately.
+    pub fn try_send(&self, message: Message) -> Result<(), TrySendError> {
+        if self.is_disconnected() {
+            return Err(TrySendError::Disconnected(message));
+        }
+        let Some(send_queue) = self.send_queue.as_deref() else {
+            return Err(TrySendError::NoQueue(message));
+        };
+        send_queue.try_push(message, |message| {
+            let admitted = self
+                .rate_limiter
+                .check_key(&DEFAULT_RATE_LIMIT_KEY.to_string())
+                .is_ok();
+            if admitted {
+                trace_frame("Sent", message, self.frame_redactor);
+            }
+            admitted
+        })
+    }
+
+    /// Sends the subscribe `message` for the `topic` and tracks it, so that it is resent
+    /// after a reconnect.
+    ///
+    /// Nothing is sent if the `topic` is already subscribed. The `message` stays tracked
+    /// if sending fails, so it is still sent once the client reconnects.
+    pub async fn subscribe(
+        &self,
+        topic: String,
+        message: String,
+        keys: Option<Vec<String>>,
+    ) -> Result<(), Error> {
+        if !self
+            .subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .subscribe(topic.clone(), message.clone(), keys.clone())
+        {
+            tracing::debug!("Already subscribed to {topic}");
+            return Ok(());
+        }
+        Self::await_rate_limit(&self.rate_limiter, keys).await;
+        tracing::trace!("Subscribing to {topic}: {message}");
+        Self::send_message(
+            &self.writer,
+            self.send_queue.as_deref(),
+            Message::Text(message),
+            self.frame_redactor,
+        )
+        .await
+    }
+
+    /// Sends the unsubscribe `message` for the `topic` and stops tracking it.
+    ///
+    /// Nothing is sent if the `topic` is not subscribed.
+    pub async fn unsubscribe(
+        &self,
+        topic: &str,
+        message: String,
+        keys: Option<Vec<String>>,
+    ) -> Result<(), Error> {
+        if !self
+            .subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .unsubscribe(topic)
+        {
+            tracing::debug!("Not subscribed to {topic}");
+            return Ok(());
+        }
+        Self::await_rate_limit(&self.rate_limiter, keys).await;
+        tracing::trace!("Unsubscribing from {topic}: {message}");
+        Self::send_message(
+            &self.writer,
+            self.send_queue.as_deref(),
+            Message::Text(message),
+            self.frame_redactor,
+        )
+        .await
+    }
+
+    /// Returns the subscribed topics in the order they were subscribed.
+    #[must_use]
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .topics()
+    }
+
+    /// Records the server ack for the subscription to the `topic`, for acks which are not
+    /// recognized by the configured ack parser.
+    ///
+    /// Returns `false` if the `topic` is not subscribed or already acked.
+    pub fn ack_subscription(&self, topic: &str) -> bool {
+        self.subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .ack(topic)
+    }
+
+    /// Waits until every tracked subscription has been acked by the server, including the
+    /// subscriptions replayed after a reconnect.
+    ///
+    /// Resolves immediately if acks are not tracked.
+    pub async fn subscriptions_acked(&self) {
+        let acked = self
+            .subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .acked_notify();
+        loop {
+            let notified = acked.notified();
+            tokio::pin!(notified);
+            // Register for the notification before checking, so no ack is missed
+            notified.as_mut().enable();
+            if self
+                .subscriptions
+                .lock()
+                .expect("Subscriptions lock poisoned")
+                .all_acked()
+            {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Resends the subscribe messages not acked within the ack timeout, in the order they
+    /// were subscribed, paced by the `rate_limiter`.
+    async fn resend_unacked_subscriptions(
+        writer: &SharedMessageWriter,
+        subscriptions: &SharedSubscriptions,
+        rate_limiter: &RateLimiter<String, MonotonicClock>,
+        frame_redactor: Option<FrameRedactor>,
+    ) {
+        let messages = subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .expired_messages(Instant::now());
+        if messages.is_empty() {
+            return;
+        }
+
+        tracing::warn!("Resending {} unacked subscriptions", messages.len());
+        for (message, keys) in messages {
+            Self::await_rate_limit(rate_limiter, keys).await;
+            let message = Message::Text(message);
+            trace_frame("Sent", &message, frame_redactor);
+            if let Err(e) = writer.lock().await.send(message).await {
+                tracing::error!("Error resending subscription: {e}");
+            }
+        }
+    }
+
+    /// Resends the tracked subscribe messages on a new connection, paced by the
+    /// `rate_limiter`.
+    async fn replay_subscriptions(
+        writer: &SharedMessageWriter,
+        subscriptions: &SharedSubscriptions,
+        rate_limiter: &RateLimiter<String, MonotonicClock>,
+        frame_redactor: Option<FrameRedactor>,
+    ) {
+        let messages = subscriptions
+            .lock()
+            .expect("Subscriptions lock poisoned")
+            .messages();
+        if messages.is_empty() {
+            return;
+        }
+
+        tracing::debug!("Replaying {} subscriptions", messages.len());
+        for (message, keys) in messages {
+            Self::await_rate_limit(rate_limiter, keys).await;
+            let message = Message::Text(message);
+            trace_frame("Sent", &message, frame_redactor);
+            if let Err(e) = writer.lock().await.send(message).await {
+                tracing::error!("Error replaying subscription: {e}");
+            }
+        }
+    }
+
+    /// Sends the `message` through the `send_queue` if the client has one, otherwise writes
+    /// it directly, tracing it through the `frame_redactor` if given.
+    pub(crate) async fn send_message(
+        writer: &SharedMessageWriter,
+        send_queue: Option<&SendQueue>,
+        message: Message,
+        frame_redactor: Option<FrameRedactor>,
+    ) -> Result<(), Error> {
+        trace_frame("Sent", &message, frame_redactor);
+        match send_queue {
+            Some(send_queue) => send_queue.push(message).await,
+            None => writer.lock().await.send(message).await,
+        }
+    }
+
+    /// Keep writing queued messages to the connection.
+    fn spawn_send_task(
+        send_queue: Arc<SendQueue>,
+        writer: SharedMessageWriter,
+    ) -> task::JoinHandle<()> {
+        tracing::debug!("Started task 'send'");
+        task::spawn(async move {
+            loop {
+                let message = send_queue.pop().await;
+                let mut guard = writer.lock().await;
+                if let Err(e) = guard.send(message).await {
+                    tracing::error!("Error sending queued message: {e}");
+                }
+            }
+        })
+    }
+
+    /// Waits until the rate limiting quota of every key in `keys` allows sending a message.
+    pub(crate) async fn await_rate_limit(
+        rate_limiter: &RateLimiter<String, MonotonicClock>,
+        keys: Option<Vec<String>>,
+    ) {
+        let keys = keys.unwrap_or_else(|| vec![DEFAULT_RATE_LIMIT_KEY.to_string()]);
+        for key in &keys {
+            rate_limiter.until_key_ready(key).await;
+        }
     }
 
     pub async fn send_close_message(&self) {
@@ -405,10 +2004,16 @@ impl WebSocketClient {
     fn spawn_controller_task(
         mut inner: WebSocketClientInner,
         disconnect_mode: Arc<AtomicBool>,
+        drain_on_close: Arc<AtomicBool>,
+        close_frame: SharedCloseFrame,
+        rate_limiter: Arc<RateLimiter<String, MonotonicClock>>,
+        subscriptions: SharedSubscriptions,
         post_reconnection: Option<PyObject>,
         post_disconnection: Option<PyObject>,
     ) -> task::JoinHandle<()> {
         task::spawn(async move {
+            let mut backoff = inner.config.reconnect_backoff();
+
             loop {
                 sleep(Duration::from_millis(100)).await;
 
@@ -418,6 +2023,14 @@ impl WebSocketClient {
                     (false, false) => match inner.reconnect().await {
                         Ok(()) => {
                             tracing::debug!("Reconnected successfully");
+                            backoff.reset();
+                            Self::replay_subscriptions(
+                                &inner.writer,
+                                &subscriptions,
+                                &rate_limiter,
+                                inner.config.frame_redactor,
+                            )
+                            .await;
                             if let Some(ref handler) = post_reconnection {
                                 Python::with_gil(|py| match handler.call0(py) {
                                     Ok(_) => tracing::debug!("Called `post_reconnection` handler"),
@@ -429,14 +2042,34 @@ impl WebSocketClient {
                                 });
                             }
                         }
-                        Err(e) => {
-                            tracing::error!("Reconnect failed {e}");
+                        Err(e) if !is_retryable(&e) => {
+                            tracing::error!("Reconnect failed with non-retryable error {e}");
                             break;
                         }
+                        Err(e) => match backoff.next_duration() {
+                            Some(delay) => {
+                                tracing::warn!(
+                                    "Reconnect attempt {} failed {e}, retrying in {delay:?}",
+                                    backoff.attempts()
+                                );
+                                sleep(delay).await;
+                            }
+                            None => {
+                                tracing::error!("Reconnect failed {e}");
+                                break;
+                            }
+                        },
                     },
                     (true, true) => {
+                        if drain_on_close.load(Ordering::SeqCst) {
+                            inner.drain().await;
+                        }
                         tracing::debug!("Shutting down inner client");
-                        inner.shutdown().await;
+                        let frame = close_frame
+                            .lock()
+                            .expect("Close frame lock poisoned")
+                            .take();
+                        inner.shutdown(frame).await;
                         if let Some(ref handler) = post_disconnection {
                             Python::with_gil(|py| match handler.call0(py) {
                                 Ok(_) => tracing::debug!("Called `post_disconnection` handler"),
@@ -453,11 +2086,200 @@ impl WebSocketClient {
                     (true, false) => {
                         tracing::debug!("Inner client is disconnected");
                         tracing::debug!("Shutting down inner client to clean up running tasks");
-                        inner.shutdown().await
+                        inner.shutdown(None).await
+                    }
+                    (false, true) => {
+                        Self::resend_unacked_subscriptions(
+                            &inner.writer,
+                            &subscriptions,
+                            &rate_limiter,
+                            inner.config.frame_redactor,
+                        )
+                        .await;
                     }
-                    _ => (),
                 }
             }
         })
     }
 }
+
+impl WebSocketClient {
+    /// Connects to the `url` and returns separate read and write halves of the connection,
+    /// so that one task can read while another writes.
+    ///
+    /// The connection is wrapped with TLS by [`tcp_tls`] using the given `connector` for
+    /// `wss` URLs. Unlike [`Self::connect`], no tasks are spawned: the caller drives both
+    /// halves, and reconnecting, heartbeats and rate limiting are not applied.
+    ///
+    /// The halves share their closed state. Once the reader receives a `Close` frame, ends
+    /// or fails, sending on the writer fails with [`Error::AlreadyClosed`]. Once the writer
+    /// is closed or fails, the reader stream ends.
+    pub async fn connect_split(
+        url: &str,
+        headers: Vec<(String, String)>,
+        connector: Option<Connector>,
+    ) -> Result<(WebSocketReader, WebSocketWriter), Error> {
+        let (writer, reader) = WebSocketClientInner::connect_with_server(
+            url,
+            headers,
+            connector,
+            &SocketOptions::default(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?;
+        let state = Arc::new(SplitState::default());
+
+        Ok((
+            WebSocketReader {
+                reader,
+                state: state.clone(),
+            },
+            WebSocketWriter { writer, state },
+        ))
+    }
+}
+
+/// The closed state shared by the halves of a split connection.
+#[derive(Debug, Default)]
+struct SplitState {
+    closed: AtomicBool,
+    reader_waker: AtomicWaker,
+}
+
+impl SplitState {
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.reader_waker.wake();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+}
+
+/// The read half of a connection split by [`WebSocketClient::connect_split`], a stream of
+/// the received messages.
+pub struct WebSocketReader {
+    reader: MessageReader,
+    state: Arc<SplitState>,
+}
+
+impl Stream for WebSocketReader {
+    type Item = Result<Message, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Register before checking, so a close by the writer in between is not missed
+        self.state.reader_waker.register(cx.waker());
+        if self.state.is_closed() {
+            return Poll::Ready(None);
+        }
+
+        let next = self.reader.poll_next_unpin(cx);
+        if let Poll::Ready(None | Some(Err(_) | Ok(Message::Close(_)))) = &next {
+            self.state.closed.store(true, Ordering::SeqCst);
+        }
+        next
+    }
+}
+
+impl WebSocketReader {
+    /// Converts the reader into a stream of the payloads of the received binary frames,
+    /// skipping all other messages.
+    #[must_use]
+    pub fn into_binary(self) -> BinaryReader {
+        BinaryReader { reader: self }
+    }
+}
+
+/// A stream of the payloads of the binary frames received by a [`WebSocketReader`].
+///
+/// Payloads are yielded as [`Bytes`] without copying (see [`binary_payload`]), so parsers
+/// can slice them without reallocating.
+pub struct BinaryReader {
+    reader: WebSocketReader,
+}
+
+impl Stream for BinaryReader {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(self.reader.poll_next_unpin(cx)) {
+                Some(Ok(message)) => {
+                    if let Some(payload) = binary_payload(message) {
+                        return Poll::Ready(Some(Ok(payload)));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Returns the payload of a binary `message` as [`Bytes`], or `None` for any other message.
+///
+/// The buffer the frame was received into is moved into the [`Bytes`] rather than copied.
+#[must_use]
+pub fn binary_payload(message: Message) -> Option<Bytes> {
+    match message {
+        Message::Binary(data) => Some(Bytes::from(data)),
+        _ => None,
+    }
+}
+
+/// The write half of a connection split by [`WebSocketClient::connect_split`], a sink of
+/// the messages to send.
+pub struct WebSocketWriter {
+    writer: MessageWriter,
+    state: Arc<SplitState>,
+}
+
+impl WebSocketWriter {
+    /// Closes the connection on the given inner `result` failing.
+    fn check<T>(&self, result: Poll<Result<T, Error>>) -> Poll<Result<T, Error>> {
+        if let Poll::Ready(Err(_)) = &result {
+            self.state.close();
+        }
+        result
+    }
+}
+
+impl Sink<Message> for WebSocketWriter {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if self.state.is_closed() {
+            return Poll::Ready(Err(Error::AlreadyClosed));
+        }
+        let result = self.writer.poll_ready_unpin(cx);
+        self.check(result)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Error> {
+        if self.state.is_closed() {
+            return Err(Error::AlreadyClosed);
+        }
+        let result = self.writer.start_send_unpin(item);
+        if result.is_err() {
+            self.state.close();
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let result = self.writer.poll_flush_unpin(cx);
+        self.check(result)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let result = self.writer.poll_close_unpin(cx);
+        if result.is_ready() {
+            self.state.close();
+        }
+        result
+    }
+}
@@ -2636,8 +2636,28 @@ class WebSocketConfig:
         heartbeat: int | None = None,
         heartbeat_msg: str | None = None,
         ping_handler: Callable[..., Any] | None = None,
+        reconnect_delay_initial_ms: int | None = None,
+        reconnect_delay_max_ms: int | None = None,
+        reconnect_jitter_ms: int | None = None,
+        reconnect_max_attempts: int | None = None,
+        heartbeat_timeout: int | None = None,
+        ping_payload: bytes | None = None,
+        send_queue_capacity: int | None = None,
+        backpressure_policy: BackpressurePolicy | None = None,
+        max_message_size: int | None = None,
+        max_frame_size: int | None = None,
+        read_buffer_size: int | None = None,
+        write_buffer_size: int | None = None,
+        trace_frames: bool | None = None,
+        parked_capacity: int | None = None,
+        parked_policy: BackpressurePolicy | None = None,
     ) -> None: ...
 
+class BackpressurePolicy(Enum):
+    Block = "Block"
+    DropOldest = "DropOldest"
+    Error = "Error"
+
 class WebSocketClient:
     @classmethod
     def connect(
@@ -2646,12 +2666,32 @@ class WebSocketClient:
         post_connection: Callable[..., None] | None = None,
         post_reconnection: Callable[..., None] | None = None,
         post_disconnection: Callable[..., None] | None = None,
+        keyed_quotas: list[tuple[str, Quota]] = [],
+        default_quota: Quota | None = None,
     ) -> Awaitable[WebSocketClient]: ...
+    @classmethod
+    def warm_connect(
+        cls,
+        config: WebSocketConfig,
+        post_connection: Callable[..., None] | None = None,
+        post_reconnection: Callable[..., None] | None = None,
+        post_disconnection: Callable[..., None] | None = None,
+        keyed_quotas: list[tuple[str, Quota]] = [],
+        default_quota: Quota | None = None,
+    ) -> Awaitable[WebSocketClient]: ...
+    def activate(self) -> None: ...
+    def is_active(self) -> bool: ...
     def disconnect(self) -> Awaitable[None]: ...
+    def close(self, code: int, reason: str) -> Awaitable[None]: ...
+    def drain_and_close(self) -> Awaitable[None]: ...
     def is_alive(self) -> bool: ...
-    def send(self, data: bytes) -> Awaitable[None]: ...
-    def send_text(self, data: bytes) -> Awaitable[None]: ...
+    def send(self, data: bytes, keys: list[str] | None = None) -> Awaitable[None]: ...
+    def send_text(self, data: bytes, keys: list[str] | None = None) -> Awaitable[None]: ...
     def send_pong(self, data: bytes) -> Awaitable[None]: ...
+    def send_queue_depth(self) -> int: ...
+    def subscribe(self, topic: str, data: bytes, keys: list[str] | None = None) -> Awaitable[None]: ...
+    def unsubscribe(self, topic: str, data: bytes, keys: list[str] | None = None) -> Awaitable[None]: ...
+    def subscriptions(self) -> list[str]: ...
 
 class SocketClient:
     @classmethod
@@ -2674,6 +2714,8 @@ class SocketConfig:
         suffix: bytes,
         handler: Callable[..., Any],
         heartbeat: tuple[int, list[int]] | None = None,
+        length_prefix: int | None = None,
+        max_frame_len: int | None = None,
     ) -> None: ...
 
 ###################################################################################################
@@ -3842,6 +3884,7 @@ class DatabentoDataLoader:
         publishers_filepath: PathLike[str] | str,
     ) -> None: ...
     def load_publishers(self, filepath: PathLike[str] | str) -> None: ...
+    def load_glbx_exchange_map(self, filepath: PathLike[str] | str) -> None: ...
     def get_publishers(self) -> dict[int, DatabentoPublisher]: ...
     def get_dataset_for_venue(self, venue: Venue) -> str: ...
     def schema_for_file(self, filepath: str) -> str: ...
@@ -3870,6 +3913,7 @@ class DatabentoHistoricalClient:
     ) -> None: ...
     @property
     def key(self) -> str: ...
+    def load_glbx_exchange_map(self, filepath: PathLike[str] | str) -> None: ...
     async def get_dataset_range(self, dataset: str) -> dict[str, str]: ...
     async def get_range_instruments(
         self,